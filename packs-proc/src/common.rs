@@ -1,36 +1,198 @@
-use proc_macro2::Span;
-use syn::{Attribute, Type, Variant};
+use proc_macro2::{Ident, TokenStream};
+use syn::{Attribute, DataStruct, Error, Fields, Type, Variant};
+use quote::{format_ident, ToTokens};
 
 pub mod enums;
 
-pub fn gen_type_param() -> syn::Ident {
-    syn::Ident::new("T__PACKS_PROC_IMPL_PACKUNPACK", Span::call_site())
+/// The generic parameter `__<name>` of a generated item, e.g. `__W` for the writer of `encode`.
+/// The leading underscores keep it apart from the generics of the deriving type.
+pub fn gen_type_param(name: &str) -> syn::Ident {
+    format_ident!("__{}", name)
 }
 
-pub fn get_singleton_field_type(v: &Variant) -> &Type {
-    if v.fields.len() != 1 {
-        panic!("Variant '{}' has != 1 fields", v.ident)
-    } else {
-        &v.fields.iter().next().unwrap().ty
+pub fn get_singleton_field_type(v: &Variant) -> syn::Result<&Type> {
+    let mut fields = v.fields.iter();
+    match (fields.next(), fields.next()) {
+        (Some(field), None) => Ok(&field.ty),
+        _ => Err(Error::new_spanned(v, format!("variant '{}' has != 1 fields", v.ident))),
     }
 }
 
-pub fn get_tag_attr(attributes: &[Attribute]) -> Option<u8> {
-    get_name_value_attr("tag", attributes).map(lit_to_u8)
+/// A field of a structure-shaped variant: its member, its binding in patterns and its type.
+pub type VariantField<'a> = (syn::Member, Ident, &'a Type);
+
+/// The fields of a structure-shaped variant, i.e. one with named fields or with other than one
+/// unnamed field. A variant with a single unnamed field is `None`, its field is packed as the
+/// variant itself. Unit variants are rejected.
+pub fn get_struct_variant_fields(v: &Variant) -> syn::Result<Option<Vec<VariantField<'_>>>> {
+    match &v.fields {
+        Fields::Unit =>
+            Err(Error::new_spanned(&v.ident, "unit variants are not supported, as variants are packed as structures")),
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(None),
+        fields => {
+            if fields.len() > 15 {
+                return Err(Error::new_spanned(fields, "more than 15 fields are not allowed for a variant"));
+            }
+
+            Ok(Some(fields.iter().enumerate().map(|(i, f)| {
+                let member = match &f.ident {
+                    Some(ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(syn::Index::from(i)),
+                };
+                (member, format_ident!("field_{}", i), &f.ty)
+            }).collect()))
+        },
+    }
+}
+
+/// The value of a tag attribute: either a literal byte given as `#[tag = u8]`, or an expression of
+/// type `u8` given as `#[tag(expr)]`, like the path of a `const`, which is emitted as it is. Rust
+/// only allows literals after the `=` of an attribute.
+pub enum Tag {
+    Byte(u8),
+    Expr(syn::Expr),
+}
+
+impl Tag {
+    fn from_attr(attr: &Attribute) -> syn::Result<Self> {
+        if let Ok(syn::Meta::NameValue(nv)) = attr.parse_meta() {
+            return lit_to_u8(nv.lit).map(Tag::Byte)
+        }
+
+        match attr.parse_args() {
+            Ok(syn::Expr::Lit(syn::ExprLit { lit, .. })) => lit_to_u8(lit).map(Tag::Byte),
+            Ok(expr) => Ok(Tag::Expr(expr)),
+            Err(_) => Err(Error::new_spanned(attr, "expected #[tag = u8] or #[tag(expr)]")),
+        }
+    }
+}
+
+impl ToTokens for Tag {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Tag::Byte(byte) => byte.to_tokens(tokens),
+            Tag::Expr(expr) => expr.to_tokens(tokens),
+        }
+    }
+}
+
+pub fn get_tag_attrs(attributes: &[Attribute]) -> syn::Result<Vec<Tag>> {
+    attributes.iter()
+        .filter(|attr| attr.path.is_ident("tag"))
+        .map(Tag::from_attr)
+        .collect()
+}
+
+/// The (first) tag attribute, underlining `ident` if it is missing.
+pub fn require_tag_attr(ident: &Ident, attributes: &[Attribute]) -> syn::Result<Tag> {
+    get_tag_attrs(attributes)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::new_spanned(ident, "expected #[tag = u8]"))
+}
+
+pub fn get_rename_attr(attributes: &[Attribute]) -> syn::Result<Option<String>> {
+    get_name_value_attr("rename", attributes)?.map(lit_to_string).transpose()
+}
+
+pub fn get_fields_attr(attributes: &[Attribute]) -> syn::Result<Option<usize>> {
+    get_name_value_attr("fields", attributes)?.map(lit_to_usize).transpose()
+}
+
+pub fn get_pack_attr_param(attributes: &[Attribute]) -> syn::Result<Option<syn::Ident>> {
+    get_func_attr_param("pack", attributes)
+}
+
+pub fn get_unpack_attr_param(attributes: &[Attribute]) -> syn::Result<Option<syn::Ident>> {
+    get_func_attr_param("unpack", attributes)
+}
+
+/// The function given as `#[attr_name(func)]`, if the attribute is present.
+fn get_func_attr_param(attr_name: &str, attributes: &[Attribute]) -> syn::Result<Option<syn::Ident>> {
+    match get_attr(attr_name, attributes) {
+        Some(attr) => attr.parse_args().map(Some).map_err(|_|
+            Error::new_spanned(attr, format!("expected #[{}(function)]", attr_name))),
+        None => Ok(None),
+    }
+}
+
+/// Collects the parameters of all `#[packs(...)]` attributes.
+pub fn get_packs_params(attributes: &[Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+    let mut params = Vec::new();
+    for attr in attributes {
+        if attr.path.is_ident("packs") {
+            match attr.parse_meta() {
+                Ok(syn::Meta::List(list)) => params.extend(list.nested),
+                _ => return Err(Error::new_spanned(attr, "expected #[packs(...)]")),
+            }
+        }
+    }
+
+    Ok(params)
 }
 
-pub fn get_fields_attr(attributes: &[Attribute]) -> Option<usize> {
-    get_name_value_attr("fields", attributes).map(lit_to_usize)
+pub fn has_packs_flag(flag: &str, attributes: &[Attribute]) -> syn::Result<bool> {
+    Ok(get_packs_params(attributes)?.iter().any(|param|
+        matches!(param, syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident(flag))))
+}
+
+pub fn get_packs_name_value(param_name: &str, attributes: &[Attribute]) -> syn::Result<Option<syn::Lit>> {
+    for param in get_packs_params(attributes)? {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) = param {
+            if nv.path.is_ident(param_name) {
+                return Ok(Some(nv.lit))
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// The field with `#[packs(rest)]`, which takes all fields beyond the declared ones. It has to be
+/// the last field of a structure.
+pub fn rest_field(s: &DataStruct, as_list: bool) -> syn::Result<Option<&syn::Ident>> {
+    let mut rest = None;
+    for (i, f) in s.fields.iter().enumerate() {
+        if has_packs_flag("rest", &f.attrs)? {
+            rest = Some((i, f));
+            break
+        }
+    }
+    let (i, f) = match rest {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+
+    if as_list {
+        return Err(Error::new_spanned(f, "#[packs(rest)] is not supported with #[packs(as_list)]"));
+    }
+    if i + 1 != s.fields.len() {
+        return Err(Error::new_spanned(f, "only the last field can have #[packs(rest)]"));
+    }
+
+    Ok(f.ident.as_ref())
 }
 
-pub fn get_pack_attr_param(attributes: &[Attribute]) -> Option<syn::Ident> {
-    let attr = get_attr("pack", attributes)?;
-    attr.parse_args().ok()
+/// Whether the type is an `Option`, judged by the last segment of its path.
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) =>
+            p.path.segments.last().map(|s| s.ident == "Option").unwrap_or(false),
+        _ => false,
+    }
 }
 
-pub fn get_unpack_attr_param(attributes: &[Attribute]) -> Option<syn::Ident> {
-    let attr = get_attr("unpack", attributes)?;
-    attr.parse_args().ok()
+pub fn get_int_width_attr(attributes: &[Attribute]) -> syn::Result<Option<usize>> {
+    let lit = match get_packs_name_value("int_width", attributes)? {
+        Some(lit) => lit,
+        None => return Ok(None),
+    };
+    match lit_to_usize(lit.clone())? {
+        width @ (1 | 2 | 4 | 8) => Ok(Some(width)),
+        width => Err(Error::new_spanned(
+            lit,
+            format!("#[packs(int_width = {})] is not supported, expected 1, 2, 4 or 8", width))),
+    }
 }
 
 pub fn get_attr<'a>(attr_name: &str, attributes: &'a [Attribute]) -> Option<&'a Attribute> {
@@ -45,37 +207,40 @@ pub fn get_attr<'a>(attr_name: &str, attributes: &'a [Attribute]) -> Option<&'a
     None
 }
 
-pub fn get_name_value_attr(attr_name: &str, attributes: &[Attribute]) -> Option<syn::Lit> {
-    for attr in attributes {
-        if let syn::Meta::NameValue(nv) = attr.parse_meta().unwrap() {
-            if let Some(ident) = nv.path.get_ident() {
-                if ident == attr_name {
-                    return Some(nv.lit)
-                }
-            }
+pub fn get_name_value_attr(attr_name: &str, attributes: &[Attribute]) -> syn::Result<Option<syn::Lit>> {
+    Ok(get_name_value_attrs(attr_name, attributes)?.into_iter().next())
+}
+
+pub fn get_name_value_attrs(attr_name: &str, attributes: &[Attribute]) -> syn::Result<Vec<syn::Lit>> {
+    let mut lits = Vec::new();
+    for attr in attributes.iter().filter(|attr| attr.path.is_ident(attr_name)) {
+        match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(nv)) => lits.push(nv.lit),
+            _ => return Err(Error::new_spanned(attr, format!("expected #[{} = ...]", attr_name))),
         }
     }
 
-    None
+    Ok(lits)
 }
 
-fn lit_to_u8(lit: syn::Lit) -> u8 {
+fn lit_to_u8(lit: syn::Lit) -> syn::Result<u8> {
     match lit {
-        syn::Lit::Int(li) => {
-            li.base10_parse().unwrap()
-        }
-        syn::Lit::Byte(lb) => {
-            lb.value()
-        }
-        _ => panic!("Cannot parse into u8")
+        syn::Lit::Int(li) => li.base10_parse(),
+        syn::Lit::Byte(lb) => Ok(lb.value()),
+        lit => Err(Error::new_spanned(lit, "expected a u8")),
     }
 }
 
-fn lit_to_usize(lit: syn::Lit) -> usize {
+fn lit_to_usize(lit: syn::Lit) -> syn::Result<usize> {
     match lit {
-        syn::Lit::Int(li) => {
-            li.base10_parse().unwrap()
-        },
-        _ => panic!("Cannot parse into usize")
+        syn::Lit::Int(li) => li.base10_parse(),
+        lit => Err(Error::new_spanned(lit, "expected a usize")),
+    }
+}
+
+fn lit_to_string(lit: syn::Lit) -> syn::Result<String> {
+    match lit {
+        syn::Lit::Str(ls) => Ok(ls.value()),
+        lit => Err(Error::new_spanned(lit, "expected a string")),
     }
-}
\ No newline at end of file
+}
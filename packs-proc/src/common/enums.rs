@@ -1,5 +1,7 @@
-use syn::Attribute;
-use crate::common::get_tag_attr;
+use proc_macro2::TokenStream;
+use syn::{Error, Variant};
+use quote::quote;
+use crate::common::{get_tag_attrs, Tag};
 
 /// A thin wrapper around `Vec<u8>` which keeps track of the `tags` provided by the `tag` attribute
 /// in variants of an enum.
@@ -8,12 +10,13 @@ pub struct Tags {
 }
 
 impl Tags {
-    fn add_tag(&mut self, tag: u8) {
+    fn add_tag(&mut self, tag: u8, v: &Variant) -> syn::Result<()> {
         if self.tags.contains(&tag) {
-            panic!("Tag {:X} is not unique!", tag)
+            return Err(Error::new_spanned(&v.ident, format!("tag {:#04X} is not unique", tag)))
         }
 
         self.tags.push(tag);
+        Ok(())
     }
 
     pub fn with_capacity(cap: usize) -> Self {
@@ -24,16 +27,26 @@ impl Tags {
         }
     }
 
-    /// Adds a tag from attributes. Panics, if none is present.
-    pub fn add_from_attr(&mut self, attrs: &[Attribute]) {
-        if let Some(t) = get_tag_attr(attrs) {
-            self.add_tag(t)
-        } else {
-            panic!("No #[tag = u8] attribute found.")
+    /// Adds all tags from the attributes of the variant and returns the pattern matching them on
+    /// `tag`. A variant can have more than one tag, each of them is then accepted on decoding.
+    /// Fails, if none is present. Tags given by an expression are matched by a guard and cannot be
+    /// checked for uniqueness.
+    pub fn add_from_attr(&mut self, v: &Variant) -> syn::Result<TokenStream> {
+        let tags = get_tag_attrs(&v.attrs)?;
+        if tags.is_empty() {
+            return Err(Error::new_spanned(&v.ident, "expected #[tag = u8]"))
+        }
+
+        for t in &tags {
+            if let Tag::Byte(byte) = t {
+                self.add_tag(*byte, v)?
+            }
         }
-    }
 
-    pub fn last_tag(&self) -> u8 {
-        *self.tags.last().expect("No tag in Tags")
+        if tags.iter().all(|t| matches!(t, Tag::Byte(_))) {
+            Ok(quote! { #(#tags)|* })
+        } else {
+            Ok(quote! { _ if #(tag == #tags)||* })
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,72 @@
+use proc_macro2::{Ident, TokenStream};
+use syn::{Generics, DataStruct, Error};
+use crate::common::{gen_type_param, get_rename_attr, is_option};
+use quote::quote;
+
+/// Implements `from_dictionary` for a struct with named fields, taking each field out of a
+/// `Dictionary` by its name (or the one given by `#[rename = "..."]`), and an `Unpack` which
+/// decodes a dictionary and converts it using `from_dictionary`.
+pub fn impl_from_dict(ident: &Ident, generics: &Generics, s: &DataStruct) -> syn::Result<TokenStream> {
+    let ty_read = gen_type_param("R");
+    let ty_struct = gen_type_param("S");
+
+    let mut field_cases = proc_macro2::TokenStream::new();
+    let mut field_types = Vec::new();
+
+    for f in &s.fields {
+        let field_ident =
+            f.ident.as_ref().ok_or_else(|| Error::new_spanned(f, "expected a named field"))?;
+        let field_type = &f.ty;
+        let key = get_rename_attr(&f.attrs)?.unwrap_or_else(|| field_ident.to_string());
+        field_types.push(field_type);
+
+        let extract = quote! {
+            <#field_type as ::packs::Extract<#ty_struct>>::extract(v)
+                .ok_or_else(|| ::packs::DecodeError::UnexpectedPropertyType(String::from(#key)))?
+        };
+
+        let field =
+            if is_option(field_type) {
+                // missing optional fields are `None`:
+                quote! {
+                    #field_ident: match dict.extract_property(#key) {
+                        Some(v) => #extract,
+                        None => None,
+                    },
+                }
+            } else {
+                quote! {
+                    #field_ident: match dict.extract_property(#key) {
+                        Some(v) => #extract,
+                        None => return Err(::packs::DecodeError::MissingProperty(String::from(#key))),
+                    },
+                }
+            };
+
+        field_cases.extend(field);
+    }
+
+    Ok(quote! {
+        impl #generics #ident #generics {
+            /// Takes each field out of the dictionary by its name. Missing `Option` fields are
+            /// `None`; any other missing field is reported as `MissingProperty`.
+            pub fn from_dictionary<#ty_struct>(mut dict: ::packs::Dictionary<#ty_struct>) -> Result<Self, ::packs::DecodeError>
+                where #(#field_types: ::packs::Extract<#ty_struct>),* {
+                Ok(#ident {
+                    #field_cases
+                })
+            }
+        }
+
+        impl #generics ::packs::Unpack for #ident #generics {
+            fn decode_body<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read) -> Result<Self, ::packs::DecodeError> {
+                Self::decode_body_with(marker, reader, &::packs::DecodeOptions::default())
+            }
+
+            fn decode_body_with<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read, options: &::packs::DecodeOptions) -> Result<Self, ::packs::DecodeError> {
+                let dict = <::packs::Dictionary<::packs::GenericStruct>>::decode_body_with(marker, reader, options)?;
+                Self::from_dictionary(dict)
+            }
+        }
+    })
+}
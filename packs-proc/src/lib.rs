@@ -1,36 +1,69 @@
 use proc_macro::TokenStream;
-use syn::DeriveInput;
+use syn::{parse_macro_input, DeriveInput, Error};
 use pack::{impl_pack_sum, impl_pack_struct};
 use unpack::{impl_unpack_sum, impl_unpack_struct};
+use pack_string::impl_pack_string;
+use from_dict::impl_from_dict;
+use node_properties::impl_node_properties;
+use common::has_packs_flag;
 
 mod pack;
 mod unpack;
+mod pack_string;
+mod from_dict;
+mod node_properties;
 mod common;
 
-#[proc_macro_derive(Pack, attributes(tag, pack, fields))]
+#[proc_macro_derive(Pack, attributes(tag, pack, fields, packs))]
 pub fn pack_derive(input: TokenStream) -> TokenStream {
-    let ast: DeriveInput = syn::parse(input).unwrap();
+    let ast = parse_macro_input!(input as DeriveInput);
 
     let t =
         match &ast.data {
             syn::Data::Enum(e) => impl_pack_sum(&ast.ident, &ast.generics, e),
             syn::Data::Struct(s) => impl_pack_struct(&ast.ident, &ast.generics, &ast.attrs, s),
-            _ => panic!("Only enums and structs are supported for deriving Pack."),
+            _ => Err(Error::new_spanned(&ast.ident, "only enums and structs are supported for deriving Pack")),
         };
 
-    t.into()
+    t.unwrap_or_else(Error::into_compile_error).into()
 }
 
-#[proc_macro_derive(Unpack, attributes(tag, unpack, fields))]
+#[proc_macro_derive(Unpack, attributes(tag, unpack, fields, packs, rename))]
 pub fn unpack_derive(input: TokenStream) -> TokenStream {
-    let ast: DeriveInput = syn::parse(input).unwrap();
+    let ast = parse_macro_input!(input as DeriveInput);
 
     let t =
         match &ast.data {
+            syn::Data::Struct(s) => match has_packs_flag("from_dict", &ast.attrs) {
+                Ok(true) => impl_from_dict(&ast.ident, &ast.generics, s),
+                Ok(false) => impl_unpack_struct(&ast.ident, &ast.generics, &ast.attrs, s),
+                Err(e) => Err(e),
+            },
             syn::Data::Enum(e) => impl_unpack_sum(&ast.ident, &ast.generics, e),
-            syn::Data::Struct(s) => impl_unpack_struct(&ast.ident, &ast.generics, &ast.attrs, s),
-            _ => panic!("Only enums and structs are supported for deriving Unpack."),
+            _ => Err(Error::new_spanned(&ast.ident, "only enums and structs are supported for deriving Unpack")),
         };
 
-    t.into()
+    t.unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(PackString, attributes(rename))]
+pub fn pack_string_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let t =
+        match &ast.data {
+            syn::Data::Enum(e) => impl_pack_string(&ast.ident, &ast.generics, e),
+            _ => Err(Error::new_spanned(&ast.ident, "only enums are supported for deriving PackString")),
+        };
+
+    t.unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Generates a typed view on a property `Dictionary`, e.g. of a `Node`, from a struct describing
+/// the expected properties. Each field becomes a getter and a `set_` setter; `Option` fields may be
+/// missing or `Null`.
+#[proc_macro]
+pub fn node_properties(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_node_properties(&ast).unwrap_or_else(Error::into_compile_error).into()
 }
@@ -0,0 +1,123 @@
+use proc_macro2::TokenStream;
+use syn::{DeriveInput, Data, Error, Fields, Type, GenericArgument, PathArguments};
+use crate::common::{gen_type_param, get_rename_attr};
+use quote::{quote, format_ident};
+
+/// Generates a struct wrapping a `Dictionary` with a typed getter and setter for each of the given
+/// fields. The fields themselves are not part of the generated struct; they only describe which
+/// properties are expected and of which type.
+pub fn impl_node_properties(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &ast.ident;
+    let vis = &ast.vis;
+    let attrs = &ast.attrs;
+    let ty_struct = gen_type_param("S");
+
+    let fields =
+        match &ast.data {
+            Data::Struct(s) => match &s.fields {
+                Fields::Named(named) => &named.named,
+                fields => return Err(Error::new_spanned(fields, "node_properties! expects a struct with named fields")),
+            },
+            _ => return Err(Error::new_spanned(ident, "node_properties! expects a struct")),
+        };
+
+    let mut accessors = TokenStream::new();
+    for f in fields {
+        let field_ident = f.ident.as_ref().ok_or_else(|| Error::new_spanned(f, "expected a named field"))?;
+        let field_vis = &f.vis;
+        let setter = format_ident!("set_{}", field_ident);
+        let key = get_rename_attr(&f.attrs)?.unwrap_or_else(|| field_ident.to_string());
+
+        let accessor =
+            match option_inner(&f.ty) {
+                Some(inner) => quote! {
+                    /// The property, or `None` if it is missing or `Null`.
+                    #field_vis fn #field_ident(&self) -> Result<Option<&#inner>, ::packs::DecodeError>
+                        where #inner: ::packs::ExtractRef<#ty_struct> {
+                        match self.properties.get_property(#key) {
+                            None | Some(::packs::Value::Null) => Ok(None),
+                            Some(v) =>
+                                <#inner as ::packs::ExtractRef<#ty_struct>>::extract_ref(v)
+                                    .map(Some)
+                                    .ok_or_else(|| ::packs::DecodeError::UnexpectedPropertyType(String::from(#key))),
+                        }
+                    }
+
+                    /// Sets the property, or removes it if `value` is `None`.
+                    #field_vis fn #setter(&mut self, value: Option<#inner>)
+                        where #inner: Into<::packs::Value<#ty_struct>> {
+                        match value {
+                            Some(v) => { self.properties.add_property(#key, v); },
+                            None => { self.properties.extract_property(#key); },
+                        }
+                    }
+                },
+                None => {
+                    let field_type = &f.ty;
+                    quote! {
+                        #field_vis fn #field_ident(&self) -> Result<&#field_type, ::packs::DecodeError>
+                            where #field_type: ::packs::ExtractRef<#ty_struct> {
+                            match self.properties.get_property(#key) {
+                                Some(v) =>
+                                    <#field_type as ::packs::ExtractRef<#ty_struct>>::extract_ref(v)
+                                        .ok_or_else(|| ::packs::DecodeError::UnexpectedPropertyType(String::from(#key))),
+                                None => Err(::packs::DecodeError::MissingProperty(String::from(#key))),
+                            }
+                        }
+
+                        #field_vis fn #setter(&mut self, value: #field_type)
+                            where #field_type: Into<::packs::Value<#ty_struct>> {
+                            self.properties.add_property(#key, value);
+                        }
+                    }
+                },
+            };
+
+        accessors.extend(accessor);
+    }
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis struct #ident<#ty_struct> {
+            pub properties: ::packs::Dictionary<#ty_struct>,
+        }
+
+        impl<#ty_struct> #ident<#ty_struct> {
+            pub fn new(properties: ::packs::Dictionary<#ty_struct>) -> Self {
+                #ident { properties }
+            }
+
+            pub fn into_properties(self) -> ::packs::Dictionary<#ty_struct> {
+                self.properties
+            }
+
+            #accessors
+        }
+
+        impl<#ty_struct> From<::packs::Dictionary<#ty_struct>> for #ident<#ty_struct> {
+            fn from(properties: ::packs::Dictionary<#ty_struct>) -> Self {
+                #ident { properties }
+            }
+        }
+    })
+}
+
+/// The `T` of an `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    if segment.ident != "Option" {
+        return None
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -1,79 +1,207 @@
 use proc_macro2::{Ident, TokenStream};
-use syn::{Generics, Attribute, DataStruct};
-use crate::common::{get_fields_attr, get_tag_attr, get_pack_attr_param, gen_type_param, get_singleton_field_type};
+use syn::{Generics, Attribute, DataStruct, Error};
+use crate::common::{get_fields_attr, require_tag_attr, get_pack_attr_param, gen_type_param, get_singleton_field_type, get_struct_variant_fields, get_int_width_attr, has_packs_flag, rest_field};
 use quote::quote;
 
-pub fn impl_pack_struct(ident: &Ident, generics: &Generics, attrs: &[Attribute], s: &DataStruct) -> TokenStream {
-    let tag = get_tag_attr(attrs).expect("Need #[tag = u8] attribute on struct.");
-    let ty_write = gen_type_param();
+pub fn impl_pack_struct(ident: &Ident, generics: &Generics, attrs: &[Attribute], s: &DataStruct) -> syn::Result<TokenStream> {
+    let as_list = has_packs_flag("as_list", attrs)?;
+    let ty_write = gen_type_param("W");
 
     let mut pack_cases = proc_macro2::TokenStream::new();
+    let mut size_hints = proc_macro2::TokenStream::new();
+    let mut encoded_lens = Some(proc_macro2::TokenStream::new());
     let mut fields = 0usize;
+    let rest = rest_field(s, as_list)?;
 
     for f in &s.fields {
         let field_ident =
-            f.ident.as_ref().expect("Expected identifier at field.");
+            f.ident.as_ref().ok_or_else(|| Error::new_spanned(f, "expected a named field"))?;
         let field_type = &f.ty;
+        if rest == Some(field_ident) || has_packs_flag("skip", &f.attrs)? {
+            // the #[packs(rest)] field is written after all others, #[packs(skip)] fields not at all:
+            continue
+        }
         // use the #[fields = usize] attribute if given:
-        fields += get_fields_attr(&f.attrs).unwrap_or(1);
+        fields += get_fields_attr(&f.attrs)?.unwrap_or(1);
 
-        let pack =
-            if let Some(ident) = get_pack_attr_param(&f.attrs) {
-                // with #[pack(func)] attribute:
-                quote! {
+        let (pack, size_hint, encoded_len) =
+            if let Some(ident) = get_pack_attr_param(&f.attrs)? {
+                // with #[pack(func)] attribute; the length is unknown then, it is estimated with
+                // zero and the default `encoded_len` is used, which counts the bytes:
+                (quote! {
                     written += #ident(&self.#field_ident, writer)?;
-                 }
+                 },
+                 quote! {},
+                 None)
+            } else if let Some(width) = get_int_width_attr(&f.attrs)? {
+                // with #[packs(int_width = usize)] attribute:
+                let len = width + 1;
+                (quote! {
+                    written += ::packs::ll::types::fixed::encode_int_with_width(i128::from(self.#field_ident), #width, &mut *writer)?;
+                },
+                 quote! { + #len },
+                 Some(quote! { + #len }))
             } else {
                 // without:
-                quote! {
-                    written += <#field_type as Pack>::encode(&self.#field_ident, writer)?;
-                }
+                (quote! {
+                    written += <#field_type as ::packs::Pack>::encode_with(&self.#field_ident, writer, options)?;
+                },
+                 quote! { + <#field_type as ::packs::Pack>::size_hint(&self.#field_ident) },
+                 Some(quote! { + <#field_type as ::packs::Pack>::encoded_len(&self.#field_ident) }))
             };
 
         pack_cases.extend(pack);
+        size_hints.extend(size_hint);
+        encoded_lens = encoded_lens.and_then(|mut lens| {
+            lens.extend(encoded_len?);
+            Some(lens)
+        });
     }
 
     if fields > 15 {
-        panic!("More then 15 fields are not allowed for a struct.");
+        return Err(Error::new_spanned(&s.fields, "more than 15 fields are not allowed for a struct"));
     }
 
-    quote! {
-        impl #generics Pack for #ident #generics {
-            fn encode<#ty_write: std::io::Write>(&self, writer: &mut #ty_write) -> Result<usize, EncodeError> {
+    // the fields of #[packs(rest)] count on top of the declared ones:
+    let (fields_count, rest_cases) =
+        match rest {
+            Some(rest) => {
+                size_hints.extend(quote! { + self.#rest.iter().map(::packs::Pack::size_hint).sum::<usize>() });
+                encoded_lens = encoded_lens.map(|mut lens| {
+                    lens.extend(quote! { + self.#rest.iter().map(::packs::Pack::encoded_len).sum::<usize>() });
+                    lens
+                });
+                (quote! {
+                    {
+                        let fields = #fields + self.#rest.len();
+                        if fields > 15 {
+                            return Err(::packs::EncodeError::TooManyStructFields(fields))
+                        }
+                        fields
+                    }
+                 },
+                 quote! {
+                    for field in &self.#rest {
+                        written += ::packs::Pack::encode_with(field, writer, options)?;
+                    }
+                 })
+            },
+            None => (quote! { #fields }, quote! {}),
+        };
+
+    // with #[packs(as_list)] the fields are written as a list, without any tag:
+    let (header, header_len) =
+        if as_list {
+            (quote! { ::packs::Marker::TinyList(#fields) }, 1usize)
+        } else {
+            let tag = require_tag_attr(ident, attrs)?;
+            (quote! { ::packs::Marker::Structure(#fields_count, #tag) }, 2usize)
+        };
+
+    let encoded_len = encoded_lens.map(|lens| quote! {
+        fn encoded_len(&self) -> usize {
+            #header_len #lens
+        }
+    });
+
+    Ok(quote! {
+        impl #generics ::packs::Pack for #ident #generics {
+            fn encode<#ty_write: ::std::io::Write>(&self, writer: &mut #ty_write) -> Result<usize, ::packs::EncodeError> {
+                self.encode_with(writer, &::packs::EncodeOptions::default())
+            }
+
+            fn encode_with<#ty_write: ::std::io::Write>(&self, writer: &mut #ty_write, options: &::packs::EncodeOptions) -> Result<usize, ::packs::EncodeError> {
                 let mut written =
-                    Marker::Structure(#fields, #tag).encode(writer)?;
+                    #header.encode(writer)?;
                 #pack_cases
+                #rest_cases
 
                 Ok(written)
             }
+
+            fn size_hint(&self) -> usize {
+                #header_len #size_hints
+            }
+
+            #encoded_len
         }
-    }
+    })
 }
 
-pub fn impl_pack_sum(ident: &Ident, generics: &Generics, ast: &syn::DataEnum) -> TokenStream {
+pub fn impl_pack_sum(ident: &Ident, generics: &Generics, ast: &syn::DataEnum) -> syn::Result<TokenStream> {
     let mut pack_cases = proc_macro2::TokenStream::new();
+    let mut size_hint_cases = proc_macro2::TokenStream::new();
+    let mut encoded_len_cases = proc_macro2::TokenStream::new();
 
-    let ty_param = gen_type_param();
+    let ty_param = gen_type_param("W");
 
     for v in ast.variants.iter() {
         let var_name = &v.ident;
-        let var_type = get_singleton_field_type(v);
+
+        // a variant with several or named fields is packed as a structure of these:
+        if let Some(fields) = get_struct_variant_fields(v)? {
+            let tag = require_tag_attr(var_name, &v.attrs)?;
+            let len = fields.len();
+            let members: Vec<_> = fields.iter().map(|(member, _, _)| member).collect();
+            let bindings: Vec<_> = fields.iter().map(|(_, binding, _)| binding).collect();
+            let types: Vec<_> = fields.iter().map(|(_, _, ty)| ty).collect();
+            let pattern = quote! { #ident::#var_name { #(#members: #bindings),* } };
+
+            pack_cases.extend(quote! {
+                #pattern => {
+                    let mut written = ::packs::Marker::Structure(#len, #tag).encode(writer)?;
+                    #(written += <#types as ::packs::Pack>::encode_with(#bindings, writer, options)?;)*
+                    Ok(written)
+                },
+            });
+            size_hint_cases.extend(quote! {
+                #pattern => 2 #(+ <#types as ::packs::Pack>::size_hint(#bindings))*,
+            });
+            encoded_len_cases.extend(quote! {
+                #pattern => 2 #(+ <#types as ::packs::Pack>::encoded_len(#bindings))*,
+            });
+            continue
+        }
+
+        let var_type = get_singleton_field_type(v)?;
 
         pack_cases.extend(quote! {
             #ident::#var_name(v) => {
-                <#var_type as Pack>::encode(v, writer)
+                <#var_type as ::packs::Pack>::encode_with(v, writer, options)
             },
         });
+        size_hint_cases.extend(quote! {
+            #ident::#var_name(v) => <#var_type as ::packs::Pack>::size_hint(v),
+        });
+        encoded_len_cases.extend(quote! {
+            #ident::#var_name(v) => <#var_type as ::packs::Pack>::encoded_len(v),
+        });
     }
 
 
-    quote! {
-        impl #generics Pack for #ident #generics  {
-            fn encode<#ty_param: std::io::Write>(&self, writer: &mut #ty_param) -> Result<usize, EncodeError> {
+    Ok(quote! {
+        impl #generics ::packs::Pack for #ident #generics  {
+            fn encode<#ty_param: ::std::io::Write>(&self, writer: &mut #ty_param) -> Result<usize, ::packs::EncodeError> {
+                self.encode_with(writer, &::packs::EncodeOptions::default())
+            }
+
+            fn encode_with<#ty_param: ::std::io::Write>(&self, writer: &mut #ty_param, options: &::packs::EncodeOptions) -> Result<usize, ::packs::EncodeError> {
                 match self {
                     #pack_cases
                 }
             }
+
+            fn size_hint(&self) -> usize {
+                match self {
+                    #size_hint_cases
+                }
+            }
+
+            fn encoded_len(&self) -> usize {
+                match self {
+                    #encoded_len_cases
+                }
+            }
         }
-    }
+    })
 }
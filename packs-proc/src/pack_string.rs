@@ -0,0 +1,74 @@
+use proc_macro2::{Ident, TokenStream};
+use syn::{Generics, DataEnum, Error, Fields};
+use crate::common::{gen_type_param, get_rename_attr};
+use quote::quote;
+
+/// Implements `Pack` and `Unpack` for a fieldless enum, encoding each variant as a PackStream
+/// string. The string is the variant's name or the one given by `#[rename = "..."]`.
+pub fn impl_pack_string(ident: &Ident, generics: &Generics, ast: &DataEnum) -> syn::Result<TokenStream> {
+    let mut pack_cases = proc_macro2::TokenStream::new();
+    let mut unpack_cases = proc_macro2::TokenStream::new();
+    let mut names: Vec<String> = Vec::with_capacity(ast.variants.len());
+
+    let ty_write = gen_type_param("W");
+    let ty_read = gen_type_param("R");
+
+    for v in ast.variants.iter() {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(Error::new_spanned(v, "PackString only supports fieldless enums"))
+        }
+
+        let var_name = &v.ident;
+        let name = get_rename_attr(&v.attrs)?.unwrap_or_else(|| var_name.to_string());
+        if names.contains(&name) {
+            return Err(Error::new_spanned(var_name, format!("string '{}' is not unique", name)))
+        }
+
+        pack_cases.extend(quote! {
+            #ident::#var_name => #name,
+        });
+
+        unpack_cases.extend(quote! {
+            #name => Ok(#ident::#var_name),
+        });
+
+        names.push(name);
+    }
+
+    // the name is encoded borrowed, without allocating a `String`:
+    let name = quote! {
+        ::std::borrow::Cow::<'static, str>::Borrowed(match self {
+            #pack_cases
+        })
+    };
+
+    Ok(quote! {
+        impl #generics ::packs::Pack for #ident #generics {
+            fn encode<#ty_write: ::std::io::Write>(&self, writer: &mut #ty_write) -> Result<usize, ::packs::EncodeError> {
+                ::packs::Pack::encode(&#name, writer)
+            }
+
+            fn size_hint(&self) -> usize {
+                ::packs::Pack::size_hint(&#name)
+            }
+
+            fn encoded_len(&self) -> usize {
+                ::packs::Pack::encoded_len(&#name)
+            }
+        }
+
+        impl #generics ::packs::Unpack for #ident #generics {
+            fn decode_body<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read) -> Result<Self, ::packs::DecodeError> {
+                Self::decode_body_with(marker, reader, &::packs::DecodeOptions::default())
+            }
+
+            fn decode_body_with<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read, options: &::packs::DecodeOptions) -> Result<Self, ::packs::DecodeError> {
+                let name = <String as ::packs::Unpack>::decode_body_with(marker, reader, options)?;
+                match name.as_str() {
+                    #unpack_cases
+                    _ => Err(::packs::DecodeError::UnknownVariant(name)),
+                }
+            }
+        }
+    })
+}
@@ -1,67 +1,144 @@
 use proc_macro2::{Ident, TokenStream};
-use syn::{Generics, Attribute, DataStruct};
+use syn::{Generics, Attribute, DataStruct, Error};
 use crate::common::enums::Tags;
-use crate::common::{get_fields_attr, get_unpack_attr_param, get_tag_attr, gen_type_param, get_singleton_field_type};
+use crate::common::{get_fields_attr, get_unpack_attr_param, require_tag_attr, gen_type_param, get_singleton_field_type, get_struct_variant_fields, has_packs_flag, is_option, rest_field};
 use quote::quote;
 
-pub fn impl_unpack_sum(ident: &Ident, generics: &Generics, ast: &syn::DataEnum) -> TokenStream {
+pub fn impl_unpack_sum(ident: &Ident, generics: &Generics, ast: &syn::DataEnum) -> syn::Result<TokenStream> {
     let mut unpack_cases = proc_macro2::TokenStream::new();
     let mut tags = Tags::with_capacity(ast.variants.len());
+    let mut has_struct_variants = false;
 
-    let ty_param = gen_type_param();
+    let ty_param = gen_type_param("R");
 
     for v in ast.variants.iter() {
-        tags.add_from_attr(&v.attrs);
-        let tag = tags.last_tag();
+        let var_tags = tags.add_from_attr(v)?;
 
         let var_name = &v.ident;
-        let var_type = get_singleton_field_type(v);
+
+        // a variant with several or named fields is read from a structure of these:
+        if let Some(fields) = get_struct_variant_fields(v)? {
+            let len = fields.len();
+            let members = fields.iter().map(|(member, _, _)| member);
+            let types = fields.iter().map(|(_, _, ty)| ty);
+            has_struct_variants = true;
+
+            unpack_cases.extend(quote! {
+                #var_tags => {
+                    if len != #len {
+                        return Err(::packs::DecodeError::UnexpectedNumberOfFields(#len, len))
+                    }
+                    let options = &options.descend()?;
+
+                    Ok(#ident::#var_name {
+                        #(#members: <#types as ::packs::Unpack>::decode_with(reader, options)?,)*
+                    })
+                },
+            });
+            continue
+        }
+
+        let var_type = get_singleton_field_type(v)?;
 
         unpack_cases.extend(quote! {
-            #tag => Ok(#ident::#var_name(<#var_type as Unpack>::decode_body(marker, reader)?)),
+            #var_tags => Ok(#ident::#var_name(<#var_type as ::packs::Unpack>::decode_body_with(marker, reader, options)?)),
         })
     }
 
+    // only structure-shaped variants check the number of fields themselves:
+    let len = if has_struct_variants { quote! { len } } else { quote! { _ } };
+
+    Ok(quote! {
+        impl #generics ::packs::Unpack for #ident #generics {
+            fn decode_body<#ty_param: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_param) -> Result<Self, ::packs::DecodeError> {
+                Self::decode_body_with(marker, reader, &::packs::DecodeOptions::default())
+            }
 
-    quote! {
-        impl #generics Unpack for #ident #generics {
-            fn decode_body<#ty_param: std::io::Read>(marker: Marker, reader: &mut #ty_param) -> Result<Self, DecodeError> {
+            fn decode_body_with<#ty_param: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_param, options: &::packs::DecodeOptions) -> Result<Self, ::packs::DecodeError> {
                 match marker {
-                    Marker::Structure(_, tag) => {
+                    ::packs::Marker::Structure(#len, tag) => {
                         match tag {
                             #unpack_cases
-                            _ => Err(DecodeError::UnexpectedTagByte(tag)),
+                            _ => Err(::packs::DecodeError::UnexpectedTagByte(tag)),
                         }
                     },
-                    _ => Err(DecodeError::UnexpectedMarker(marker)),
+                    _ => Err(::packs::DecodeError::UnexpectedMarker(marker)),
                 }
             }
         }
-    }
+    })
 }
 
-pub fn impl_unpack_struct(ident: &Ident, generics: &Generics, attrs: &[Attribute], s: &DataStruct) -> TokenStream {
-    let tag = get_tag_attr(attrs).expect("Need #[tag = u8] attribute on struct.");
-    let ty_read = gen_type_param();
+pub fn impl_unpack_struct(ident: &Ident, generics: &Generics, attrs: &[Attribute], s: &DataStruct) -> syn::Result<TokenStream> {
+    let as_list = has_packs_flag("as_list", attrs)?;
+    let ty_read = gen_type_param("R");
 
     let mut unpack_cases = proc_macro2::TokenStream::new();
     let mut struct_build = proc_macro2::TokenStream::new();
     let mut fields_len = 0;
+    // the number of fields which have to be present, i.e. without the trailing
+    // #[packs(default)] and #[packs(optional)] ones:
+    let mut min_fields_len = None;
+    // the numbers of fields at which a trailing #[packs(default)] or #[packs(optional)] entry
+    // starts, i.e. all accepted numbers of fields but the full one:
+    let mut entry_boundaries = Vec::new();
+    let rest = rest_field(s, as_list)?;
+    // whether any field is decoded with the options:
+    let mut uses_options = false;
 
     for f in &s.fields {
         let f_ty = &f.ty;
-        let f_ident = f.ident.as_ref().expect("Expected field ident");
-        fields_len += get_fields_attr(&f.attrs).unwrap_or(1);
+        let f_ident = f.ident.as_ref().ok_or_else(|| Error::new_spanned(f, "expected a named field"))?;
+        if has_packs_flag("skip", &f.attrs)? {
+            // #[packs(skip)] fields are not on the wire:
+            unpack_cases.extend(quote! { let #f_ident = Default::default(); });
+            struct_build.extend(quote! { #f_ident, });
+            continue
+        }
+        uses_options |= get_unpack_attr_param(&f.attrs)?.is_none();
+        if rest == Some(f_ident) {
+            // #[packs(rest)] takes all fields beyond the declared ones:
+            unpack_cases.extend(quote! {
+                let #f_ident =
+                    (#fields_len..len)
+                        .map(|_| ::packs::Unpack::decode_with(reader, options))
+                        .collect::<Result<#f_ty, ::packs::DecodeError>>()?;
+            });
+            struct_build.extend(quote! { #f_ident, });
+            continue
+        }
+        let is_optional = has_packs_flag("optional", &f.attrs)?;
+        if is_optional && !is_option(f_ty) {
+            return Err(Error::new_spanned(f_ty, "#[packs(optional)] needs a field of type Option<T>"));
+        }
+        // an optional field is a defaulted one, which is `None` then:
+        let is_default = is_optional || has_packs_flag("default", &f.attrs)?;
+        if is_default && min_fields_len.is_none() {
+            min_fields_len = Some(fields_len);
+        } else if !is_default && min_fields_len.is_some() {
+            return Err(Error::new_spanned(f, "only trailing fields can have #[packs(default)] or #[packs(optional)]"));
+        }
+        if is_default {
+            entry_boundaries.push(fields_len);
+        }
+        fields_len += get_fields_attr(&f.attrs)?.unwrap_or(1);
 
-        let unpack =
+        let decode =
             // use #[unpack(func)]:
-            if let Some(func) = get_unpack_attr_param(&f.attrs) {
+            if let Some(func) = get_unpack_attr_param(&f.attrs)? {
+                quote! { #func(reader)? }
+            } else {
+                quote! { <#f_ty as ::packs::Unpack>::decode_with(reader, options)? }
+            };
+
+        let unpack =
+            if is_default {
                 quote! {
-                    let #f_ident = #func(reader)?;
+                    let #f_ident = if len >= #fields_len { #decode } else { Default::default() };
                 }
             } else {
                 quote! {
-                    let #f_ident = <#f_ty as Unpack>::decode(reader)?;
+                    let #f_ident = #decode;
                 }
             };
 
@@ -75,31 +152,73 @@ pub fn impl_unpack_struct(ident: &Ident, generics: &Generics, attrs: &[Attribute
     }
 
     if fields_len > 15 {
-        panic!("More then 15 fields are not allowed for a struct.");
+        return Err(Error::new_spanned(&s.fields, "more than 15 fields are not allowed for a struct"));
     }
 
-    quote! {
-        impl #generics Unpack for #ident #generics {
-            fn decode_body<#ty_read: std::io::Read>(marker: Marker, reader: &mut #ty_read) -> Result<Self, DecodeError> {
-                match marker {
-                    Marker::Structure(u, tag) => {
-                        if #fields_len != u {
-                            return Err(DecodeError::UnexpectedNumberOfFields(#fields_len, u))
-                        }
+    // fields with #[unpack(func)] do not get the options:
+    let descend =
+        if uses_options {
+            quote! { let options = &options.descend()?; }
+        } else {
+            quote! { options.descend()?; }
+        };
+
+    // a number of fields ending within an entry of several fields would leave the rest of the
+    // entry unread, so only the boundaries between entries are accepted:
+    let check_len =
+        match (min_fields_len, rest) {
+            (_, Some(_)) if fields_len == 0 => quote! { false },
+            (_, Some(_)) => quote! { len < #fields_len && ![#(#entry_boundaries),*].contains(&len) },
+            (Some(_), None) => quote! { ![#(#entry_boundaries,)* #fields_len].contains(&len) },
+            (None, None) => quote! { #fields_len != len },
+        };
+
+    let check_header =
+        if as_list {
+            // with #[packs(as_list)] the fields are read from a list of any list marker:
+            quote! {
+                let len = ::packs::ll::types::lengths::read_list_size(marker, reader)?;
+                if #check_len {
+                    return Err(::packs::DecodeError::UnexpectedNumberOfElements(#fields_len, len))
+                }
+            }
+        } else {
+            let tag = require_tag_attr(ident, attrs)?;
+            quote! {
+                let len =
+                    match marker {
+                        ::packs::Marker::Structure(len, tag) => {
+                            if #check_len {
+                                return Err(::packs::DecodeError::UnexpectedNumberOfFields(#fields_len, len))
+                            }
+
+                            if #tag != tag {
+                                return Err(::packs::DecodeError::UnexpectedTagByte(tag))
+                            }
+
+                            len
+                        },
+                        _ => return Err(::packs::DecodeError::UnexpectedMarker(marker))
+                    };
+            }
+        };
 
-                        if #tag != tag {
-                            return Err(DecodeError::UnexpectedTagByte(tag))
-                        }
+    Ok(quote! {
+        impl #generics ::packs::Unpack for #ident #generics {
+            fn decode_body<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read) -> Result<Self, ::packs::DecodeError> {
+                Self::decode_body_with(marker, reader, &::packs::DecodeOptions::default())
+            }
 
-                        #unpack_cases
+            fn decode_body_with<#ty_read: ::std::io::Read>(marker: ::packs::Marker, reader: &mut #ty_read, options: &::packs::DecodeOptions) -> Result<Self, ::packs::DecodeError> {
+                #check_header
+                #descend
 
-                        Ok(#ident {
-                            #struct_build
-                        })
-                    },
-                    _ => Err(DecodeError::UnexpectedMarker(marker))
-                }
+                #unpack_cases
+
+                Ok(#ident {
+                    #struct_build
+                })
             }
         }
-    }
+    })
 }
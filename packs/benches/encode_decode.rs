@@ -0,0 +1,72 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use packs::*;
+use packs::ll::bounds::{is_in_i16_bound, is_in_i32_bound, is_in_i8_bound};
+use packs::ll::types::sized::write_body_by_iter;
+use packs::std_structs::{Node, Path, Relationship, StdStruct};
+
+fn path(len: i64) -> Path {
+    let nodes: Vec<Node> =
+        (0..=len).map(|id| {
+            let mut node = Node::new(id);
+            node.labels.insert(String::from("Person"));
+            node.properties.add_property("name", format!("Person {}", id));
+            node.properties.add_property("age", 20 + id);
+            node
+        }).collect();
+
+    let rels: Vec<Relationship> =
+        (0..len).map(|id| Relationship {
+            id,
+            start_node_id: id,
+            end_node_id: id + 1,
+            _type: String::from("KNOWS"),
+            properties: Dictionary::new(),
+        }).collect();
+
+    let ids = (1..=len).flat_map(|i| vec!(i, i)).collect();
+
+    Path { nodes, rels, ids }
+}
+
+fn integers() -> Vec<i64> {
+    (0..10_000).map(|i| i * i * 7919 - 5_000_000).collect()
+}
+
+fn big_string() -> String {
+    "Lorem ipsum dolor sit amet. ".repeat(4096)
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let path = Value::Structure(StdStruct::Path(path(20)));
+    let integers = integers();
+    let string = big_string();
+
+    c.bench_function("encode path", |b| b.iter(|| black_box(&path).to_vec().unwrap()));
+    c.bench_function("encode integer list", |b| b.iter(|| black_box(&integers).to_vec().unwrap()));
+    c.bench_function("encode big string", |b| b.iter(|| black_box(&string).to_vec().unwrap()));
+    c.bench_function("write_body_by_iter integers", |b| b.iter(|| {
+        let mut buffer = Vec::with_capacity(integers.len() * 9);
+        write_body_by_iter(&mut black_box(&integers).iter(), &mut buffer).unwrap()
+    }));
+    c.bench_function("integer bounds", |b| b.iter(|| {
+        black_box(&integers).iter()
+            .filter(|i| is_in_i8_bound(**i) || is_in_i16_bound(**i) || is_in_i32_bound(**i))
+            .count()
+    }));
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let path = Value::Structure(StdStruct::Path(path(20))).to_vec().unwrap();
+    let integers = integers().to_vec().unwrap();
+    let string = big_string().to_vec().unwrap();
+
+    c.bench_function("decode path", |b| b.iter(||
+        <Value<StdStruct>>::decode(&mut black_box(path.as_slice())).unwrap()));
+    c.bench_function("decode integer list", |b| b.iter(||
+        <Vec<i64>>::decode(&mut black_box(integers.as_slice())).unwrap()));
+    c.bench_function("decode big string", |b| b.iter(||
+        String::decode(&mut black_box(string.as_slice())).unwrap()));
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
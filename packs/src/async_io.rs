@@ -0,0 +1,243 @@
+//! # Overview
+//! Encoding and decoding over `tokio`'s `AsyncWrite` and `AsyncRead`, behind the `tokio` feature.
+//! Every `Pack` is an [`AsyncPack`](crate::async_io::AsyncPack) and every `Unpack` an
+//! [`AsyncUnpack`](crate::async_io::AsyncUnpack), including derived types; both buffer one whole
+//! value and leave the encoding and decoding to the synchronous implementations:
+//! ```
+//! use packs::*;
+//! use packs::async_io::{AsyncPack, AsyncUnpack};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut buffer = Vec::new();
+//! String::from("hello").encode_async(&mut buffer).await.unwrap();
+//! 42i64.encode_async(&mut buffer).await.unwrap();
+//!
+//! let mut reader = buffer.as_slice();
+//! assert_eq!(String::decode_async(&mut reader).await.unwrap(), "hello");
+//! assert_eq!(i64::decode_async(&mut reader).await.unwrap(), 42);
+//! # });
+//! ```
+use std::future::Future;
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{Pack, Unpack, DecodeError, EncodeError, DecodeOptions, EncodeOptions, Marker};
+use crate::ll::marker::MarkerHighNibble;
+use crate::ll::types::lengths::{body_len_of, BodyLen, marker_is_dictionary};
+
+/// `Pack` over an `AsyncWrite`. The value is encoded into a buffer first, which is then written as
+/// a whole.
+pub trait AsyncPack: Pack {
+    /// Encodes the value into `writer`, returning the number of bytes written.
+    fn encode_async<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> impl Future<Output = Result<usize, EncodeError>> + Send {
+        let mut buffer = Vec::with_capacity(self.size_hint());
+        let encoded = self.encode(&mut buffer).map(|_| buffer);
+        write_encoded(writer, encoded)
+    }
+
+    fn encode_async_with<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W, options: &EncodeOptions) -> impl Future<Output = Result<usize, EncodeError>> + Send {
+        let mut buffer = Vec::with_capacity(self.size_hint());
+        let encoded = self.encode_with(&mut buffer, options).map(|_| buffer);
+        write_encoded(writer, encoded)
+    }
+}
+
+async fn write_encoded<W: AsyncWrite + Unpin>(writer: &mut W, encoded: Result<Vec<u8>, EncodeError>) -> Result<usize, EncodeError> {
+    let buffer = encoded?;
+    writer.write_all(&buffer).await?;
+    Ok(buffer.len())
+}
+
+impl<P: Pack + ?Sized> AsyncPack for P {}
+
+/// `Unpack` over an `AsyncRead`. The bytes of exactly one value are read first, following the
+/// markers and sizes, which are then decoded as a whole.
+pub trait AsyncUnpack: Unpack {
+    /// Decodes the next value of `reader`. A reader which ends within the value is reported as
+    /// `ReadIOError`.
+    fn decode_async<R: AsyncRead + Unpin + Send>(reader: &mut R) -> impl Future<Output = Result<Self, DecodeError>> + Send {
+        Self::decode_async_with(reader, DecodeOptions::default())
+    }
+
+    fn decode_async_with<R: AsyncRead + Unpin + Send>(reader: &mut R, options: DecodeOptions) -> impl Future<Output = Result<Self, DecodeError>> + Send {
+        async move {
+            let bytes = read_value_bytes(reader, &options).await?;
+            Self::decode_with(&mut bytes.as_slice(), &options)
+        }
+    }
+}
+
+impl<U: Unpack> AsyncUnpack for U {}
+
+/// Reads the bytes of the next complete value of `reader`, without decoding it. The limits of
+/// `options` are checked on every marker, before its body is read, such that a hostile size cannot
+/// make this buffer more than the limits allow.
+pub async fn read_value_bytes<R: AsyncRead + Unpin>(reader: &mut R, options: &DecodeOptions) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = Vec::new();
+    // for each open container, the number of values still to read and the options for them:
+    let mut pending = vec!((1usize, *options));
+
+    while let Some((remaining, options)) = pending.last_mut() {
+        if *remaining == 0 {
+            pending.pop();
+            continue
+        }
+        *remaining -= 1;
+        let options = *options;
+        let start = bytes.len();
+
+        let first = reader.read_u8().await?;
+        bytes.push(first);
+        if MarkerHighNibble::Structure.is_contained_in(first) {
+            bytes.push(reader.read_u8().await?);
+        }
+        let marker = Marker::decode(&mut &bytes[start..])?;
+
+        // the size information following the marker:
+        let header_start = bytes.len();
+        read_exactly(reader, marker.byte_len() - (header_start - start), &mut bytes).await?;
+
+        let values = match body_len_of(marker, &mut &bytes[header_start..])? {
+            BodyLen::Fixed(n) => {
+                if marker_is_sized(marker) {
+                    options.check_string_len(n)?;
+                }
+                read_exactly(reader, n, &mut bytes).await?;
+                continue
+            },
+            BodyLen::Count(n) if marker_is_dictionary(marker) => 2 * options.check_collection_len(n)?,
+            BodyLen::Count(n) => options.check_collection_len(n)?,
+            BodyLen::Nested(n, _) => n,
+        };
+        pending.push((values, options.descend()?));
+    }
+
+    Ok(bytes)
+}
+
+/// Whether the marker is one of a string or byte array, whose body has a declared size.
+fn marker_is_sized(marker: Marker) -> bool {
+    matches!(marker,
+        Marker::TinyString(_) | Marker::String8 | Marker::String16 | Marker::String32
+        | Marker::Bytes8 | Marker::Bytes16 | Marker::Bytes32)
+}
+
+/// Appends exactly `n` bytes of `reader` to `bytes`, without allocating them up front.
+async fn read_exactly<R: AsyncRead + Unpin>(reader: &mut R, n: usize, bytes: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let read = reader.take(n as u64).read_to_end(bytes).await?;
+    if read < n {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use crate::{Pack, Unpack, Value, GenericStruct, Dictionary, DecodeError};
+    use crate::async_io::{AsyncPack, AsyncUnpack};
+
+    /// Hands out one byte per read.
+    struct ByteByByte<'a>(&'a [u8]);
+
+    impl<'a> AsyncRead for ByteByByte<'a> {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            if let Some((first, rest)) = self.0.split_first() {
+                buf.put_slice(&[*first]);
+                self.0 = rest;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_base_types() {
+        let mut buffer = Vec::new();
+        for i in &[0i64, -16, 1000, i64::MIN, i64::MAX] {
+            i.encode_async(&mut buffer).await.unwrap();
+        }
+        "x".repeat(300).encode_async(&mut buffer).await.unwrap();
+        Some(1.5f64).encode_async(&mut buffer).await.unwrap();
+
+        let mut reader = ByteByByte(&buffer);
+        for i in &[0i64, -16, 1000, i64::MIN, i64::MAX] {
+            assert_eq!(i64::decode_async(&mut reader).await.unwrap(), *i);
+        }
+        assert_eq!(String::decode_async(&mut reader).await.unwrap(), "x".repeat(300));
+        assert_eq!(<Option<f64>>::decode_async(&mut reader).await.unwrap(), Some(1.5));
+        assert!(reader.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn round_trip_nested_value() {
+        let mut dict = Dictionary::new();
+        dict.add_property("list", vec!(Value::Integer(1), Value::Null, Value::from("a")));
+        let value : Value<GenericStruct> = Value::Structure(GenericStruct {
+            tag_byte: 0x01,
+            fields: vec!(Value::Dictionary(dict), Value::List(vec!())),
+        });
+        let bytes = value.to_vec().unwrap();
+
+        let decoded = <Value<GenericStruct>>::decode_async(&mut ByteByByte(&bytes)).await.unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[tokio::test]
+    async fn truncated_input() {
+        let bytes = String::from("hello").to_vec().unwrap();
+        let err = String::decode_async(&mut ByteByByte(&bytes[..3])).await.unwrap_err();
+        assert!(err.is_io());
+
+        let err = <Value<GenericStruct>>::decode_async(&mut ByteByByte(&[0x92, 0x01])).await.unwrap_err();
+        assert!(err.is_io());
+    }
+
+    #[tokio::test]
+    async fn limits_are_checked_before_buffering() {
+        use crate::DecodeOptions;
+        use crate::async_io::read_value_bytes;
+
+        let options = DecodeOptions { max_string_len: Some(10), ..DecodeOptions::default() };
+        // a String32 declaring 2 GiB, which are never read:
+        let err = String::decode_async_with(&mut ByteByByte(&[0xD2, 0x7F, 0xFF, 0xFF, 0xFF]), options).await.unwrap_err();
+        assert!(matches!(err, DecodeError::SizeLimitExceeded(0x7FFFFFFF, 10)));
+
+        let options = DecodeOptions { max_collection_len: Some(2), ..DecodeOptions::default() };
+        let err = read_value_bytes(&mut ByteByByte(&[0x91, 0x93, 0x01, 0x02, 0x03]), &options).await.unwrap_err();
+        assert!(matches!(err, DecodeError::SizeLimitExceeded(3, 2)));
+
+        let options = DecodeOptions { max_depth: Some(2), ..DecodeOptions::default() };
+        let bytes = [0x91, 0x90];
+        assert_eq!(read_value_bytes(&mut ByteByByte(&bytes), &options).await.unwrap(), bytes);
+        let bytes = [0x91, 0x91, 0x90];
+        let err = read_value_bytes(&mut ByteByByte(&bytes), &options).await.unwrap_err();
+        assert!(matches!(err, DecodeError::DepthLimitExceeded));
+        // as when decoding from a slice:
+        assert!(matches!(
+            <Value<GenericStruct>>::decode_with(&mut bytes.as_ref(), &options),
+            Err(DecodeError::DepthLimitExceeded)));
+    }
+
+    #[cfg(feature = "std_structs")]
+    #[tokio::test]
+    async fn round_trip_node() {
+        use std::collections::HashSet;
+        use crate::std_structs::{Node, StdStructPrimitive};
+
+        let mut properties = Dictionary::<StdStructPrimitive>::new();
+        properties.add_property("name", "Ada");
+        let mut labels = HashSet::new();
+        labels.insert(String::from("Person"));
+        let node = Node { id: 7, labels, properties };
+
+        let mut buffer = Vec::new();
+        let written = node.encode_async(&mut buffer).await.unwrap();
+        assert_eq!(written, buffer.len());
+
+        assert_eq!(Node::decode_async(&mut ByteByByte(&buffer)).await.unwrap(), node);
+    }
+}
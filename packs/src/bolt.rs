@@ -0,0 +1,163 @@
+//! # Overview
+//! Helpers for consumers of the bolt protocol. This library does not implement bolt itself; the
+//! helpers only know enough of it to separate messages, leaving their decoding to the caller.
+use std::io::Read;
+
+use crate::error::DecodeError;
+use crate::framing::ChunkedReader;
+use crate::ll::marker::Marker;
+
+/// The tag of a `RECORD` message.
+pub const RECORD: u8 = 0x71;
+/// The tag of a `SUCCESS` message.
+pub const SUCCESS: u8 = 0x70;
+/// The tag of an `IGNORED` message.
+pub const IGNORED: u8 = 0x7E;
+/// The tag of a `FAILURE` message.
+pub const FAILURE: u8 = 0x7F;
+
+/// An iterator over the raw `RECORD` messages of a result stream, each as the de-chunked bytes of
+/// the whole message. Messages are read lazily, one per call to `next`, so they can be handed to
+/// other threads and decoded there, e.g. as `Value<GenericStruct>`.
+///
+/// The iteration ends at the summary message, `SUCCESS`, `IGNORED` or `FAILURE`, which is then
+/// available through [`summary`](crate::bolt::RawRecordStream::summary). Any other message, and
+/// input ending before the summary, is reported as an error, after which the iteration ends too:
+/// ```
+/// use packs::*;
+/// use packs::bolt::{RawRecordStream, SUCCESS};
+///
+/// let bytes : &[u8] = &[
+///     0x00, 0x04, 0xB1, 0x71, 0x91, 0x01, 0x00, 0x00, // RECORD [1]
+///     0x00, 0x04, 0xB1, 0x71, 0x91, 0x02, 0x00, 0x00, // RECORD [2]
+///     0x00, 0x03, 0xB1, 0x70, 0xA0, 0x00, 0x00,       // SUCCESS {}
+/// ];
+/// let mut records = RawRecordStream::new(bytes);
+///
+/// let values =
+///     records.by_ref()
+///         .map(|record| <Value<GenericStruct>>::decode(&mut record.unwrap().as_slice()).unwrap())
+///         .collect::<Vec<_>>();
+///
+/// assert_eq!(values.len(), 2);
+/// assert_eq!(records.summary(), Some(&[0xB1, SUCCESS, 0xA0][..]));
+/// ```
+pub struct RawRecordStream<R> {
+    reader: ChunkedReader<R>,
+    summary: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl<R: Read> RawRecordStream<R> {
+    pub fn new(inner: R) -> Self {
+        RawRecordStream {
+            reader: ChunkedReader::new(inner),
+            summary: None,
+            done: false,
+        }
+    }
+
+    /// The bytes of the summary message, once the iteration has reached it.
+    pub fn summary(&self) -> Option<&[u8]> {
+        self.summary.as_deref()
+    }
+
+    /// Consumes the stream, returning the bytes of the summary message, if it has been reached.
+    pub fn into_summary(self) -> Option<Vec<u8>> {
+        self.summary
+    }
+
+    fn next_record(&mut self) -> Result<Option<Vec<u8>>, DecodeError> {
+        let message =
+            self.reader.read_message()?
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+
+        match Marker::decode(&mut message.as_slice())? {
+            Marker::Structure(_, RECORD) => Ok(Some(message)),
+            Marker::Structure(_, SUCCESS) | Marker::Structure(_, IGNORED) | Marker::Structure(_, FAILURE) => {
+                self.summary = Some(message);
+                Ok(None)
+            },
+            Marker::Structure(_, tag) => Err(DecodeError::UnexpectedTagByte(tag)),
+            marker => Err(DecodeError::UnexpectedMarker(marker)),
+        }
+    }
+}
+
+impl<R: Read> Iterator for RawRecordStream<R> {
+    type Item = Result<Vec<u8>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None
+        }
+
+        let next = self.next_record();
+        if !matches!(next, Ok(Some(_))) {
+            self.done = true;
+        }
+        next.transpose()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::bolt::{RawRecordStream, FAILURE};
+    use crate::{DecodeError, Pack, Value, GenericStruct};
+
+    /// Frames each message into chunks of at most `chunk_size` bytes.
+    fn chunked(messages: &[Vec<u8>], chunk_size: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for message in messages {
+            for chunk in message.chunks(chunk_size) {
+                bytes.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+                bytes.extend_from_slice(chunk);
+            }
+            bytes.extend_from_slice(&[0x00, 0x00]);
+        }
+        bytes
+    }
+
+    fn message(tag: u8, field: Value<GenericStruct>) -> Vec<u8> {
+        Value::Structure(GenericStruct { tag_byte: tag, fields: vec!(field) }).to_vec().unwrap()
+    }
+
+    #[test]
+    fn records_until_summary() {
+        let records: Vec<Vec<u8>> =
+            (0..10)
+                .map(|i| message(0x71, (0..i).map(|j| Value::String(format!("value {}", j))).collect()))
+                .collect();
+        let failure = message(FAILURE, Value::Null);
+
+        let mut messages = records.clone();
+        messages.push(failure.clone());
+        // a message after the summary is not read anymore:
+        messages.push(message(0x71, Value::Null));
+
+        let bytes = chunked(&messages, 7);
+        let mut stream = RawRecordStream::new(bytes.as_slice());
+        let read = stream.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(read, records);
+        assert_eq!(stream.into_summary(), Some(failure));
+    }
+
+    #[test]
+    fn unexpected_messages() {
+        let bytes = chunked(&[message(0x71, Value::Null), message(0x10, Value::Null)], 100);
+        let mut stream = RawRecordStream::new(bytes.as_slice());
+
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(stream.next(), Some(Err(DecodeError::UnexpectedTagByte(0x10)))));
+        assert!(stream.next().is_none());
+
+        // the input ends before the summary:
+        let bytes = chunked(&[message(0x71, Value::Null)], 100);
+        let mut stream = RawRecordStream::new(bytes.as_slice());
+
+        assert!(stream.next().unwrap().is_ok());
+        assert!(stream.next().unwrap().unwrap_err().is_io());
+        assert!(stream.summary().is_none());
+    }
+}
@@ -0,0 +1,147 @@
+//! # Overview
+//! A value for each of `true` and `false`, e.g. the two branches of a switch, as the named
+//! counterpart to the pair `(on_true, on_false)`. The pair is a tuple and encoded as a list of two
+//! items; a [`BoolPair`](crate::bool_pair::BoolPair) is encoded as a structure of two fields with
+//! the tag [`BoolPair::TAG`](crate::bool_pair::BoolPair::TAG). Both convert into each other.
+use std::io::{Read, Write};
+
+use crate::error::{DecodeError, EncodeError};
+use crate::ll::marker::Marker;
+use crate::options::{DecodeOptions, EncodeOptions};
+use crate::packable::{Pack, Unpack};
+
+/// A value for each of `true` and `false`, encoded as the structure `BoolPair { on_true,
+/// on_false }`, i.e. with two fields in this order:
+/// ```
+/// use packs::{Pack, Unpack};
+/// use packs::bool_pair::BoolPair;
+///
+/// let pair = BoolPair { on_true: Some(String::from("on")), on_false: None };
+///
+/// let mut buffer = Vec::new();
+/// pair.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xB2, 0x42, 0x82, 0x6F, 0x6E, 0xC0));
+/// assert_eq!(BoolPair::decode(&mut buffer.as_slice()).unwrap(), pair);
+///
+/// let (on_true, on_false) = pair.into();
+/// assert_eq!((on_true, on_false), (Some(String::from("on")), None));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BoolPair<V> {
+    pub on_true: V,
+    pub on_false: V,
+}
+
+impl<V> BoolPair<V> {
+    pub const TAG: u8 = 0x42;
+
+    pub fn get(&self, key: bool) -> &V {
+        if key { &self.on_true } else { &self.on_false }
+    }
+
+    pub fn get_mut(&mut self, key: bool) -> &mut V {
+        if key { &mut self.on_true } else { &mut self.on_false }
+    }
+}
+
+impl<V> From<(V, V)> for BoolPair<V> {
+    fn from((on_true, on_false): (V, V)) -> Self {
+        BoolPair { on_true, on_false }
+    }
+}
+
+impl<V> From<BoolPair<V>> for (V, V) {
+    fn from(pair: BoolPair<V>) -> Self {
+        (pair.on_true, pair.on_false)
+    }
+}
+
+impl<V: Pack> Pack for BoolPair<V> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        Ok(Marker::Structure(2, Self::TAG).encode(writer)?
+            + self.on_true.encode_with(writer, options)?
+            + self.on_false.encode_with(writer, options)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + self.on_true.size_hint() + self.on_false.size_hint()
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.on_true.encoded_len() + self.on_false.encoded_len()
+    }
+}
+
+impl<V: Unpack> Unpack for BoolPair<V> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        match marker {
+            Marker::Structure(u, tag) => {
+                if u != 2 {
+                    return Err(DecodeError::UnexpectedNumberOfFields(2, u))
+                }
+
+                if tag != Self::TAG {
+                    return Err(DecodeError::UnexpectedTagByte(tag))
+                }
+
+                let options = &options.descend()?;
+                let on_true = V::decode_with(reader, options)?;
+                let on_false = V::decode_with(reader, options)?;
+
+                Ok(BoolPair { on_true, on_false })
+            },
+            _ => Err(DecodeError::UnexpectedMarker(marker))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::bool_pair::BoolPair;
+    use crate::packable::test::{pack_unpack_test, encoded_len_test};
+    use crate::{Pack, Unpack, DecodeError};
+
+    #[test]
+    fn pack_unpack_bool_pair() {
+        let pairs = [
+            BoolPair { on_true: Some(1i64), on_false: None },
+            BoolPair { on_true: None, on_false: Some(-1000) },
+            BoolPair::default(),
+        ];
+        pack_unpack_test(&pairs);
+        encoded_len_test(&pairs);
+
+        let mut pair = BoolPair::from((1i64, 2i64));
+        *pair.get_mut(false) += 1;
+        assert_eq!((*pair.get(true), *pair.get(false)), (1, 3));
+        assert!(pair.size_hint() >= pair.to_vec().unwrap().len());
+    }
+
+    #[test]
+    fn unpack_malformed_bool_pair() {
+        let bytes: &[u8] = &[0xB3, BoolPair::<i64>::TAG, 0x01, 0x02, 0x03];
+        assert!(matches!(
+            <BoolPair<i64>>::decode(&mut &bytes[..]),
+            Err(DecodeError::UnexpectedNumberOfFields(2, 3))));
+
+        let bytes: &[u8] = &[0xB2, 0x43, 0x01, 0x02];
+        assert!(matches!(
+            <BoolPair<i64>>::decode(&mut &bytes[..]),
+            Err(DecodeError::UnexpectedTagByte(0x43))));
+
+        // the pair as a tuple is a list, not a `BoolPair`:
+        let bytes = (1i64, 2i64).to_vec().unwrap();
+        assert!(matches!(
+            <BoolPair<i64>>::decode(&mut bytes.as_slice()),
+            Err(DecodeError::UnexpectedMarker(_))));
+    }
+}
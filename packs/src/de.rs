@@ -0,0 +1,359 @@
+//! # Overview
+//! A `serde` deserializer from PackStream, behind the `serde` feature, the counterpart of
+//! [`ser`](crate::ser). The input is decoded as a `Value<GenericStruct>` first and then handed to
+//! the `Deserialize` implementation, following the same mapping as the serializer:
+//! ```
+//! use serde::{Serialize, Deserialize};
+//! use packs::{ser, de};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! enum Shape {
+//!     Point,
+//!     Circle { radius: f64 },
+//! }
+//!
+//! let shapes = vec!(Shape::Point, Shape::Circle { radius: 1.5 });
+//! let bytes = ser::to_vec(&shapes).unwrap();
+//!
+//! assert_eq!(de::from_slice::<Vec<Shape>>(&bytes).unwrap(), shapes);
+//! ```
+//! Structures are read by the order of their fields, hence a struct is also read from a list.
+//! Their tags are only checked for the names given by
+//! [`with_tag`](crate::de::Deserializer::with_tag); a struct can also be read from a dictionary,
+//! by the names of its fields.
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::Read;
+use std::rc::Rc;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::{Unpack, Value, GenericStruct, DecodeError};
+use crate::value::dictionary::PropertyMap;
+
+impl de::Error for DecodeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DecodeError::Custom(msg.to_string())
+    }
+}
+
+/// Deserializes a decoded `Value<GenericStruct>` into any `Deserialize` type.
+pub struct Deserializer {
+    value: Value<GenericStruct>,
+    tags: Rc<HashMap<&'static str, u8>>,
+}
+
+impl Deserializer {
+    pub fn from_value(value: Value<GenericStruct>) -> Self {
+        Deserializer { value, tags: Rc::new(HashMap::new()) }
+    }
+
+    /// Decodes the next value of the `reader`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        Ok(Self::from_value(Value::decode(reader)?))
+    }
+
+    /// Expects structs and variants called `name` to have the given `tag`, reporting any other
+    /// as `DecodeError::UnexpectedTagByte`.
+    pub fn with_tag(mut self, name: &'static str, tag: u8) -> Self {
+        Rc::make_mut(&mut self.tags).insert(name, tag);
+        self
+    }
+
+    fn deserialize_structure<'de, V: Visitor<'de>>(self, name: &str, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.value {
+            Value::Structure(s) => {
+                if let Some(tag) = self.tags.get(name) {
+                    if *tag != s.tag_byte {
+                        return Err(DecodeError::UnexpectedTagByte(s.tag_byte))
+                    }
+                }
+                visitor.visit_seq(SeqDeserializer::new(s.fields, self.tags))
+            },
+            value => de::Deserializer::deserialize_any(Deserializer { value, tags: self.tags }, visitor),
+        }
+    }
+}
+
+/// Decodes a value from the `reader` into `T`.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: &mut R) -> Result<T, DecodeError> {
+    T::deserialize(Deserializer::from_reader(reader)?)
+}
+
+/// Decodes a value from the `bytes` into `T`.
+pub fn from_slice<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<T, DecodeError> {
+    from_reader(&mut bytes)
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Boolean(b) => visitor.visit_bool(b),
+            Value::Integer(i) | Value::IntegerWide(i, _) => visitor.visit_i64(i),
+            Value::Float(x) => visitor.visit_f64(x),
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes.0),
+            Value::String(s) => visitor.visit_string(s),
+            Value::List(items) => visitor.visit_seq(SeqDeserializer::new(items, self.tags)),
+            Value::Dictionary(dict) => visitor.visit_map(MapDeserializer::new(dict.into_map(), self.tags)),
+            Value::Structure(s) => visitor.visit_seq(SeqDeserializer::new(s.fields, self.tags)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Structure(ref s) if s.fields.is_empty() => self.deserialize_structure(name, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_structure(name, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_structure(name, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Dictionary(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.into_properties().next().expect("Expected one entry.");
+                visitor.visit_enum(EnumDeserializer { variant, value: Deserializer { value, tags: self.tags } })
+            },
+            _ => Err(DecodeError::Custom(String::from("expected a string or a dictionary with a single entry for an enum"))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit seq tuple map identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DecodeError> for Deserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct SeqDeserializer {
+    items: std::vec::IntoIter<Value<GenericStruct>>,
+    tags: Rc<HashMap<&'static str, u8>>,
+}
+
+impl SeqDeserializer {
+    fn new(items: Vec<Value<GenericStruct>>, tags: Rc<HashMap<&'static str, u8>>) -> Self {
+        SeqDeserializer { items: items.into_iter(), tags }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        match self.items.next() {
+            Some(value) => seed.deserialize(Deserializer { value, tags: Rc::clone(&self.tags) }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: <PropertyMap<GenericStruct> as IntoIterator>::IntoIter,
+    value: Option<Value<GenericStruct>>,
+    tags: Rc<HashMap<&'static str, u8>>,
+}
+
+impl MapDeserializer {
+    fn new(entries: PropertyMap<GenericStruct>, tags: Rc<HashMap<&'static str, u8>>) -> Self {
+        MapDeserializer { entries: entries.into_iter(), value: None, tags }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let value = self.value.take().ok_or_else(|| DecodeError::Custom(String::from("value without a key")))?;
+        seed.deserialize(Deserializer { value, tags: Rc::clone(&self.tags) })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// An enum given as a dictionary `{variant: value}`.
+struct EnumDeserializer {
+    variant: String,
+    value: Deserializer,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = DecodeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(IntoDeserializer::<DecodeError>::into_deserializer(self.variant.as_str()))?;
+        Ok((variant, VariantDeserializer { name: self.variant, value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    name: String,
+    value: Deserializer,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        <()>::deserialize(self.value)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self.value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_any(self.value, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_structure(&self.name, visitor)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+    use serde::{Serialize, Deserialize};
+
+    use crate::{ser, de, Value, GenericStruct, DecodeError, EncodeError};
+    use crate::ser::Serializer;
+    use crate::de::Deserializer;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Address {
+        street: String,
+        number: Option<u16>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Role {
+        Admin,
+        Guest(String),
+        Member { since: i32, levels: (u8, u8) },
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: Option<u32>,
+        score: f32,
+        initial: char,
+        addresses: Vec<Address>,
+        roles: Vec<Role>,
+        meta: HashMap<String, i64>,
+        id: Id,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Id(i64);
+
+    fn person() -> Person {
+        let mut meta = HashMap::new();
+        meta.insert(String::from("visits"), 3);
+
+        Person {
+            name: String::from("Ada"),
+            age: None,
+            score: 0.5,
+            initial: 'A',
+            addresses: vec!(
+                Address { street: String::from("Main Street"), number: Some(7) },
+                Address { street: String::from("Side Street"), number: None }),
+            roles: vec!(Role::Admin, Role::Guest(String::from("x")), Role::Member { since: 1990, levels: (1, 2) }),
+            meta,
+            id: Id(42),
+        }
+    }
+
+    #[test]
+    fn round_trip_nested_structs_enums_and_options() {
+        let person = person();
+        let bytes = ser::to_vec(&person).unwrap();
+        assert_eq!(de::from_slice::<Person>(&bytes).unwrap(), person);
+
+        let bytes = Serializer::new().with_tag("Person", 0x50).with_tag("Address", 0x41).to_vec(&person).unwrap();
+        let decoded: Person =
+            Deserialize::deserialize(Deserializer::from_reader(&mut bytes.as_slice()).unwrap().with_tag("Person", 0x50)).unwrap();
+        assert_eq!(decoded, person);
+    }
+
+    #[test]
+    fn mapping_to_values() {
+        let ser = Serializer::new().with_tag("Address", 0x41).with_default_tag(0x01);
+        let address = Address { street: String::from("Main Street"), number: None };
+
+        assert_eq!(
+            ser.to_value(&address).unwrap(),
+            Value::Structure(GenericStruct {
+                tag_byte: 0x41,
+                fields: vec!(Value::String(String::from("Main Street")), Value::Null),
+            }));
+        assert_eq!(ser.to_value(&Role::Admin).unwrap(), Value::String(String::from("Admin")));
+        assert_eq!(ser.to_value(&Id(3)).unwrap(), Value::Integer(3));
+
+        let member = ser.to_value(&Role::Member { since: 1, levels: (2, 3) }).unwrap();
+        let member = member.get("Member").unwrap().as_struct().unwrap();
+        assert_eq!(member.tag_byte, 0x01);
+        assert_eq!(member.fields[1], Value::List(vec!(Value::Integer(2), Value::Integer(3))));
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = Serializer::new().with_tag("Address", 0x41).to_vec(&Address { street: String::new(), number: None }).unwrap();
+        let wrong_tag = Deserializer::from_reader(&mut bytes.as_slice()).unwrap().with_tag("Address", 0x42);
+        assert!(matches!(Address::deserialize(wrong_tag), Err(DecodeError::UnexpectedTagByte(0x41))));
+
+        assert!(matches!(de::from_slice::<Address>(&[0x01]), Err(DecodeError::Custom(_))));
+        assert!(matches!(ser::to_vec(&u64::MAX), Err(EncodeError::IntegerOutOfRange(_, 8))));
+
+        let mut by_int = HashMap::new();
+        by_int.insert(1, 1);
+        assert!(matches!(ser::to_vec(&by_int), Err(EncodeError::Custom(_))));
+    }
+}
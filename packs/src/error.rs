@@ -1,5 +1,7 @@
+use std::ops::Deref;
 use thiserror::Error;
 use crate::ll::marker::Marker;
+use crate::value::ValueKind;
 
 #[derive(Error, Debug)]
 pub enum DecodeError {
@@ -9,6 +11,8 @@ pub enum DecodeError {
     UnexpectedMarker(Marker),
     #[error("Unknown marker byte '{0}'")]
     UnknownMarkerByte(u8),
+    #[error("Marker byte '{0:#X}' is reserved")]
+    ReservedMarkerByte(u8),
     #[error("Cannot read size info as usize")]
     CannotReadSizeInfo,
     #[error("Unexpected tag byte '{0}'")]
@@ -17,6 +21,143 @@ pub enum DecodeError {
     UnexpectedNumberOfFields(usize, usize),
     #[error("Not allowed to decode NoStruct")]
     TryingToDecodeNoStruct,
+    #[error("Unknown variant '{0}'")]
+    UnknownVariant(String),
+    #[error("Integer {0} cannot be read as a boolean")]
+    IntegerNotBool(i64),
+    #[error("Expected {0} bytes but got {1}")]
+    UnexpectedNumberOfBytes(usize, usize),
+    #[error("Missing property '{0}'")]
+    MissingProperty(String),
+    #[error("Property '{0}' has an unexpected type")]
+    UnexpectedPropertyType(String),
+    #[error("Expected a list of {0} elements but got {1}")]
+    UnexpectedNumberOfElements(usize, usize),
+    #[error("Integer {0} is out of range for {1}")]
+    IntegerOutOfRange(i64, &'static str),
+    #[error("Values are nested deeper than allowed")]
+    DepthLimitExceeded,
+    #[error("Declared size {0} exceeds the limit of {1}")]
+    SizeLimitExceeded(usize, usize),
+    #[error("Input exceeds the limit of {0} bytes")]
+    ByteLimitExceeded(usize),
+    #[error("Invalid dictionary key '{0}'")]
+    InvalidKey(String),
+    #[error("Cannot parse dictionary key '{0}'")]
+    KeyParseError(String),
+    #[error("Invalid hex string '{0}'")]
+    InvalidHex(String),
+    #[error("Expected a single character but got '{0}'")]
+    ExpectedSingleChar(String),
+    #[error("Float {0} is not finite")]
+    NonFiniteFloat(f64),
+    #[error("Float {0:e} is subnormal")]
+    SubnormalFloat(f64),
+    /// An error reported by a `serde::Deserialize` implementation, see the `de` module.
+    #[error("{0}")]
+    Custom(String),
+    #[error("Cannot decode field '{0}'")]
+    Field(String, #[source] WrappedError),
+    #[error("Cannot decode the value at byte {0}")]
+    At(usize, #[source] WrappedError),
+    #[error("Cannot decode record {0}")]
+    Record(usize, #[source] WrappedError),
+    /// The tag of a structure marker is not in the buffer of the reader yet, see
+    /// [`peek_marker`](crate::ll::marker::peek_marker).
+    #[error("The marker is not completely buffered")]
+    MarkerNotBuffered,
+    #[error("String is not valid UTF-8: {0}")]
+    InvalidUtf8(#[source] std::str::Utf8Error),
+    /// Writing what has been decoded failed, see
+    /// [`transcode_to_ndjson`](crate::utils::transcode_to_ndjson).
+    #[error("IO error while writing: {0}")]
+    WriteIOError(#[source] std::io::Error),
+}
+
+/// A [`DecodeError`](crate::DecodeError) wrapped by one of the variants `Field`, `At` and `Record`,
+/// dereferencing to it. Since it is no error itself, the source of the wrapping variant is the
+/// wrapped `DecodeError` rather than a `Box` of it.
+#[derive(Debug)]
+pub struct WrappedError(Box<DecodeError>);
+
+impl Deref for WrappedError {
+    type Target = DecodeError;
+
+    fn deref(&self) -> &DecodeError {
+        &self.0
+    }
+}
+
+impl WrappedError {
+    /// The wrapped error, without the `Box`.
+    pub fn into_inner(self) -> DecodeError {
+        *self.0
+    }
+}
+
+impl DecodeError {
+    /// Whether the error has been caused by the underlying reader, e.g. a closed connection or an
+    /// unexpected end of input, or by a writer the decoded data is written to. Such an error says
+    /// nothing about the data itself and reading it again might succeed. This includes a marker
+    /// which cannot be peeked as it is not completely buffered yet.
+    /// ```
+    /// use packs::{Unpack, DecodeError};
+    ///
+    /// // an `Int16` missing its second byte:
+    /// let err = i64::decode(&mut [0xC9u8, 0x01].as_ref()).unwrap_err();
+    ///
+    /// assert!(err.is_io());
+    /// assert!(!err.is_protocol());
+    /// ```
+    pub fn is_io(&self) -> bool {
+        matches!(self.root(), DecodeError::ReadIOError(_) | DecodeError::WriteIOError(_) | DecodeError::MarkerNotBuffered)
+    }
+
+    /// Wraps the error as `Field`, adding the name of the field which was being decoded. The
+    /// wrapped error is the [`source`](std::error::Error::source) of the new one, such that the
+    /// whole path shows up in an error chain:
+    /// ```
+    /// use std::error::Error;
+    /// use packs::{Unpack, DecodeError};
+    ///
+    /// let err =
+    ///     String::decode(&mut [0x2A].as_ref())
+    ///         .map_err(|e| e.in_field("name").in_field("user"))
+    ///         .unwrap_err();
+    ///
+    /// assert_eq!(err.to_string(), "Cannot decode field 'user'");
+    /// assert_eq!(err.source().unwrap().to_string(), "Cannot decode field 'name'");
+    /// assert!(matches!(err.root(), DecodeError::UnexpectedMarker(_)));
+    /// ```
+    pub fn in_field(self, field: &str) -> DecodeError {
+        DecodeError::Field(String::from(field), WrappedError(Box::new(self)))
+    }
+
+    /// Wraps the error as `At`, adding the byte position of the value which was being decoded.
+    pub fn at(self, position: usize) -> DecodeError {
+        DecodeError::At(position, WrappedError(Box::new(self)))
+    }
+
+    /// Wraps the error as `Record`, adding the index of the record, i.e. of the top-level value in
+    /// a stream of values, which was being decoded.
+    pub fn in_record(self, index: usize) -> DecodeError {
+        DecodeError::Record(index, WrappedError(Box::new(self)))
+    }
+
+    /// The innermost error, i.e. the error wrapped by all `Field`, `At` and `Record` variants.
+    pub fn root(&self) -> &DecodeError {
+        match self {
+            DecodeError::Field(_, inner) | DecodeError::At(_, inner) | DecodeError::Record(_, inner) => inner.root(),
+            e => e,
+        }
+    }
+
+    /// Whether the read data does not denote a valid value of the expected type, e.g. an unknown
+    /// marker or a structure with the wrong tag. This is every error which is not an
+    /// [`is_io`](crate::DecodeError::is_io) error; reading the same data again fails the same way.
+    pub fn is_protocol(&self) -> bool {
+        !self.is_io()
+    }
 }
 
 #[derive(Error, Debug)]
@@ -24,6 +165,101 @@ pub enum EncodeError {
     #[error("IO error while writing: {0}")]
     WriteIOError(#[from] std::io::Error),
     #[error("Too many struct fields: {0}")]
-    TooManyStructFields(usize)
+    TooManyStructFields(usize),
+    #[error("Path {0:?} is not valid UTF-8")]
+    NonUtf8Path(std::ffi::OsString),
+    #[error("Encoded output exceeds the limit of {limit} bytes")]
+    OutputTooLarge {
+        limit: usize,
+        /// The number of bytes the whole encoding needs, if known.
+        needed: Option<usize>,
+    },
+    #[error("Integer {0} does not fit into {1} bytes")]
+    IntegerOutOfRange(i128, usize),
+    #[error("Invalid dictionary key of length {0}")]
+    InvalidKey(usize),
+    #[error("Dictionary key '{0}' is given more than once")]
+    DuplicateKey(String),
+    #[error("Pre-encoded element {0} is empty")]
+    EmptyElement(usize),
+    /// An error reported by a `serde::Serialize` implementation, see the `ser` module.
+    #[error("{0}")]
+    Custom(String),
+}
+
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Value is neither a list nor null")]
+pub struct NotAList;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Value is neither a dictionary nor null")]
+pub struct NotADictionary;
+
+/// An error of [`Value::set_path`](crate::value::Value::set_path).
+#[derive(Error, Debug, PartialEq)]
+pub enum KeyPathError {
+    #[error("Key path '{0}' has an empty segment")]
+    EmptySegment(String),
+    #[error("Value at '{0}' is a {1:?}, not a dictionary")]
+    NotADictionary(String, ValueKind),
+}
+
+/// An error of [`Value::into_json_string`](crate::value::Value::into_json_string), which reads
+/// structures back from their encoding.
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error("Cannot encode a structure: {0}")]
+    Encode(#[from] EncodeError),
+    #[error("Cannot read back an encoded structure: {0}")]
+    Decode(#[from] DecodeError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Cannot coerce {from:?} into {to:?}")]
+pub struct CoerceError {
+    pub from: ValueKind,
+    pub to: ValueKind,
 }
 
+#[cfg(test)]
+pub mod test {
+    use std::error::Error;
+    use std::io;
+    use crate::{DecodeError, Unpack};
+
+    #[test]
+    fn source_of_wrapping_variants() {
+        let wrapped = [
+            DecodeError::DepthLimitExceeded.in_field("a"),
+            DecodeError::DepthLimitExceeded.at(7),
+        ];
+
+        for err in &wrapped {
+            let source = err.source().expect("Expected a source.");
+            let inner = source.downcast_ref::<DecodeError>().expect("Expected a DecodeError.");
+            assert!(matches!(inner, DecodeError::DepthLimitExceeded));
+            assert_eq!(source.to_string(), inner.to_string());
+        }
+
+        assert!(DecodeError::DepthLimitExceeded.source().is_none());
+    }
+
+    #[test]
+    fn io_errors_are_found_through_wrapping_variants() {
+        let err = DecodeError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).at(3).in_field("x");
+
+        assert!(err.is_io());
+        assert!(matches!(err.root(), DecodeError::ReadIOError(_)));
+        assert!(DecodeError::CannotReadSizeInfo.in_field("x").is_protocol());
+    }
+
+    #[test]
+    fn invalid_utf8_is_no_io_error() {
+        let err = String::decode(&mut [0x82, 0x61, 0xFF].as_ref()).unwrap_err();
+
+        assert!(matches!(err, DecodeError::InvalidUtf8(_)));
+        assert!(!err.is_io());
+        assert!(err.is_protocol());
+    }
+}
@@ -0,0 +1,396 @@
+//! # Overview
+//! Helpers to frame encoded values for transports which need to know where a message ends.
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// A writer which collects everything written to it and, on [`finish`](LengthPrefixedWriter::finish),
+/// writes the number of collected bytes as a big-endian `u32` followed by the bytes themselves to
+/// the underlying writer:
+/// ```
+/// use packs::Pack;
+/// use packs::framing::LengthPrefixedWriter;
+///
+/// let mut writer = LengthPrefixedWriter::new(Vec::new());
+/// String::from("hello").encode(&mut writer).unwrap();
+/// let buffer = writer.finish().unwrap();
+///
+/// assert_eq!(buffer, vec!(0x00, 0x00, 0x00, 0x06, 0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F));
+/// ```
+pub struct LengthPrefixedWriter<W> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> LengthPrefixedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        LengthPrefixedWriter {
+            inner,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The number of bytes collected so far.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Writes the length prefix and the collected bytes to the underlying writer and returns it.
+    /// Fails with `InvalidInput` if more than `u32::MAX` bytes have been collected.
+    pub fn finish(mut self) -> io::Result<W> {
+        let len = u32::try_from(self.buffer.len()).map_err(|_|
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Frame of {} bytes exceeds the length prefix.", self.buffer.len())))?;
+
+        self.inner.write_all(&len.to_be_bytes())?;
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for LengthPrefixedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    /// Does nothing; the collected bytes are only written on `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The largest chunk of the chunked framing, as its size is given by a `u16`.
+pub const MAX_CHUNK_SIZE: usize = u16::MAX as usize;
+
+/// A writer for the chunked framing of the bolt protocol, the counterpart of
+/// [`ChunkedReader`](crate::framing::ChunkedReader). Everything written to it is buffered and
+/// written to the underlying writer in chunks of [`MAX_CHUNK_SIZE`](crate::framing::MAX_CHUNK_SIZE)
+/// bytes, each prefixed by its size as a big-endian `u16`.
+/// [`end_message`](crate::framing::ChunkedWriter::end_message) writes the rest as a last chunk,
+/// followed by the empty chunk `0x00 0x00` which ends the message:
+/// ```
+/// use packs::Pack;
+/// use packs::framing::ChunkedWriter;
+///
+/// let mut writer = ChunkedWriter::new(Vec::new());
+/// String::from("hello").encode(&mut writer).unwrap();
+/// writer.end_message().unwrap();
+///
+/// assert_eq!(writer.into_inner(), vec!(0x00, 0x06, 0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x00, 0x00));
+/// ```
+/// Ending a message without any bytes written writes only `0x00 0x00`, i.e. a NOOP.
+pub struct ChunkedWriter<W> {
+    inner: W,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_chunk_size(inner, MAX_CHUNK_SIZE)
+    }
+
+    /// A writer which splits messages into chunks of at most `chunk_size` bytes. Panics if
+    /// `chunk_size` is `0` or above [`MAX_CHUNK_SIZE`](crate::framing::MAX_CHUNK_SIZE).
+    pub fn with_chunk_size(inner: W, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0 && chunk_size <= MAX_CHUNK_SIZE, "Chunk size {} is not within 1 and {}.", chunk_size, MAX_CHUNK_SIZE);
+        ChunkedWriter {
+            inner,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    /// Writes the buffered bytes as a last chunk and ends the message with `0x00 0x00`. Writing
+    /// afterwards starts the next message.
+    pub fn end_message(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.write_chunk()?;
+        }
+        self.inner.write_all(&[0x00, 0x00])?;
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer; bytes of a message which has not been ended are dropped.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        self.inner.write_all(&(self.buffer.len() as u16).to_be_bytes())?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    /// Takes all of `buf`, writing every chunk it fills, since the encoders write each part of a
+    /// value with a single `write`.
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len();
+        while !buf.is_empty() {
+            let n = buf.len().min(self.chunk_size - self.buffer.len());
+            self.buffer.extend_from_slice(&buf[..n]);
+            buf = &buf[n..];
+            if self.buffer.len() == self.chunk_size {
+                self.write_chunk()?;
+            }
+        }
+        Ok(len)
+    }
+
+    /// Flushes the underlying writer; bytes which do not fill a chunk yet stay buffered.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader for the chunked framing of the bolt protocol, where a message is split into chunks,
+/// each prefixed by its size as a big-endian `u16`, and ends with an empty chunk `0x00 0x00`.
+///
+/// Reading yields the bytes of the current message without the chunk headers and reports the end
+/// of the message as end of input; the next read continues with the next message. Empty chunks
+/// before a message has started are keep-alive NOOPs and are skipped, so values decoded through
+/// the reader never see them. Within a message there are no NOOPs; an empty chunk there is the
+/// end of the message. NOOPs are a concern of the framing only: read from the raw stream, the
+/// bytes `0x00 0x00` would be decoded as two tiny integers, which is why there is no way to skip
+/// them while decoding values:
+/// ```
+/// use packs::Unpack;
+/// use packs::framing::ChunkedReader;
+///
+/// // a NOOP, then "hello" as a tiny string split into two chunks:
+/// let bytes : &[u8] = &[0x00, 0x00, 0x00, 0x02, 0x85, 0x68, 0x00, 0x04, 0x65, 0x6C, 0x6C, 0x6F, 0x00, 0x00];
+/// let mut reader = ChunkedReader::new(bytes);
+///
+/// assert_eq!(String::decode(&mut reader).unwrap(), "hello");
+/// ```
+/// Decoding a value stops right after it, not at the end of its message, so the empty chunk
+/// ending the message is still to be read. To decode the values of the next message, first call
+/// [`read_message`](crate::framing::ChunkedReader::read_message), which consumes the rest of the
+/// current message and is `None` if the values have been read completely; otherwise the end of
+/// the current message is reported as end of input:
+/// ```
+/// use packs::{Pack, Unpack};
+/// use packs::framing::{ChunkedReader, ChunkedWriter};
+///
+/// let mut writer = ChunkedWriter::new(Vec::new());
+/// 1i64.encode(&mut writer).unwrap();
+/// writer.end_message().unwrap();
+/// 2i64.encode(&mut writer).unwrap();
+/// writer.end_message().unwrap();
+/// let bytes = writer.into_inner();
+///
+/// let mut reader = ChunkedReader::new(bytes.as_slice());
+/// assert_eq!(i64::decode(&mut reader).unwrap(), 1);
+/// assert!(i64::decode(&mut reader).unwrap_err().is_io());
+///
+/// let mut reader = ChunkedReader::new(bytes.as_slice());
+/// assert_eq!(i64::decode(&mut reader).unwrap(), 1);
+/// assert_eq!(reader.read_message().unwrap(), None);
+/// assert_eq!(i64::decode(&mut reader).unwrap(), 2);
+/// ```
+pub struct ChunkedReader<R> {
+    inner: R,
+    chunk_remaining: usize,
+    in_message: bool,
+}
+
+impl<R: Read> ChunkedReader<R> {
+    pub fn new(inner: R) -> Self {
+        ChunkedReader {
+            inner,
+            chunk_remaining: 0,
+            in_message: false,
+        }
+    }
+
+    /// Reads the rest of the current message, or the whole next one if the last has been read to
+    /// its end. Returns `None` if there is nothing to read, i.e. if the underlying reader ends
+    /// before another message starts or if no bytes of the current message are left.
+    pub fn read_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut message = Vec::new();
+        self.read_to_end(&mut message)?;
+        if message.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(message))
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the size of the next chunk, or `None` if the underlying reader has ended right before.
+    fn read_chunk_header(&mut self) -> io::Result<Option<usize>> {
+        let mut header = [0; 2];
+        if self.inner.read(&mut header[..1])? == 0 {
+            return Ok(None)
+        }
+        self.inner.read_exact(&mut header[1..])?;
+        Ok(Some(u16::from_be_bytes(header) as usize))
+    }
+}
+
+impl<R: Read> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        while self.chunk_remaining == 0 {
+            match self.read_chunk_header()? {
+                None if self.in_message => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                None => return Ok(0),
+                Some(0) if self.in_message => {
+                    self.in_message = false;
+                    return Ok(0)
+                },
+                Some(0) => (),
+                Some(size) => {
+                    self.chunk_remaining = size;
+                    self.in_message = true;
+                },
+            }
+        }
+
+        let max = buf.len().min(self.chunk_remaining);
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof))
+        }
+        self.chunk_remaining -= n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::framing::{LengthPrefixedWriter, ChunkedReader, ChunkedWriter, MAX_CHUNK_SIZE};
+    use crate::{Pack, Unpack, Value, NoStruct, Bytes};
+    use std::io::{Read, Write};
+
+    #[test]
+    fn length_prefix_of_several_values() {
+        let mut writer = LengthPrefixedWriter::new(Vec::new());
+        assert!(writer.is_empty());
+
+        let list : Value<NoStruct> = (0..300).map(Value::Integer).collect();
+        let written = list.encode(&mut writer).unwrap() + 42i64.encode(&mut writer).unwrap();
+        assert_eq!(writer.len(), written);
+
+        let buffer = writer.finish().unwrap();
+        assert_eq!(&buffer[..4], &(written as u32).to_be_bytes());
+        assert_eq!(buffer.len(), 4 + written);
+        assert_eq!(buffer.last(), Some(&0x2A));
+    }
+
+    #[test]
+    fn chunked_reader_separates_messages() {
+        let bytes : &[u8] = &[
+            0x00, 0x03, 0x01, 0x02, 0x03, 0x00, 0x01, 0x04, 0x00, 0x00,
+            0x00, 0x00,
+            0x00, 0x01, 0x05, 0x00, 0x00,
+        ];
+        let mut reader = ChunkedReader::new(bytes);
+
+        assert_eq!(reader.read_message().unwrap(), Some(vec!(0x01, 0x02, 0x03, 0x04)));
+        assert_eq!(reader.read_message().unwrap(), Some(vec!(0x05)));
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+
+    #[test]
+    fn chunked_reader_decodes_messages_in_a_row() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        for s in &["first", "second"] {
+            String::from(*s).encode(&mut writer).unwrap();
+            writer.end_message().unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let mut reader = ChunkedReader::new(bytes.as_slice());
+        assert_eq!(String::decode(&mut reader).unwrap(), "first");
+        assert_eq!(reader.read_message().unwrap(), None);
+        assert_eq!(String::decode(&mut reader).unwrap(), "second");
+        assert_eq!(reader.read_message().unwrap(), None);
+        assert_eq!(reader.read_message().unwrap(), None);
+        assert!(reader.into_inner().is_empty());
+    }
+
+    #[test]
+    fn chunked_reader_truncated_message() {
+        // a chunk of three bytes with two of them, and a message without its end:
+        for bytes in [&[0x00, 0x03, 0x01, 0x02][..], &[0x00, 0x01, 0x01]].iter() {
+            let mut reader = ChunkedReader::new(*bytes);
+            let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn chunked_writer_splits_messages() {
+        let mut writer = ChunkedWriter::with_chunk_size(Vec::new(), 3);
+        writer.write_all(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07]).unwrap();
+        writer.end_message().unwrap();
+        // exactly one chunk, without an empty chunk before the end:
+        writer.write_all(&[0x08, 0x09, 0x0A]).unwrap();
+        writer.end_message().unwrap();
+
+        assert_eq!(
+            writer.into_inner(),
+            vec!(
+                0x00, 0x03, 0x01, 0x02, 0x03, 0x00, 0x03, 0x04, 0x05, 0x06, 0x00, 0x01, 0x07, 0x00, 0x00,
+                0x00, 0x03, 0x08, 0x09, 0x0A, 0x00, 0x00));
+    }
+
+    #[test]
+    fn chunked_round_trip_at_chunk_boundaries() {
+        // a byte array of len bytes is encoded with 3 more bytes, 5 beyond u16::MAX; together with
+        // the trailing integer this gives messages of exactly one and two chunks, and one byte more:
+        for len in &[MAX_CHUNK_SIZE - 4, MAX_CHUNK_SIZE - 3, 2 * MAX_CHUNK_SIZE - 6, 2 * MAX_CHUNK_SIZE - 5] {
+            let value : Value<NoStruct> = Value::Bytes(Bytes(vec![0xAB; *len]));
+
+            let mut writer = ChunkedWriter::new(Vec::new());
+            value.encode(&mut writer).unwrap();
+            42i64.encode(&mut writer).unwrap();
+            writer.end_message().unwrap();
+            let bytes = writer.into_inner();
+
+            let payload = value.encoded_len() + 1;
+            let chunks = payload.div_ceil(MAX_CHUNK_SIZE);
+            assert_eq!(bytes.len(), payload + 2 * chunks + 2, "framing of {} bytes", len);
+
+            let mut reader = ChunkedReader::new(bytes.as_slice());
+            assert_eq!(<Value<NoStruct>>::decode(&mut reader).unwrap(), value);
+            assert_eq!(i64::decode(&mut reader).unwrap(), 42);
+            assert_eq!(reader.read_message().unwrap(), None);
+        }
+    }
+
+    #[cfg(feature = "std_structs")]
+    #[test]
+    fn chunked_node() {
+        use crate::std_structs::Node;
+
+        let mut node = Node::new(1);
+        for i in 0..100 {
+            node.properties.add_property(&format!("property {}", i), "x".repeat(i));
+        }
+
+        let mut writer = ChunkedWriter::with_chunk_size(Vec::new(), 100);
+        node.encode(&mut writer).unwrap();
+        writer.end_message().unwrap();
+        let bytes = writer.into_inner();
+
+        let mut reader = ChunkedReader::new(bytes.as_slice());
+        assert_eq!(Node::decode(&mut reader).unwrap(), node);
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+}
@@ -146,24 +146,45 @@
 //!
 //! assert_eq!(Value::Structure(MyStruct::Person(person)), runtime_typed);
 //! ```
+// the derive macros refer to this crate as `::packs`, also for the structures derived in here:
+extern crate self as packs;
+
 mod value;
 mod structure;
 mod packable;
 mod error;
+mod options;
 pub mod ll;
 pub mod utils;
+pub mod transcode;
+pub mod framing;
+pub mod bolt;
+pub mod skip;
+pub mod maps;
+pub mod bool_pair;
+pub mod testing;
 
 #[cfg(feature = "std_structs")]
 pub mod std_structs;
 
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+
 #[cfg(feature = "derive")]
 pub use packs_proc::*;
 
 // Public API:
 pub use packable::{Pack, Unpack};
-pub use error::{EncodeError, DecodeError};
-pub use value::{Value, Extract, ExtractRef, ExtractMut, extract_list_ref, extract_list, extract_list_mut};
+pub use error::{EncodeError, DecodeError, WrappedError, NotAList, NotADictionary, CoerceError, KeyPathError, JsonError};
+pub use options::{DecodeOptions, EncodeOptions, PackStreamVersion, DEFAULT_MAX_DEPTH};
+pub use value::{Value, ValueKind, Extract, ExtractRef, ExtractMut, extract_list_ref, extract_list, extract_list_mut};
 pub use value::bytes::Bytes;
-pub use value::dictionary::Dictionary;
+pub use value::visitor::{Visitor, NodeCounter};
+pub use value::big_int::BigInt;
+pub use value::dictionary::{Dictionary, TypedView, DictPatch, PropertyMap};
 pub use ll::marker::Marker;
-pub use structure::{GenericStruct, NoStruct};
\ No newline at end of file
+pub use structure::{GenericStruct, FixedStruct, ExtStruct, NoStruct, DeferredStructWriter};
\ No newline at end of file
@@ -1,4 +1,8 @@
 pub mod bitops;
+#[cfg(feature = "bench")]
 pub mod bounds;
+#[cfg(not(feature = "bench"))]
+pub(crate) mod bounds;
 pub mod types;
-pub mod marker;
\ No newline at end of file
+pub mod marker;
+pub mod io;
\ No newline at end of file
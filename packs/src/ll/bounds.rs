@@ -10,14 +10,14 @@ pub fn is_in_minus_tiny_int_bound(i: i64) -> bool {
 }
 
 pub fn is_in_i8_bound(i: i64) -> bool {
-    i >= i8::min_value() as i64 && i <= i8::max_value() as i64
+    i >= i8::MIN as i64 && i <= i8::MAX as i64
 }
 
 pub fn is_in_i16_bound(i: i64) -> bool {
-    i >= i16::min_value() as i64 && i <= i16::max_value() as i64
+    i >= i16::MIN as i64 && i <= i16::MAX as i64
 }
 
 pub fn is_in_i32_bound(i: i64) -> bool {
-    i >= i32::min_value() as i64 && i <= i32::max_value() as i64
+    i >= i32::MIN as i64 && i <= i32::MAX as i64
 }
 
@@ -0,0 +1,392 @@
+//! Adapters around [`Read`](std::io::Read) and [`Write`](std::io::Write) which are used by the
+//! encoding and decoding functions but are useful on their own as well.
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "sha2")]
+use sha2::{Digest, Sha256};
+
+/// A reader over a byte slice which keeps track of how many bytes have been read so far. Unlike
+/// [`Cursor`](std::io::Cursor) the position is a `usize` and the not yet read part of the slice can
+/// be borrowed directly:
+/// ```
+/// use packs::Unpack;
+/// use packs::ll::io::SliceReader;
+///
+/// let mut reader = SliceReader::new(&[0x2A, 0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F]);
+/// let value = i64::decode(&mut reader).unwrap();
+///
+/// assert_eq!(value, 42);
+/// assert_eq!(reader.position(), 1);
+/// assert_eq!(reader.remaining(), 6);
+/// assert_eq!(reader.remaining_slice(), &[0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+    slice: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(slice: &'a [u8]) -> Self {
+        SliceReader {
+            slice,
+            position: 0,
+        }
+    }
+
+    /// The number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.slice.len() - self.position
+    }
+
+    /// The part of the slice which has not been read yet.
+    pub fn remaining_slice(&self) -> &'a [u8] {
+        &self.slice[self.position..]
+    }
+
+    /// Advances by `n` bytes without reading them. Fails with `UnexpectedEof`, without advancing,
+    /// if fewer than `n` bytes are left.
+    pub fn skip(&mut self, n: usize) -> io::Result<()> {
+        if self.remaining() < n {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof))
+        }
+
+        self.position += n;
+        Ok(())
+    }
+}
+
+impl<'a> Read for SliceReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.remaining_slice().read(buf)?;
+        self.position += n;
+        Ok(n)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.remaining_slice().read_exact(buf)?;
+        self.position += buf.len();
+        Ok(())
+    }
+}
+
+/// A reader which copies every byte read from the underlying reader into a writer, e.g. to keep the
+/// raw bytes of a decoded value:
+/// ```
+/// use packs::Unpack;
+/// use packs::ll::io::TeeReader;
+///
+/// let bytes : &[u8] = &[0xC9, 0x01, 0x00, 0x2A];
+/// let mut reader = TeeReader::new(bytes, Vec::new());
+/// let value = i64::decode(&mut reader).unwrap();
+///
+/// let (_, captured) = reader.into_parts();
+/// assert_eq!(value, 256);
+/// assert_eq!(captured, vec!(0xC9, 0x01, 0x00));
+/// ```
+pub struct TeeReader<R, W> {
+    inner: R,
+    sink: W,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    pub fn new(inner: R, sink: W) -> Self {
+        TeeReader {
+            inner,
+            sink,
+        }
+    }
+
+    /// Consumes the reader, returning the underlying reader and the writer holding the bytes read.
+    pub fn into_parts(self) -> (R, W) {
+        (self.inner, self.sink)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sink.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// A writer which counts the bytes written to the underlying writer. Together with
+/// [`io::sink`](std::io::sink) it measures how many bytes an encoding takes without keeping them:
+/// ```
+/// use packs::Pack;
+/// use packs::ll::io::CountingWriter;
+///
+/// let mut writer = CountingWriter::new(std::io::sink());
+/// 1000i64.encode(&mut writer).unwrap();
+///
+/// assert_eq!(writer.count(), 3);
+/// ```
+pub struct CountingWriter<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CountingWriter {
+            inner,
+            count: 0,
+        }
+    }
+
+    /// The number of bytes written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader which allows to read at most a given number of bytes from the underlying reader. Unlike
+/// [`Read::take`](std::io::Read::take), which reports an end of input once the limit is reached,
+/// reading beyond the limit fails with an error of kind `Other`, so an exhausted budget cannot be
+/// mistaken for input which has just ended:
+/// ```
+/// use packs::Unpack;
+/// use packs::ll::io::LimitedReader;
+///
+/// // "hello" as a tiny string:
+/// let bytes : &[u8] = &[0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F];
+///
+/// let mut reader = LimitedReader::new(bytes, 4);
+/// assert!(String::decode(&mut reader).is_err());
+/// assert!(reader.is_exceeded());
+///
+/// let mut reader = LimitedReader::new(bytes, 6);
+/// assert_eq!(String::decode(&mut reader).unwrap(), "hello");
+/// assert_eq!(reader.remaining(), 0);
+/// ```
+pub struct LimitedReader<R> {
+    inner: R,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub fn new(inner: R, limit: usize) -> Self {
+        LimitedReader {
+            inner,
+            remaining: limit,
+            exceeded: false,
+        }
+    }
+
+    /// The number of bytes which can still be read.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Whether a read has been refused because the limit was reached.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        if self.remaining == 0 {
+            self.exceeded = true;
+            return Err(io::Error::other("read limit exceeded"))
+        }
+
+        let max = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n;
+        Ok(n)
+    }
+}
+
+/// A writer which allows to write at most a given number of bytes to the underlying writer. A write
+/// which does not fit into the remaining budget is refused as a whole with an error of kind
+/// `Other`, such that nothing beyond the limit reaches the underlying writer:
+/// ```
+/// use packs::Pack;
+/// use packs::ll::io::BoundedWriter;
+///
+/// let mut writer = BoundedWriter::new(Vec::new(), 4);
+/// assert!(String::from("hello").encode(&mut writer).is_err());
+/// assert!(writer.is_exceeded());
+///
+/// let mut writer = BoundedWriter::new(Vec::new(), 6);
+/// String::from("hello").encode(&mut writer).unwrap();
+/// assert_eq!(writer.remaining(), 0);
+/// ```
+pub struct BoundedWriter<W> {
+    inner: W,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl<W: Write> BoundedWriter<W> {
+    pub fn new(inner: W, limit: usize) -> Self {
+        BoundedWriter {
+            inner,
+            remaining: limit,
+            exceeded: false,
+        }
+    }
+
+    /// The number of bytes which can still be written.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Whether a write has been refused because it would have crossed the limit.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for BoundedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.remaining {
+            self.exceeded = true;
+            return Err(io::Error::other("write limit exceeded"))
+        }
+
+        let n = self.inner.write(buf)?;
+        self.remaining -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "sha2")]
+/// A writer which feeds every byte written to the underlying writer through a SHA-256 hasher. This
+/// allows to compute the digest of an encoded value in the same pass as writing it out.
+/// ```
+/// use packs::Pack;
+/// use packs::ll::io::DigestWriter;
+///
+/// let mut writer = DigestWriter::new(Vec::new());
+/// 42i64.encode(&mut writer).unwrap();
+///
+/// let (buffer, digest) = writer.finalize();
+/// assert_eq!(buffer, vec!(0x2A));
+/// assert_eq!(digest.len(), 32);
+/// ```
+pub struct DigestWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+#[cfg(feature = "sha2")]
+impl<W: Write> DigestWriter<W> {
+    pub fn new(inner: W) -> Self {
+        DigestWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Consumes the writer, returning the underlying writer and the digest of all bytes written.
+    pub fn finalize(self) -> (W, [u8; 32]) {
+        (self.inner, self.hasher.finalize().into())
+    }
+}
+
+#[cfg(feature = "sha2")]
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::ll::io::SliceReader;
+    use std::io::Read;
+
+    #[test]
+    fn slice_reader_tracks_position() {
+        let mut reader = SliceReader::new(&[0x01, 0x02, 0x03]);
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).unwrap();
+
+        assert_eq!(buf, [0x01, 0x02]);
+        assert_eq!(reader.position(), 2);
+        assert_eq!(reader.remaining_slice(), &[0x03]);
+
+        assert!(reader.read_exact(&mut buf).is_err());
+        assert_eq!(reader.position(), 2, "A failed read must not advance the position.");
+
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn limited_reader_refuses_reads_beyond_limit() {
+        use crate::ll::io::LimitedReader;
+
+        let mut reader = LimitedReader::new([0x01u8, 0x02, 0x03].as_ref(), 2);
+        let mut buf = [0; 3];
+
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], &[0x01, 0x02]);
+        assert!(!reader.is_exceeded());
+
+        assert_eq!(reader.read(&mut buf).unwrap_err().kind(), std::io::ErrorKind::Other);
+        assert!(reader.is_exceeded());
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn digest_writer_matches_digest_of_written_bytes() {
+        use sha2::{Digest, Sha256};
+        use std::io::Write;
+        use crate::ll::io::DigestWriter;
+
+        let mut writer = DigestWriter::new(Vec::new());
+        writer.write_all(&[0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F]).unwrap();
+        let (buffer, digest) = writer.finalize();
+
+        let expected: [u8; 32] = Sha256::digest(&buffer).into();
+        assert_eq!(digest, expected);
+    }
+}
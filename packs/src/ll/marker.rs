@@ -1,5 +1,5 @@
 use std::fmt::{Display, Formatter};
-use std::io::{Read, Write};
+use std::io::{BufRead, Read, Write};
 use std::io;
 
 use crate::error::DecodeError;
@@ -102,6 +102,30 @@ impl Marker {
         }
     }
 
+    /// The number of bytes the marker takes on the wire together with the size information
+    /// following it, i.e. everything before the body. The tag byte of a `Structure` is counted as
+    /// well, the value of a fixed length type like `Int16` is not:
+    /// ```
+    /// use packs::ll::marker::Marker;
+    ///
+    /// assert_eq!(Marker::TinyString(3).byte_len(), 1);
+    /// assert_eq!(Marker::String16.byte_len(), 3);
+    /// assert_eq!(Marker::Structure(3, 0x4E).byte_len(), 2);
+    /// assert_eq!(Marker::Int64.byte_len(), 1);
+    /// ```
+    pub fn byte_len(&self) -> usize {
+        use Marker::*;
+        match self {
+            TinyString(_) | TinyList(_) | TinyDictionary(_) => 1,
+            Structure(_, _) => 2,
+            PlusTinyInt(_) | MinusTinyInt(_) => 1,
+            Float64 | Int8 | Int16 | Int32 | Int64 | Null | True | False => 1,
+            String8 | Bytes8 | List8 | Dictionary8 => 2,
+            String16 | Bytes16 | List16 | Dictionary16 => 3,
+            String32 | Bytes32 | List32 | Dictionary32 => 5,
+        }
+    }
+
     pub fn encode<T: Write>(self, into: &mut T) -> io::Result<usize> {
         use Marker::*;
         match self {
@@ -121,7 +145,18 @@ impl Marker {
         }
     }
 
-    /*pub fn from_u8(from: u8) -> Option<Marker> {
+    /// The marker denoted by a single byte, e.g. one already read by a framing layer. A
+    /// `Structure` is never returned, since its tag is the byte following the marker byte; for a
+    /// structure marker byte as well as for a reserved or unknown one the result is `None`:
+    /// ```
+    /// use packs::ll::marker::Marker;
+    ///
+    /// assert_eq!(Marker::from_u8(0x85), Some(Marker::TinyString(5)));
+    /// assert_eq!(Marker::from_u8(0xC9), Some(Marker::Int16));
+    /// assert_eq!(Marker::from_u8(0xB3), None); // a structure with 3 fields
+    /// assert_eq!(Marker::from_u8(0xC4), None); // reserved
+    /// ```
+    pub fn from_u8(from: u8) -> Option<Marker> {
         if is_in_plus_tiny_int_bound(from as i64) {
             Some(Marker::PlusTinyInt(from))
         } else if MarkerHighNibble::MinusTinyInt.is_contained_in(from) {
@@ -133,7 +168,7 @@ impl Marker {
         } else if MarkerHighNibble::TinyDictionary.is_contained_in(from) {
             Some(Marker::TinyDictionary(get_tiny_size(from)))
         } else if MarkerHighNibble::Structure.is_contained_in(from) {
-            Some(Marker::Structure(get_tiny_size(from)))
+            None
         } else {
             match from {
                 0xC0 => Some(Marker::Null),
@@ -165,60 +200,63 @@ impl Marker {
                 _ => None
             }
         }
-    }*/
+    }
 
     pub fn decode<T: Read>(reader: &mut T) -> Result<Marker, DecodeError> {
         let mut buf = [0; 1];
         reader.read_exact(&mut buf)?;
         let from = buf[0];
-        if is_in_plus_tiny_int_bound(from as i64) {
-            Ok(Marker::PlusTinyInt(from))
-        } else if MarkerHighNibble::MinusTinyInt.is_contained_in(from) {
-            Ok(Marker::MinusTinyInt(from))
-        } else if MarkerHighNibble::TinyString.is_contained_in(from) {
-            Ok(Marker::TinyString(get_tiny_size(from)))
-        } else if MarkerHighNibble::TinyList.is_contained_in(from) {
-            Ok(Marker::TinyList(get_tiny_size(from)))
-        } else if MarkerHighNibble::TinyDictionary.is_contained_in(from) {
-            Ok(Marker::TinyDictionary(get_tiny_size(from)))
-        } else if MarkerHighNibble::Structure.is_contained_in(from) {
+        if MarkerHighNibble::Structure.is_contained_in(from) {
             let mut buf = [0; 1];
             reader.read_exact(&mut buf)?;
             Ok(Marker::Structure(get_tiny_size(from), buf[0]))
+        } else if is_reserved_marker_byte(from) {
+            Err(DecodeError::ReservedMarkerByte(from))
         } else {
-            match from {
-                0xC0 => Ok(Marker::Null),
-                0xC1 => Ok(Marker::Float64),
-                0xC2 => Ok(Marker::True),
-                0xC3 => Ok(Marker::False),
-
-                0xC8 => Ok(Marker::Int8),
-                0xC9 => Ok(Marker::Int16),
-                0xCA => Ok(Marker::Int32),
-                0xCB => Ok(Marker::Int64),
-
-                0xCC => Ok(Marker::Bytes8),
-                0xCD => Ok(Marker::Bytes16),
-                0xCE => Ok(Marker::Bytes32),
-
-                0xD0 => Ok(Marker::String8),
-                0xD1 => Ok(Marker::String16),
-                0xD2 => Ok(Marker::String32),
-
-                0xD4 => Ok(Marker::List8),
-                0xD5 => Ok(Marker::List16),
-                0xD6 => Ok(Marker::List32),
-
-                0xD8 => Ok(Marker::Dictionary8),
-                0xD9 => Ok(Marker::Dictionary16),
-                0xDA => Ok(Marker::Dictionary32),
-
-                _ => Err(DecodeError::UnknownMarkerByte(from))
-            }
+            Marker::from_u8(from).ok_or(DecodeError::UnknownMarkerByte(from))
         }
     }
 }
 
+/// Reads the upcoming marker without removing it from `reader`, such that the value can still be
+/// decoded as a whole afterwards, e.g. by an implementation chosen by the marker:
+/// ```
+/// use std::io::Cursor;
+/// use packs::Unpack;
+/// use packs::ll::marker::{Marker, peek_marker};
+///
+/// let mut reader = Cursor::new(vec!(0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F));
+///
+/// assert_eq!(peek_marker(&mut reader).unwrap(), Marker::TinyString(5));
+/// assert_eq!(reader.position(), 0);
+/// assert_eq!(String::decode(&mut reader).unwrap(), "hello");
+/// ```
+/// Peeking at the end of the input fails with `UnexpectedEof`. A `Structure` marker takes two
+/// bytes, the marker byte and the tag; both have to be available in the buffer of `reader` at
+/// once. Since `fill_buf` only reads more once the buffer is empty, peeking a structure whose tag
+/// has not been buffered yet fails with `MarkerNotBuffered` instead; decoding the value still
+/// works then. This is also reported if the input ends after the marker byte, which cannot be told
+/// apart without consuming it.
+pub fn peek_marker<T: BufRead>(reader: &mut T) -> Result<Marker, DecodeError> {
+    let buf = reader.fill_buf()?;
+    let from = *buf.first().ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    if MarkerHighNibble::Structure.is_contained_in(from) {
+        let tag = *buf.get(1).ok_or(DecodeError::MarkerNotBuffered)?;
+        Ok(Marker::Structure(get_tiny_size(from), tag))
+    } else if is_reserved_marker_byte(from) {
+        Err(DecodeError::ReservedMarkerByte(from))
+    } else {
+        Marker::from_u8(from).ok_or(DecodeError::UnknownMarkerByte(from))
+    }
+}
+
+/// Whether the byte is reserved by the specification for future use, i.e. `0xC4` to `0xC7`, `0xCF`,
+/// `0xD3`, `0xD7` and `0xDB` to `0xEF`. Decoding such a byte as a marker fails with
+/// `DecodeError::ReservedMarkerByte`.
+pub fn is_reserved_marker_byte(byte: u8) -> bool {
+    matches!(byte, 0xC4..=0xC7 | 0xCF | 0xD3 | 0xD7 | 0xDB..=0xEF)
+}
+
 impl Display for Marker {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -278,11 +316,12 @@ impl MarkerHighNibble {
 #[cfg(test)]
 pub mod test {
     use crate::ll::marker::Marker;
+    use crate::DecodeError;
 
     pub fn marker_from_bytes_test(marker: Marker, mut bytes: &[u8]) {
         let m =
             Marker::decode(&mut bytes)
-                .expect(&format!("Decoding error on bytes {:X?} trying to read out marker {:?}", bytes, marker));
+                .unwrap_or_else(|_| panic!("Decoding error on bytes {:X?} trying to read out marker {:?}", bytes, marker));
         assert_eq!(marker, m);
     }
 
@@ -347,4 +386,134 @@ pub mod test {
             marker_from_bytes_test(m, &[m.high_nibble() as u8]);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reserved_marker_bytes() {
+        let reserved =
+            [0xC4, 0xC5, 0xC6, 0xC7, 0xCF, 0xD3, 0xD7].iter().copied()
+                .chain(0xDB..=0xEF);
+
+        for byte in reserved {
+            match Marker::decode(&mut [byte].as_ref()) {
+                Err(DecodeError::ReservedMarkerByte(b)) => assert_eq!(b, byte),
+                r => panic!("Expected ReservedMarkerByte for {:#X}, got {:?}", byte, r),
+            }
+        }
+    }
+
+    #[test]
+    fn byte_len_of_each_marker() {
+        let cases = [
+            (Marker::TinyString(5), 1),
+            (Marker::TinyList(5), 1),
+            (Marker::TinyDictionary(5), 1),
+            (Marker::Structure(5, 0x4E), 2),
+            (Marker::PlusTinyInt(0x2A), 1),
+            (Marker::MinusTinyInt(0xF0), 1),
+            (Marker::Float64, 1),
+            (Marker::Int8, 1),
+            (Marker::Int16, 1),
+            (Marker::Int32, 1),
+            (Marker::Int64, 1),
+            (Marker::Null, 1),
+            (Marker::True, 1),
+            (Marker::False, 1),
+            (Marker::String8, 2),
+            (Marker::String16, 3),
+            (Marker::String32, 5),
+            (Marker::Bytes8, 2),
+            (Marker::Bytes16, 3),
+            (Marker::Bytes32, 5),
+            (Marker::List8, 2),
+            (Marker::List16, 3),
+            (Marker::List32, 5),
+            (Marker::Dictionary8, 2),
+            (Marker::Dictionary16, 3),
+            (Marker::Dictionary32, 5),
+        ];
+
+        for (marker, len) in cases.iter() {
+            assert_eq!(marker.byte_len(), *len, "Unexpected byte_len of {:?}", marker);
+        }
+    }
+
+    #[test]
+    fn from_u8_single_byte_markers() {
+        assert_eq!(Marker::from_u8(0x0F), Some(Marker::PlusTinyInt(0x0F)));
+        for i in 0u8..0x10 {
+            assert_eq!(Marker::from_u8(0xF0 | i), Some(Marker::MinusTinyInt(0xF0 | i)));
+            assert_eq!(Marker::from_u8(0x80 | i), Some(Marker::TinyString(i as usize)));
+            assert_eq!(Marker::from_u8(0x90 | i), Some(Marker::TinyList(i as usize)));
+            assert_eq!(Marker::from_u8(0xA0 | i), Some(Marker::TinyDictionary(i as usize)));
+            assert_eq!(Marker::from_u8(0xB0 | i), None);
+        }
+
+        let r = [
+            Marker::Null,
+            Marker::Float64,
+            Marker::False,
+            Marker::True,
+            Marker::Int8,
+            Marker::Int16,
+            Marker::Int32,
+            Marker::Int64,
+            Marker::String8,
+            Marker::String16,
+            Marker::String32,
+            Marker::List8,
+            Marker::List16,
+            Marker::List32,
+            Marker::Dictionary8,
+            Marker::Dictionary16,
+            Marker::Dictionary32,
+            Marker::Bytes8,
+            Marker::Bytes16,
+            Marker::Bytes32,
+        ];
+
+        for m in r.iter() {
+            assert_eq!(Marker::from_u8(m.high_nibble() as u8), Some(*m));
+        }
+
+        assert_eq!(Marker::from_u8(0xC4), None);
+    }
+
+    #[test]
+    fn peek_marker_does_not_advance() {
+        use std::io::Cursor;
+        use crate::ll::marker::peek_marker;
+        use crate::{Unpack, Value, GenericStruct};
+
+        // a structure with tag `0x01` and one field, followed by a tiny int:
+        let mut reader = Cursor::new(vec!(0xB1, 0x01, 0x2A, 0x01));
+
+        assert_eq!(peek_marker(&mut reader).unwrap(), Marker::Structure(1, 0x01));
+        assert_eq!(peek_marker(&mut reader).unwrap(), Marker::Structure(1, 0x01));
+        assert_eq!(reader.position(), 0);
+
+        <Value<GenericStruct>>::decode(&mut reader).unwrap();
+        assert_eq!(reader.position(), 3);
+
+        assert_eq!(peek_marker(&mut reader).unwrap(), Marker::PlusTinyInt(0x01));
+        assert_eq!(reader.position(), 3);
+
+        i64::decode(&mut reader).unwrap();
+        let err = peek_marker(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::ReadIOError(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn peek_marker_with_unbuffered_tag() {
+        use std::io::BufReader;
+        use crate::ll::marker::peek_marker;
+        use crate::{Unpack, Value, GenericStruct};
+
+        let bytes : &[u8] = &[0xB1, 0x01, 0x2A];
+        let mut reader = BufReader::with_capacity(1, bytes);
+
+        let err = peek_marker(&mut reader).unwrap_err();
+        assert!(matches!(err, DecodeError::MarkerNotBuffered));
+        assert!(err.is_io());
+        assert!(<Value<GenericStruct>>::decode(&mut reader).is_ok());
+    }
+}
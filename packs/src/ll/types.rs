@@ -1,3 +1,6 @@
 pub mod fixed;
+#[cfg(feature = "bench")]
 pub mod sized;
+#[cfg(not(feature = "bench"))]
+pub(crate) mod sized;
 pub mod lengths;
\ No newline at end of file
@@ -2,6 +2,9 @@ use std::io::Read;
 use crate::ll::marker::Marker;
 use std::io::{Write};
 use std::io;
+use std::convert::TryFrom;
+use crate::error::EncodeError;
+use crate::ll::bounds::{is_in_i16_bound, is_in_i32_bound, is_in_i8_bound, is_in_minus_tiny_int_bound, is_in_plus_tiny_int_bound};
 
 pub fn decode_minus_tiny_int<T: Read>(mut reader: T) -> io::Result<i8> {
     let mut buf = [0; 1];
@@ -93,6 +96,99 @@ pub fn decode_body_i64<T: Read>(mut from: T) -> io::Result<i64> {
     Ok(i64::from_be_bytes(buf))
 }
 
+/// Encodes an integer with exactly `width` bytes, i.e. as `Int8`, `Int16`, `Int32` or `Int64` for
+/// a `width` of `1`, `2`, `4` or `8`, regardless of how small the value is:
+/// ```
+/// use packs::ll::types::fixed::encode_int_with_width;
+/// let mut buffer = Vec::new();
+/// encode_int_with_width(1, 4, &mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xCA, 0x00, 0x00, 0x00, 0x01));
+/// ```
+/// Reports `IntegerOutOfRange` if the value does not fit into `width` bytes.
+///
+/// **Note**: Panics on any other `width`.
+pub fn encode_int_with_width<T: Write>(value: i128, width: usize, into: T) -> Result<usize, EncodeError> {
+    let out_of_range = || EncodeError::IntegerOutOfRange(value, width);
+    match width {
+        1 => Ok(encode_i8(i8::try_from(value).map_err(|_| out_of_range())?, into)?),
+        2 => Ok(encode_i16(i16::try_from(value).map_err(|_| out_of_range())?, into)?),
+        4 => Ok(encode_i32(i32::try_from(value).map_err(|_| out_of_range())?, into)?),
+        8 => Ok(encode_i64(i64::try_from(value).map_err(|_| out_of_range())?, into)?),
+        _ => panic!("Unsupported integer width {}, expected 1, 2, 4 or 8.", width),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// The width an integer has been encoded with, as denoted by its marker. `Tiny` covers both the
+/// `PlusTinyInt` and the `MinusTinyInt`, which have no bytes besides their marker.
+pub enum IntWidth {
+    Tiny,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+}
+
+impl IntWidth {
+    /// The width of an integer marker, `None` for any other marker.
+    pub fn of_marker(marker: Marker) -> Option<IntWidth> {
+        match marker {
+            Marker::PlusTinyInt(_) | Marker::MinusTinyInt(_) => Some(IntWidth::Tiny),
+            Marker::Int8 => Some(IntWidth::Int8),
+            Marker::Int16 => Some(IntWidth::Int16),
+            Marker::Int32 => Some(IntWidth::Int32),
+            Marker::Int64 => Some(IntWidth::Int64),
+            _ => None,
+        }
+    }
+
+    /// The number of bytes following the marker, i.e. `0` for `Tiny`.
+    pub fn bytes(self) -> usize {
+        match self {
+            IntWidth::Tiny => 0,
+            IntWidth::Int8 => 1,
+            IntWidth::Int16 => 2,
+            IntWidth::Int32 => 4,
+            IntWidth::Int64 => 8,
+        }
+    }
+}
+
+/// Encodes an integer with the given width, like
+/// [`encode_int_with_width`](crate::ll::types::fixed::encode_int_with_width) but allowing for tiny
+/// ints as well. Reports `IntegerOutOfRange` if the value does not fit.
+pub fn encode_int_as<T: Write>(value: i64, width: IntWidth, into: T) -> Result<usize, EncodeError> {
+    match width {
+        IntWidth::Tiny if is_in_plus_tiny_int_bound(value) => Ok(encode_plus_tiny_int(value as u8, into)?),
+        IntWidth::Tiny if is_in_minus_tiny_int_bound(value) => Ok(encode_minus_tiny_int(value as i8, into)?),
+        IntWidth::Tiny => Err(EncodeError::IntegerOutOfRange(i128::from(value), 0)),
+        _ => encode_int_with_width(i128::from(value), width.bytes(), into),
+    }
+}
+
+/// The number of bytes an integer occupies when encoded in the smallest way, as done by
+/// [`Pack`](crate::packable::Pack) for `i64` and `i32`:
+/// ```
+/// use packs::ll::types::fixed::int_encoded_len;
+///
+/// assert_eq!(int_encoded_len(-16), 1); // tiny int
+/// assert_eq!(int_encoded_len(300), 3); // `Int16`
+/// ```
+pub fn int_encoded_len(value: i64) -> usize {
+    if is_in_plus_tiny_int_bound(value) || is_in_minus_tiny_int_bound(value) {
+        1
+    } else if is_in_i8_bound(value) {
+        2
+    } else if is_in_i16_bound(value) {
+        3
+    } else if is_in_i32_bound(value) {
+        5
+    } else {
+        9
+    }
+}
+
 pub fn encode_f64<T: Write>(from: f64, mut into: T) -> io::Result<usize> {
     Ok(Marker::Float64.encode(&mut into)? + into.write(&from.to_be_bytes())?)
 }
@@ -106,7 +202,8 @@ pub fn decode_body_f64<T: Read>(mut from: T) -> io::Result<f64> {
 #[cfg(test)]
 pub mod test {
     mod encoding {
-        use crate::ll::types::fixed::{encode_minus_tiny_int, decode_minus_tiny_int, encode_plus_tiny_int, decode_plus_tiny_int};
+        use crate::ll::types::fixed::{encode_minus_tiny_int, decode_minus_tiny_int, encode_plus_tiny_int, decode_plus_tiny_int, encode_int_with_width};
+        use crate::error::EncodeError;
         use crate::ll::bounds::{MAX_PLUS_TINY_INT, MIN_MINUS_TINY_INT};
 
         #[test]
@@ -148,6 +245,16 @@ pub mod test {
             }
         }
 
+        #[test]
+        fn int_with_width_out_of_range() {
+            let mut buffer = Vec::new();
+            match encode_int_with_width(40000, 2, &mut buffer) {
+                Err(EncodeError::IntegerOutOfRange(40000, 2)) => (),
+                r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+            }
+            assert!(buffer.is_empty());
+        }
+
         #[test]
         fn plus_tiny_ints_encode_decode() {
             let mut buffer: Vec<u8> = Vec::with_capacity(MAX_PLUS_TINY_INT as usize);
@@ -7,7 +7,7 @@ use crate::ll::types::fixed::decode_body_i32;
 pub fn read_size_8<T: Read>(reader: &mut T) -> Result<usize, DecodeError> {
     let mut buf = [0; 1];
     reader.read_exact(&mut buf)?;
-    TryFrom::try_from(buf[0]).or(Err(DecodeError::CannotReadSizeInfo))
+    Ok(usize::from(buf[0]))
 }
 
 pub fn write_size_8<T: Write>(size: u8, writer: &mut T) -> Result<usize, EncodeError> {
@@ -17,7 +17,7 @@ pub fn write_size_8<T: Write>(size: u8, writer: &mut T) -> Result<usize, EncodeE
 pub fn read_size_16<T: Read>(reader: &mut T) -> Result<usize, DecodeError> {
     let mut buf = [0; 2];
     reader.read_exact(&mut buf)?;
-    TryFrom::try_from(u16::from_be_bytes(buf)).or(Err(DecodeError::CannotReadSizeInfo))
+    Ok(usize::from(u16::from_be_bytes(buf)))
 }
 
 pub fn write_size_16<T: Write>(size: u16, writer: &mut T) -> Result<usize, EncodeError> {
@@ -79,6 +79,86 @@ pub fn read_string_size<T: Read>(marker: Marker, reader: &mut T) -> Result<usize
     }
 }
 
+/// Reads the size of PackStream `Bytes` as denoted by the marker. Analogous to
+/// [`read_dict_size`](crate::ll::types::lengths::read_dict_size).
+pub fn read_bytes_size<T: Read>(marker: Marker, reader: &mut T) -> Result<usize, DecodeError> {
+    match marker {
+        Marker::Bytes8 => read_size_8(reader),
+        Marker::Bytes16 => read_size_16(reader),
+        Marker::Bytes32 => read_size_32(reader),
+        _ => Err(DecodeError::UnexpectedMarker(marker))
+    }
+}
+
+/// The number of bytes of marker and size information for a sized type, like a `String` or a `List`,
+/// with `size` items. Sizes too large to be encoded are reported as the largest header.
+/// ```
+/// use packs::ll::types::lengths::header_len;
+///
+/// assert_eq!(header_len(3), 1); // tiny
+/// assert_eq!(header_len(300), 3); // marker and 16 bit size
+/// ```
+pub fn header_len(size: usize) -> usize {
+    match Length::from_usize(size) {
+        Some(Length::Tiny(_)) => 1,
+        Some(Length::Bit8(_)) => 2,
+        Some(Length::Bit16(_)) => 3,
+        _ => 5,
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// What follows a marker (and its size information), as reported by
+/// [`body_len_of`](crate::ll::types::lengths::body_len_of).
+pub enum BodyLen {
+    /// The body is exactly this many bytes long, e.g. the bytes of a `String`.
+    Fixed(usize),
+    /// The body consists of this many values, each of variable length. For a `Dictionary` these
+    /// are the entries, i.e. each entry is a key and a value.
+    Count(usize),
+    /// The body consists of this many fields of a structure with the given tag byte.
+    Nested(usize, u8),
+}
+
+/// Reads the size information following `marker`, if any, and reports how long the body is:
+/// ```
+/// use packs::ll::marker::Marker;
+/// use packs::ll::types::lengths::{body_len_of, BodyLen};
+///
+/// let mut buffer : &[u8] = &[0xD0, 0x1A];
+/// let marker = Marker::decode(&mut buffer).unwrap();
+///
+/// assert_eq!(BodyLen::Fixed(26), body_len_of(marker, &mut buffer).unwrap());
+/// ```
+/// Markers of fixed sized types are reported as `Fixed` as well, e.g. `Int16` has a body of
+/// 2 bytes while `Null` or a `TinyInt` have a body of 0 bytes.
+pub fn body_len_of<T: Read>(marker: Marker, reader: &mut T) -> Result<BodyLen, DecodeError> {
+    match marker {
+        Marker::Null | Marker::True | Marker::False
+        | Marker::PlusTinyInt(_) | Marker::MinusTinyInt(_) => Ok(BodyLen::Fixed(0)),
+        Marker::Int8 => Ok(BodyLen::Fixed(1)),
+        Marker::Int16 => Ok(BodyLen::Fixed(2)),
+        Marker::Int32 => Ok(BodyLen::Fixed(4)),
+        Marker::Int64 | Marker::Float64 => Ok(BodyLen::Fixed(8)),
+
+        Marker::TinyString(_) | Marker::String8 | Marker::String16 | Marker::String32 =>
+            Ok(BodyLen::Fixed(read_string_size(marker, reader)?)),
+        Marker::Bytes8 | Marker::Bytes16 | Marker::Bytes32 =>
+            Ok(BodyLen::Fixed(read_bytes_size(marker, reader)?)),
+        Marker::TinyList(_) | Marker::List8 | Marker::List16 | Marker::List32 =>
+            Ok(BodyLen::Count(read_list_size(marker, reader)?)),
+        Marker::TinyDictionary(_) | Marker::Dictionary8 | Marker::Dictionary16 | Marker::Dictionary32 =>
+            Ok(BodyLen::Count(read_dict_size(marker, reader)?)),
+        Marker::Structure(u, tag) => Ok(BodyLen::Nested(u, tag)),
+    }
+}
+
+/// Whether a `BodyLen::Count` of this marker counts entries, i.e. twice as many values.
+pub(crate) fn marker_is_dictionary(marker: Marker) -> bool {
+    matches!(marker,
+        Marker::TinyDictionary(_) | Marker::Dictionary8 | Marker::Dictionary16 | Marker::Dictionary32)
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 /// The possible lengths for sized types like `String8`. The different sign extensions are according
 /// to the PackStream specification. This type should be used for encoding any size information, e.g.
@@ -128,7 +208,7 @@ impl Length {
             Length::Bit8(u) => u as usize,
             Length::Bit16(u) => u as usize,
             Length::Bit32(i) =>
-                TryFrom::try_from(i).expect(&format!("Cannot read usize out of {}", i))
+                TryFrom::try_from(i).unwrap_or_else(|_| panic!("Cannot read usize out of {}", i))
         }
     }
 
@@ -155,9 +235,7 @@ impl Length {
                 Ok(1)
             },
             Length::Bit8(u) => {
-                Marker::List8.encode(writer)?;
-                writer.write(&[u])?;
-                Ok(2)
+                Ok(Marker::List8.encode(writer)? + writer.write(&[u])?)
             },
             Length::Bit16(u) => {
                 Ok(
@@ -190,4 +268,30 @@ impl Length {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::ll::marker::Marker;
+    use crate::ll::types::lengths::{body_len_of, BodyLen};
+
+    fn body_len(bytes: &[u8]) -> BodyLen {
+        let mut reader = bytes;
+        let marker = Marker::decode(&mut reader).unwrap();
+        body_len_of(marker, &mut reader).unwrap()
+    }
+
+    #[test]
+    fn body_lengths() {
+        assert_eq!(BodyLen::Fixed(0), body_len(&[0xC0]));
+        assert_eq!(BodyLen::Fixed(0), body_len(&[0x2A]));
+        assert_eq!(BodyLen::Fixed(2), body_len(&[0xC9]));
+        assert_eq!(BodyLen::Fixed(8), body_len(&[0xC1]));
+        assert_eq!(BodyLen::Fixed(3), body_len(&[0x83]));
+        assert_eq!(BodyLen::Fixed(300), body_len(&[0xD1, 0x01, 0x2C]));
+        assert_eq!(BodyLen::Fixed(5), body_len(&[0xCC, 0x05]));
+        assert_eq!(BodyLen::Count(2), body_len(&[0x92]));
+        assert_eq!(BodyLen::Count(20), body_len(&[0xD8, 0x14]));
+        assert_eq!(BodyLen::Nested(3, 0x4E), body_len(&[0xB3, 0x4E]));
+    }
+}
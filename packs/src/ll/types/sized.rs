@@ -1,6 +1,8 @@
 use std::io::Write;
-use crate::{Pack, EncodeError};
+use crate::{Pack, EncodeError, EncodeOptions};
 
+/// Encodes every item of `collection`, without a header. Only used by the benchmarks.
+#[cfg(feature = "bench")]
 pub fn write_body_by_iter<'a, T: Write, P: 'a + Pack, C: Iterator<Item = &'a P>>(collection: &'a mut C, writer: &mut T) -> Result<usize, EncodeError> {
     let mut written = 0;
     for v in collection {
@@ -8,3 +10,12 @@ pub fn write_body_by_iter<'a, T: Write, P: 'a + Pack, C: Iterator<Item = &'a P>>
     }
     Ok(written)
 }
+
+/// Encodes every item of `collection` following the provided `options`, without a header.
+pub fn write_body_by_iter_with<'a, T: Write, P: 'a + Pack, C: Iterator<Item = &'a P>>(collection: &'a mut C, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+    let mut written = 0;
+    for v in collection {
+        written += v.encode_with(writer, options)?
+    }
+    Ok(written)
+}
@@ -0,0 +1,372 @@
+//! # Overview
+//! Maps with keys other than `String`, encoded as PackStream dictionaries. Since dictionary keys
+//! are always strings, each key is written in a string form and parsed back while decoding; a key
+//! which cannot be parsed is reported as `DecodeError::InvalidKey`, or as
+//! `DecodeError::KeyParseError` for a `ParsedKeyMap`.
+//!
+//! | wrapper | key | string form |
+//! | :--- | :--- | :--- |
+//! | [`CharKeyMap`](crate::maps::CharKeyMap) | `char` | the character itself |
+//! | [`ByteKeyMap`](crate::maps::ByteKeyMap) | `[u8; N]` | `2 * N` lowercase hex digits |
+//! | [`ParsedKeyMap`](crate::maps::ParsedKeyMap) | any `K: FromStr + Display` | given by `Display`, parsed back by `FromStr` |
+//!
+//! A map with `bool` keys is better given as a [`BoolPair`](crate::bool_pair::BoolPair), holding a
+//! value for each key.
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+use std::io::{Read, Write};
+
+use crate::error::{DecodeError, EncodeError};
+use crate::ll::marker::Marker;
+use crate::ll::types::lengths::{Length, read_dict_size, header_len};
+use crate::options::{DecodeOptions, EncodeOptions, preallocated};
+use crate::packable::{Pack, Unpack};
+use crate::value::dictionary::validate_key_len;
+
+/// A key which is written as a string.
+trait StringKey: Sized + Hash + Eq {
+    fn to_key(&self) -> String;
+    fn from_key(key: &str) -> Option<Self>;
+}
+
+impl StringKey for char {
+    fn to_key(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> StringKey for [u8; N] {
+    fn to_key(&self) -> String {
+        self.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Only the lowercase hex digits `to_key` writes are accepted, such that each key has a single
+    /// string form. The empty key of `N = 0` is no valid dictionary key.
+    fn from_key(key: &str) -> Option<Self> {
+        if N == 0 || key.len() != 2 * N || !key.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return None
+        }
+
+        let mut bytes = [0; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&key[2 * i..2 * i + 2], 16).ok()?;
+        }
+        Some(bytes)
+    }
+}
+
+/// A map with `char` keys, encoded as a dictionary whose keys are strings of one character:
+/// ```
+/// use packs::{Pack, Unpack};
+/// use packs::maps::CharKeyMap;
+///
+/// let mut map = CharKeyMap::default();
+/// map.0.insert('x', 42i64);
+///
+/// let mut buffer = Vec::new();
+/// map.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xA1, 0x81, 0x78, 0x2A));
+/// assert_eq!(CharKeyMap::decode(&mut buffer.as_slice()).unwrap(), map);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharKeyMap<V>(pub HashMap<char, V>);
+
+/// A map with keys of `N` bytes, encoded as a dictionary whose keys are the bytes as lowercase hex
+/// digits. Uppercase digits are rejected when decoding, and since a key cannot be empty, `N` has
+/// to be at least `1`:
+/// ```
+/// use packs::{Pack, Unpack, DecodeError};
+/// use packs::maps::ByteKeyMap;
+///
+/// let mut map = ByteKeyMap::default();
+/// map.0.insert([0xCA, 0xFE], String::from("coffee"));
+///
+/// let mut buffer = Vec::new();
+/// map.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(&buffer[..6], &[0xA1, 0x84, 0x63, 0x61, 0x66, 0x65]); // {"cafe": ..
+/// assert_eq!(ByteKeyMap::<2, String>::decode(&mut buffer.as_slice()).unwrap(), map);
+///
+/// // a key of three bytes cannot be read into `[u8; 2]`:
+/// let buffer = vec!(0xA1, 0x86, 0x63, 0x61, 0x66, 0x65, 0x30, 0x30, 0x01);
+/// assert!(matches!(
+///     ByteKeyMap::<2, i64>::decode(&mut buffer.as_slice()),
+///     Err(DecodeError::InvalidKey(key)) if key == "cafe00"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteKeyMap<const N: usize, V>(pub HashMap<[u8; N], V>);
+
+/// A map with keys of any type which can be written by `Display` and read back by `FromStr`, e.g.
+/// integers; a key which `FromStr` rejects is reported as `KeyParseError`:
+/// ```
+/// use packs::{Pack, Unpack, DecodeError};
+/// use packs::maps::ParsedKeyMap;
+///
+/// let mut map = ParsedKeyMap::default();
+/// map.0.insert(42i64, String::from("answer"));
+///
+/// let mut buffer = Vec::new();
+/// map.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(&buffer[..4], &[0xA1, 0x82, 0x34, 0x32]); // {"42": ..
+/// assert_eq!(ParsedKeyMap::decode(&mut buffer.as_slice()).unwrap(), map);
+///
+/// // the key "4x" is no `i64`:
+/// let buffer = vec!(0xA1, 0x82, 0x34, 0x78, 0x01);
+/// assert!(matches!(
+///     ParsedKeyMap::<i64, i64>::decode(&mut buffer.as_slice()),
+///     Err(DecodeError::KeyParseError(key)) if key == "4x"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedKeyMap<K: Hash + Eq, V>(pub HashMap<K, V>);
+
+impl<V> Default for CharKeyMap<V> {
+    fn default() -> Self {
+        CharKeyMap(HashMap::new())
+    }
+}
+
+impl<const N: usize, V> Default for ByteKeyMap<N, V> {
+    fn default() -> Self {
+        ByteKeyMap(HashMap::new())
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ParsedKeyMap<K, V> {
+    fn default() -> Self {
+        ParsedKeyMap(HashMap::new())
+    }
+}
+
+fn parse_string_key<K: StringKey>(key: String) -> Result<K, DecodeError> {
+    K::from_key(&key).ok_or(DecodeError::InvalidKey(key))
+}
+
+fn parse_from_str_key<K: FromStr>(key: String) -> Result<K, DecodeError> {
+    key.parse().map_err(|_| DecodeError::KeyParseError(key))
+}
+
+fn encode_map<K, V: Pack, T: Write>(map: &HashMap<K, V>, to_key: fn(&K) -> String, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+    // the keys are checked first, such that nothing is written for an invalid one:
+    let entries: Vec<(String, &V)> = map.iter().map(|(key, val)| (to_key(key), val)).collect();
+    for (key, _) in &entries {
+        validate_key_len(key.len())?;
+    }
+
+    let len = Length::from_usize(map.len()).expect("Map has invalid length");
+    let mut written = len.encode_as_dict_size(writer)?;
+    for (key, val) in entries {
+        written += key.encode(writer)? + val.encode_with(writer, options)?;
+    }
+
+    Ok(written)
+}
+
+fn encoded_map_len<K, V: Pack>(map: &HashMap<K, V>, to_key: fn(&K) -> String, value_len: fn(&V) -> usize) -> usize {
+    header_len(map.len())
+        + map.iter().map(|(k, v)| to_key(k).encoded_len() + value_len(v)).sum::<usize>()
+}
+
+fn decode_map<K: Hash + Eq, V: Unpack, T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions, from_key: fn(String) -> Result<K, DecodeError>) -> Result<HashMap<K, V>, DecodeError> {
+    let len = options.check_collection_len(read_dict_size(marker, reader)?)?;
+    let options = &options.descend()?;
+    let mut result = HashMap::with_capacity(preallocated(len));
+    for _ in 0..len {
+        let key = from_key(String::decode_with(reader, options)?)?;
+        result.insert(key, V::decode_with(reader, options)?);
+    }
+
+    Ok(result)
+}
+
+impl<V: Pack> Pack for CharKeyMap<V> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        encode_map(&self.0, StringKey::to_key, writer, options)
+    }
+
+    fn size_hint(&self) -> usize {
+        encoded_map_len(&self.0, StringKey::to_key, V::size_hint)
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoded_map_len(&self.0, StringKey::to_key, V::encoded_len)
+    }
+}
+
+impl<V: Unpack> Unpack for CharKeyMap<V> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Ok(CharKeyMap(decode_map(marker, reader, options, parse_string_key)?))
+    }
+}
+
+impl<const N: usize, V: Pack> Pack for ByteKeyMap<N, V> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        encode_map(&self.0, StringKey::to_key, writer, options)
+    }
+
+    fn size_hint(&self) -> usize {
+        encoded_map_len(&self.0, StringKey::to_key, V::size_hint)
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoded_map_len(&self.0, StringKey::to_key, V::encoded_len)
+    }
+}
+
+impl<const N: usize, V: Unpack> Unpack for ByteKeyMap<N, V> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Ok(ByteKeyMap(decode_map(marker, reader, options, parse_string_key)?))
+    }
+}
+
+impl<K: Display + Hash + Eq, V: Pack> Pack for ParsedKeyMap<K, V> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        encode_map(&self.0, K::to_string, writer, options)
+    }
+
+    fn size_hint(&self) -> usize {
+        encoded_map_len(&self.0, K::to_string, V::size_hint)
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoded_map_len(&self.0, K::to_string, V::encoded_len)
+    }
+}
+
+impl<K: FromStr + Hash + Eq, V: Unpack> Unpack for ParsedKeyMap<K, V> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Ok(ParsedKeyMap(decode_map(marker, reader, options, parse_from_str_key)?))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+
+    use crate::maps::{CharKeyMap, ByteKeyMap, ParsedKeyMap};
+    use crate::packable::test::{pack_unpack_test, encoded_len_test};
+    use crate::{Pack, Unpack, DecodeError, EncodeError, Value, NoStruct};
+
+    #[test]
+    fn pack_unpack_char_key_map() {
+        let map: HashMap<char, i64> =
+            vec!(('a', 1), ('ß', -1000), ('🦀', 0)).into_iter().collect();
+
+        pack_unpack_test(&[CharKeyMap(map), CharKeyMap::default()]);
+    }
+
+    #[test]
+    fn pack_unpack_byte_key_map() {
+        let map: HashMap<[u8; 4], Vec<String>> =
+            vec!(
+                ([0x00, 0x01, 0x02, 0x03], vec!(String::from("hello"))),
+                ([0xFF, 0xFF, 0xFF, 0xFF], Vec::new()),
+            ).into_iter().collect();
+
+        pack_unpack_test(&[ByteKeyMap(map.clone()), ByteKeyMap::default()]);
+        encoded_len_test(&[ByteKeyMap(map)]);
+    }
+
+    #[test]
+    fn pack_unpack_parsed_key_map() {
+        let map: HashMap<i64, String> =
+            vec!((0, String::from("zero")), (-1, String::new()), (i64::MAX, String::from("max")))
+                .into_iter()
+                .collect();
+
+        pack_unpack_test(&[ParsedKeyMap(map.clone()), ParsedKeyMap::default()]);
+        encoded_len_test(&[ParsedKeyMap(map)]);
+
+        let encode = |key: &str| {
+            let mut dict = crate::Dictionary::<NoStruct>::new();
+            dict.add_property(key, "value");
+            crate::Pack::to_vec(&Value::Dictionary(dict)).unwrap()
+        };
+        for key in &["1.5", "x", "99999999999999999999"] {
+            match <ParsedKeyMap<i64, String>>::decode(&mut encode(key).as_slice()) {
+                Err(DecodeError::KeyParseError(k)) => assert_eq!(&k, key),
+                r => panic!("Expected KeyParseError, got {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_keys() {
+        let encode = |key: &str| {
+            let mut dict = crate::Dictionary::<NoStruct>::new();
+            dict.add_property(key, Value::Null);
+            crate::Pack::to_vec(&Value::Dictionary(dict)).unwrap()
+        };
+
+        for key in &["ab", "xyz"] {
+            match <CharKeyMap<Option<i64>>>::decode(&mut encode(key).as_slice()) {
+                Err(DecodeError::InvalidKey(k)) => assert_eq!(&k, key),
+                r => panic!("Expected InvalidKey, got {:?}", r),
+            }
+        }
+
+        // only the lowercase form written by encoding is accepted:
+        for key in &["0g", "012", "ä", "AB", "aB", "+f"] {
+            match <ByteKeyMap<1, Option<i64>>>::decode(&mut encode(key).as_slice()) {
+                Err(DecodeError::InvalidKey(k)) => assert_eq!(&k, key),
+                r => panic!("Expected InvalidKey, got {:?}", r),
+            }
+        }
+        assert!(<ByteKeyMap<1, Option<i64>>>::decode(&mut encode("ab").as_slice()).is_ok());
+
+        // {"": null}, which cannot be encoded from a `Dictionary`:
+        let bytes: &[u8] = &[0xA1, 0x80, 0xC0];
+        match <ByteKeyMap<0, Option<i64>>>::decode(&mut &bytes[..]) {
+            Err(DecodeError::InvalidKey(k)) => assert_eq!(k, ""),
+            r => panic!("Expected InvalidKey, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn encode_rejects_empty_keys() {
+        let mut map = ByteKeyMap::<0, i64>::default();
+        map.0.insert([], 1);
+        let mut buffer = Vec::new();
+        assert!(matches!(map.encode(&mut buffer), Err(EncodeError::InvalidKey(0))));
+        assert!(buffer.is_empty());
+
+        let mut map = ParsedKeyMap::<String, i64>::default();
+        map.0.insert(String::new(), 1);
+        assert!(matches!(map.to_vec(), Err(EncodeError::InvalidKey(0))));
+    }
+}
@@ -0,0 +1,207 @@
+//! # Overview
+//! Options which alter how values are encoded and decoded. They are passed through
+//! [`Pack::encode_with`](crate::packable::Pack::encode_with) and
+//! [`Unpack::decode_with`](crate::packable::Unpack::decode_with) and are forwarded by every
+//! container and derived implementation to the values it is made of, such that an option applies
+//! to a whole tree of values:
+//! ```
+//! use packs::*;
+//! use packs::std_structs::{DateTime, StdStruct};
+//!
+//! let options = EncodeOptions { version: PackStreamVersion::V2 };
+//!
+//! let value : Value<StdStruct> =
+//!     vec!(Value::Structure(StdStruct::DateTime(
+//!         DateTime { seconds: 3600, nanoseconds: 0, tz_offset_minutes: 60 })))
+//!     .into_iter().collect();
+//!
+//! let mut buffer = Vec::new();
+//! value.encode_with(&mut buffer, &options).unwrap();
+//!
+//! // a list with one structure of tag `0x49`, carrying the UTC seconds:
+//! assert_eq!(buffer, vec!(0x91, 0xB3, 0x49, 0x00, 0x00, 0x3C));
+//! ```
+//! The plain `encode` and `decode` functions use the default options.
+use crate::error::DecodeError;
+
+/// The version of the structure semantics to follow.
+///
+/// The standard structures `DateTime` and `DateTimeZoneId` have been changed with Bolt 5.0 (and
+/// the `utc` patch of Bolt 4.4); a peer speaking the older protocol and one speaking the newer one
+/// disagree on them. Only these two structures are affected:
+///
+/// | structure | `V1` | `V2` |
+/// | :--- | :--- | :--- |
+/// | `DateTime` | tag `0x46`, `seconds` in local time | tag `0x49`, `seconds` in UTC |
+/// | `DateTimeZoneId` | tag `0x66`, `seconds` in local time | tag `0x69`, `seconds` in UTC |
+///
+/// For `DateTime` the conversion between local and UTC seconds is done using `tz_offset_minutes`,
+/// so the Rust value always holds local seconds. `DateTimeZoneId` cannot be converted without a
+/// time zone database, hence its `seconds` are passed through unchanged and have to be read as
+/// UTC seconds under `V2`.
+///
+/// All other structures, e.g. `Date` (`0x44`), `Time` (`0x54`) or `LocalDateTime` (`0x64`), are
+/// the same in both versions.
+///
+/// Decoding is strict: under `V1` the tags `0x49` and `0x69` are rejected, under `V2` the tags
+/// `0x46` and `0x66`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PackStreamVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+/// The default of [`DecodeOptions::max_depth`](crate::options::DecodeOptions::max_depth).
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The number of items for which a list or dictionary reserves capacity upfront while decoding. The
+/// declared length is read from the input and cannot be trusted, so larger ones grow while their
+/// items arrive instead.
+pub(crate) const MAX_PREALLOCATED_ITEMS: usize = 1024;
+
+/// The capacity to reserve for a list or dictionary declaring `len` items.
+pub(crate) fn preallocated(len: usize) -> usize {
+    len.min(MAX_PREALLOCATED_ITEMS)
+}
+
+/// Options for decoding, see [`Unpack::decode_with`](crate::packable::Unpack::decode_with).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecodeOptions {
+    pub version: PackStreamVersion,
+    /// Decode a `bool` from the integers `0` and `1` as well, if they are encoded as a tiny int or
+    /// an `Int8`. Any other integer is reported as `DecodeError::IntegerNotBool`. Defaults to
+    /// `false`, i.e. only `True` and `False` are accepted.
+    pub bool_from_int: bool,
+    /// Decode integers of a `Value` as `Value::IntegerWide`, keeping the width they were encoded
+    /// with, such that they are encoded the same way again. Defaults to `false`, i.e. integers are
+    /// decoded as `Value::Integer` and encoded in the smallest way.
+    pub preserve_int_width: bool,
+    /// The maximum number of lists, dictionaries and structures nested into each other, or `None`
+    /// for no limit. Defaults to [`DEFAULT_MAX_DEPTH`](crate::options::DEFAULT_MAX_DEPTH), such
+    /// that hostile input cannot exhaust the stack, also when using the plain `decode`. Every
+    /// container which holds further values, including the derived structures, takes one level off with
+    /// [`descend`](crate::options::DecodeOptions::descend) before it decodes its items and fails
+    /// with `DecodeError::DepthLimitExceeded` once none is left. Since the options are passed down
+    /// explicitly, no state is kept besides the call stack.
+    pub max_depth: Option<usize>,
+    /// The maximum number of items a list or dictionary may declare, or `None` for no limit, which
+    /// is the default. A larger size is reported as `DecodeError::SizeLimitExceeded` before any
+    /// item is read.
+    pub max_collection_len: Option<usize>,
+    /// The maximum number of bytes a string or byte array may declare, or `None` for no limit,
+    /// which is the default. A larger size is reported as `DecodeError::SizeLimitExceeded` before
+    /// any byte of the body is read.
+    pub max_string_len: Option<usize>,
+    /// Reject a float which is NaN or infinite as `DecodeError::NonFiniteFloat`, e.g. where such
+    /// a value can only stem from corrupt data. Defaults to `false`, i.e. every float the
+    /// specification allows is accepted.
+    pub reject_nonfinite_floats: bool,
+    /// Reject a subnormal float, i.e. one too close to `0.0` to be represented with full precision,
+    /// as `DecodeError::SubnormalFloat`. Defaults to `false`.
+    pub reject_subnormal_floats: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            version: PackStreamVersion::default(),
+            bool_from_int: false,
+            preserve_int_width: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_collection_len: None,
+            max_string_len: None,
+            reject_nonfinite_floats: false,
+            reject_subnormal_floats: false,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// The options for the values one level deeper, i.e. the items of a list or dictionary or the
+    /// fields of a structure. Fails with `DepthLimitExceeded` if `max_depth` is used up:
+    /// ```
+    /// use packs::{DecodeOptions, DecodeError};
+    ///
+    /// let options = DecodeOptions { max_depth: Some(1), ..DecodeOptions::default() };
+    /// let inner = options.descend().unwrap();
+    ///
+    /// assert_eq!(inner.max_depth, Some(0));
+    /// assert!(matches!(inner.descend(), Err(DecodeError::DepthLimitExceeded)));
+    /// ```
+    pub fn descend(&self) -> Result<DecodeOptions, DecodeError> {
+        match self.max_depth {
+            None => Ok(*self),
+            Some(0) => Err(DecodeError::DepthLimitExceeded),
+            Some(depth) => Ok(DecodeOptions { max_depth: Some(depth - 1), ..*self }),
+        }
+    }
+
+    /// Checks the number of items declared by a list or dictionary against `max_collection_len`,
+    /// returning it if it is within the limit:
+    /// ```
+    /// use packs::{DecodeOptions, DecodeError};
+    ///
+    /// let options = DecodeOptions { max_collection_len: Some(100), ..DecodeOptions::default() };
+    ///
+    /// assert_eq!(options.check_collection_len(100).unwrap(), 100);
+    /// assert!(matches!(options.check_collection_len(101), Err(DecodeError::SizeLimitExceeded(101, 100))));
+    /// ```
+    pub fn check_collection_len(&self, len: usize) -> Result<usize, DecodeError> {
+        check_len(len, self.max_collection_len)
+    }
+
+    /// Checks the number of bytes declared by a string or byte array against `max_string_len`,
+    /// returning it if it is within the limit.
+    pub fn check_string_len(&self, len: usize) -> Result<usize, DecodeError> {
+        check_len(len, self.max_string_len)
+    }
+
+    /// Checks a decoded float against `reject_nonfinite_floats` and `reject_subnormal_floats`,
+    /// returning it if it is accepted:
+    /// ```
+    /// use packs::{DecodeOptions, DecodeError};
+    ///
+    /// let options = DecodeOptions { reject_nonfinite_floats: true, ..DecodeOptions::default() };
+    ///
+    /// assert_eq!(options.check_float(1.5).unwrap(), 1.5);
+    /// assert!(matches!(options.check_float(f64::NAN), Err(DecodeError::NonFiniteFloat(_))));
+    /// assert!(DecodeOptions::default().check_float(f64::INFINITY).is_ok());
+    /// ```
+    pub fn check_float(&self, x: f64) -> Result<f64, DecodeError> {
+        if self.reject_nonfinite_floats && !x.is_finite() {
+            return Err(DecodeError::NonFiniteFloat(x))
+        }
+        if self.reject_subnormal_floats && x.is_subnormal() {
+            return Err(DecodeError::SubnormalFloat(x))
+        }
+
+        Ok(x)
+    }
+
+    /// Like [`check_float`](crate::options::DecodeOptions::check_float) for a float narrowed to
+    /// an `f32`, which is subnormal in a range where the `f64` is not.
+    pub(crate) fn check_f32(&self, x: f32) -> Result<f32, DecodeError> {
+        if self.reject_nonfinite_floats && !x.is_finite() {
+            return Err(DecodeError::NonFiniteFloat(x as f64))
+        }
+        if self.reject_subnormal_floats && x.is_subnormal() {
+            return Err(DecodeError::SubnormalFloat(x as f64))
+        }
+
+        Ok(x)
+    }
+}
+
+fn check_len(len: usize, limit: Option<usize>) -> Result<usize, DecodeError> {
+    match limit {
+        Some(limit) if len > limit => Err(DecodeError::SizeLimitExceeded(len, limit)),
+        _ => Ok(len),
+    }
+}
+
+/// Options for encoding, see [`Pack::encode_with`](crate::packable::Pack::encode_with).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EncodeOptions {
+    pub version: PackStreamVersion,
+}
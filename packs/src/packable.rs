@@ -66,24 +66,83 @@
 //! Structures are packed with an extra tag byte to denote which structure is packed.
 
 
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::hash::Hash;
-use std::io::{Read, Write};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::error::{DecodeError, EncodeError};
+use crate::ll::io::CountingWriter;
 use crate::ll::bounds::{is_in_i16_bound, is_in_i32_bound, is_in_i8_bound, is_in_minus_tiny_int_bound, is_in_plus_tiny_int_bound};
 use crate::ll::marker::Marker;
-use crate::ll::types::fixed::{byte_to_minus_tiny_int, encode_i16, encode_i32, encode_i64, encode_i8, encode_minus_tiny_int, encode_plus_tiny_int, decode_body_i8, decode_body_i16, decode_body_i32, decode_body_i64, decode_body_f64, encode_f64};
-use crate::ll::types::lengths::{Length, read_size_16, read_size_32, read_size_8, read_string_size, read_list_size, read_dict_size};
-use crate::ll::types::sized::{write_body_by_iter};
+use crate::ll::types::fixed::{byte_to_minus_tiny_int, encode_i16, encode_i32, encode_i64, encode_i8, encode_minus_tiny_int, encode_plus_tiny_int, decode_body_i8, decode_body_i16, decode_body_i32, decode_body_i64, decode_body_f64, encode_f64, int_encoded_len, encode_int_as, IntWidth};
+use crate::ll::types::lengths::{Length, read_string_size, read_list_size, read_dict_size, read_bytes_size, header_len};
+use crate::ll::types::sized::{write_body_by_iter_with};
+use crate::options::{DecodeOptions, EncodeOptions, preallocated};
 use crate::value::Value;
 use crate::value::bytes::Bytes;
-use crate::value::dictionary::Dictionary;
+use crate::value::dictionary::{Dictionary, PropertyMap, validate_key_len};
 
 /// Trait to encode values into any writer using PackStream; using a space efficient way
 /// to pack.
-pub trait Pack: Sized {
+pub trait Pack {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError>;
+
+    /// Encodes the value following the provided [`EncodeOptions`](crate::options::EncodeOptions).
+    /// The default implementation ignores the options; containers forward them to their items and
+    /// types whose encoding depends on them override it.
+    fn encode_with<T: Write>(&self, writer: &mut T, _options: &EncodeOptions) -> Result<usize, EncodeError> {
+        self.encode(writer)
+    }
+
+    /// An estimate of how many bytes `encode` writes, used to preallocate buffers. It is meant as
+    /// an upper bound, e.g. an `i64` is estimated with `9` bytes, but nothing relies on it being
+    /// exact. Defaults to `0`.
+    fn size_hint(&self) -> usize {
+        0
+    }
+
+    /// The exact number of bytes `encode` writes for this value, without keeping them. The default
+    /// implementation encodes the value into a [`CountingWriter`](crate::ll::io::CountingWriter)
+    /// which discards the bytes; the base types and the derived implementations compute it
+    /// instead. If encoding fails, the bytes written up to the failure are counted.
+    /// ```
+    /// use packs::Pack;
+    ///
+    /// assert_eq!(42i64.encoded_len(), 1);
+    /// assert_eq!(String::from("hello").encoded_len(), 6);
+    /// ```
+    fn encoded_len(&self) -> usize {
+        let mut writer = CountingWriter::new(io::sink());
+        let _ = self.encode(&mut writer);
+        writer.count()
+    }
+
+    /// The exact number of bytes `encode_with` writes for this value using `options`. The default
+    /// implementation encodes the value into a [`CountingWriter`](crate::ll::io::CountingWriter),
+    /// types whose encoding depends on the options compute it instead.
+    fn encoded_len_with(&self, options: &EncodeOptions) -> usize {
+        let mut writer = CountingWriter::new(io::sink());
+        let _ = self.encode_with(&mut writer, options);
+        writer.count()
+    }
+
+    /// Encodes the value into a new `Vec`, with a capacity of
+    /// [`size_hint`](crate::packable::Pack::size_hint):
+    /// ```
+    /// use packs::Pack;
+    /// let bytes = String::from("hello").to_vec().unwrap();
+    ///
+    /// assert_eq!(bytes, vec!(0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F));
+    /// ```
+    fn to_vec(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buffer = Vec::with_capacity(self.size_hint());
+        self.encode(&mut buffer)?;
+        Ok(buffer)
+    }
 }
 
 /// Trait to decode values from a stream using PackStream.
@@ -93,6 +152,19 @@ pub trait Unpack: Sized {
         let marker = Marker::decode(reader)?;
         Self::decode_body(marker, reader)
     }
+
+    /// Decodes the body following the provided [`DecodeOptions`](crate::options::DecodeOptions).
+    /// The default implementation ignores the options; containers forward them to their items and
+    /// types whose decoding depends on them override it.
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, _options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Self::decode_body(marker, reader)
+    }
+
+    /// Decodes a value following the provided [`DecodeOptions`](crate::options::DecodeOptions).
+    fn decode_with<T: Read>(reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let marker = Marker::decode(reader)?;
+        Self::decode_body_with(marker, reader, options)
+    }
 }
 
 impl Unpack for i64 {
@@ -112,6 +184,63 @@ impl Unpack for i64 {
     }
 }
 
+/// Encodes the referenced value, such that a value which is only borrowed can be encoded without
+/// cloning it.
+impl<P: Pack + ?Sized> Pack for &P {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        P::encode(self, writer)
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        P::encode_with(self, writer, options)
+    }
+
+    fn size_hint(&self) -> usize {
+        P::size_hint(self)
+    }
+
+    fn encoded_len(&self) -> usize {
+        P::encoded_len(self)
+    }
+}
+
+/// Smart pointers are encoded as the value they point to; decoding decodes the value and wraps it.
+macro_rules! impl_pack_unpack_for_pointer {
+    ($ptr:ident) => {
+        impl<P: Pack + ?Sized> Pack for $ptr<P> {
+            fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+                P::encode(self, writer)
+            }
+
+            fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+                P::encode_with(self, writer, options)
+            }
+
+            fn size_hint(&self) -> usize {
+                P::size_hint(self)
+            }
+
+            fn encoded_len(&self) -> usize {
+                P::encoded_len(self)
+            }
+        }
+
+        impl<P: Unpack> Unpack for $ptr<P> {
+            fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+                Self::decode_body_with(marker, reader, &DecodeOptions::default())
+            }
+
+            fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+                Ok($ptr::new(P::decode_body_with(marker, reader, options)?))
+            }
+        }
+    }
+}
+
+impl_pack_unpack_for_pointer!(Box);
+impl_pack_unpack_for_pointer!(Rc);
+impl_pack_unpack_for_pointer!(Arc);
+
 impl Pack for i64 {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
         if is_in_plus_tiny_int_bound(*self) {
@@ -128,6 +257,14 @@ impl Pack for i64 {
             Ok(encode_i64(*self, writer)?)
         }
     }
+
+    fn size_hint(&self) -> usize {
+        9
+    }
+
+    fn encoded_len(&self) -> usize {
+        int_encoded_len(*self)
+    }
 }
 
 impl Unpack for i32 {
@@ -160,49 +297,230 @@ impl Pack for i32 {
             Ok(encode_i32(*self, writer)?)
         }
     }
+
+    fn size_hint(&self) -> usize {
+        5
+    }
+
+    fn encoded_len(&self) -> usize {
+        int_encoded_len(i64::from(*self))
+    }
+}
+
+/// Narrow and unsigned integers are encoded as the `i64` of the same value, i.e. a `u64` above
+/// `i64::MAX` cannot be encoded and is reported as `EncodeError::IntegerOutOfRange`. Decoding reads
+/// any integer and reports one which does not fit as `DecodeError::IntegerOutOfRange`.
+macro_rules! impl_pack_unpack_via_i64 {
+    ($ty:ty, $size_hint:expr) => {
+        impl Pack for $ty {
+            fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+                i64::try_from(*self)
+                    .map_err(|_| EncodeError::IntegerOutOfRange(i128::from(*self), 8))?
+                    .encode(writer)
+            }
+
+            fn size_hint(&self) -> usize {
+                $size_hint
+            }
+
+            fn encoded_len(&self) -> usize {
+                i64::try_from(*self).map_or(0, int_encoded_len)
+            }
+        }
+
+        impl Unpack for $ty {
+            fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+                let i = i64::decode_body(marker, reader)?;
+                <$ty>::try_from(i).map_err(|_| DecodeError::IntegerOutOfRange(i, stringify!($ty)))
+            }
+        }
+    }
 }
 
+impl_pack_unpack_via_i64!(i8, 2);
+impl_pack_unpack_via_i64!(i16, 3);
+impl_pack_unpack_via_i64!(u8, 3);
+impl_pack_unpack_via_i64!(u16, 5);
+impl_pack_unpack_via_i64!(u32, 9);
+impl_pack_unpack_via_i64!(u64, 9);
+
 impl Unpack for String {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let len = read_string_size(marker, reader)?;
-        let mut result = String::new();
-        reader.take(len as u64).read_to_string(&mut result)?;
-        Ok(result)
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
     }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_string_len(read_string_size(marker, reader)?)?;
+        let mut bytes = Vec::new();
+        if reader.take(len as u64).read_to_end(&mut bytes)? < len {
+            return Err(DecodeError::from(io::Error::from(io::ErrorKind::UnexpectedEof)))
+        }
+        String::from_utf8(bytes).map_err(|e| DecodeError::InvalidUtf8(e.utf8_error()))
+    }
+}
+
+fn encode_str<T: Write>(s: &str, writer: &mut T) -> Result<usize, EncodeError> {
+    let len = Length::from_usize(s.len()).expect("String has invalid length");
+    let mut written =
+        match len {
+            Length::Tiny(t) => Marker::TinyString(t as usize).encode(writer)?,
+            Length::Bit8(_) => Marker::String8.encode(writer)?,
+            Length::Bit16(_) => Marker::String16.encode(writer)?,
+            Length::Bit32(_) => Marker::String32.encode(writer)?,
+        };
+    written += len.encode(writer)?;
+    written += writer.write(s.as_bytes())?;
+
+    Ok(written)
 }
 
 impl Pack for String {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
-        let len = Length::from_usize(self.len()).expect("String has invalid length");
-        let mut written =
-            match len {
-                Length::Tiny(t) => Marker::TinyString(t as usize).encode(writer)?,
-                Length::Bit8(_) => Marker::String8.encode(writer)?,
-                Length::Bit16(_) => Marker::String16.encode(writer)?,
-                Length::Bit32(_) => Marker::String32.encode(writer)?,
-            };
-        written += len.encode(writer)?;
-        written += writer.write(self.as_bytes())?;
+        encode_str(self, writer)
+    }
 
-        Ok(written)
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.len()
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.size_hint()
+    }
+}
+
+/// Encodes the borrowed or owned string alike, such that a `&str` can be encoded without
+/// allocating a `String` first.
+impl<'a> Pack for Cow<'a, str> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        encode_str(self, writer)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.len()
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.size_hint()
+    }
+}
+
+/// Decodes into `Cow::Owned`.
+impl Unpack for Cow<'static, str> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Ok(Cow::Owned(String::decode_body_with(marker, reader, options)?))
+    }
+}
+
+/// A `char` is encoded as a string of this one character.
+impl Pack for char {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        encode_str(self.encode_utf8(&mut [0; 4]), writer)
+    }
+
+    fn size_hint(&self) -> usize {
+        1 + self.len_utf8()
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.size_hint()
+    }
+}
+
+/// Decodes a string of exactly one character, reporting any other string as
+/// `ExpectedSingleChar`.
+impl Unpack for char {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let s = String::decode_body_with(marker, reader, options)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(DecodeError::ExpectedSingleChar(s)),
+        }
+    }
+}
+
+/// OS strings and paths are encoded as strings, which PackStream requires to be UTF-8. Hence one
+/// which is not valid UTF-8 cannot be encoded and fails with `NonUtf8Path`, instead of being
+/// changed by a lossy conversion.
+#[cfg(feature = "os")]
+macro_rules! impl_pack_unpack_for_os_string {
+    ($ty:ty) => {
+        impl Pack for $ty {
+            fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+                let s = self.to_str().ok_or_else(|| EncodeError::NonUtf8Path(self.clone().into()))?;
+                encode_str(s, writer)
+            }
+
+            fn size_hint(&self) -> usize {
+                let len = self.as_os_str().len();
+                header_len(len) + len
+            }
+
+            fn encoded_len(&self) -> usize {
+                match self.to_str() {
+                    Some(s) => header_len(s.len()) + s.len(),
+                    None => 0,
+                }
+            }
+        }
+
+        impl Unpack for $ty {
+            fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+                Self::decode_body_with(marker, reader, &DecodeOptions::default())
+            }
+
+            fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+                Ok(<$ty>::from(String::decode_body_with(marker, reader, options)?))
+            }
+        }
     }
 }
 
+#[cfg(feature = "os")]
+impl_pack_unpack_for_os_string!(std::ffi::OsString);
+#[cfg(feature = "os")]
+impl_pack_unpack_for_os_string!(std::path::PathBuf);
+
 impl<P: Pack> Pack for Vec<P> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
         let len = Length::from_usize(self.len()).expect("Vec has invalid size");
         let mut written = len.encode_as_list_size(writer)?;
-        written += write_body_by_iter(&mut self.iter(), writer)?;
+        written += write_body_by_iter_with(&mut self.iter(), writer, options)?;
         Ok(written)
     }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::encoded_len).sum::<usize>()
+    }
 }
 
 impl<P: Unpack> Unpack for Vec<P> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let len = read_list_size(marker, reader)?;
-        let mut result = Vec::with_capacity(len);
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_list_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = Vec::with_capacity(preallocated(len));
         for _ in 0..len {
-            let p = P::decode(reader)?;
+            let p = P::decode_with(reader, options)?;
             result.push(p);
         }
 
@@ -211,13 +529,154 @@ impl<P: Unpack> Unpack for Vec<P> {
 }
 
 
+impl<P: Pack, const N: usize> Pack for [P; N] {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let len = Length::from_usize(N).expect("Array has invalid size");
+        let mut written = len.encode_as_list_size(writer)?;
+        written += write_body_by_iter_with(&mut self.iter(), writer, options)?;
+        Ok(written)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(N) + self.iter().map(P::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(N) + self.iter().map(P::encoded_len).sum::<usize>()
+    }
+}
+
+/// Decodes a list of exactly `N` items into an array, reporting any other length as
+/// `UnexpectedNumberOfElements`.
+impl<P: Unpack, const N: usize> Unpack for [P; N] {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = read_list_size(marker, reader)?;
+        if len != N {
+            return Err(DecodeError::UnexpectedNumberOfElements(N, len))
+        }
+
+        let options = &options.descend()?;
+        let mut items = Vec::with_capacity(N);
+        for _ in 0..N {
+            items.push(P::decode_with(reader, options)?);
+        }
+
+        Ok(<[P; N]>::try_from(items).unwrap_or_else(|_| unreachable!("exactly N items have been decoded")))
+    }
+}
+
+/// Tuples are encoded as lists with one item per component, in order. Decoding reads a list of
+/// exactly as many items and reports any other length as `UnexpectedNumberOfElements`.
+macro_rules! impl_pack_unpack_for_tuple {
+    ($len:expr; $($name:ident $idx:tt),+) => {
+        impl<$($name: Pack),+> Pack for ($($name,)+) {
+            fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+                self.encode_with(writer, &EncodeOptions::default())
+            }
+
+            fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+                let mut written = Marker::TinyList($len).encode(writer)?;
+                $(written += self.$idx.encode_with(writer, options)?;)+
+                Ok(written)
+            }
+
+            fn size_hint(&self) -> usize {
+                1 $(+ self.$idx.size_hint())+
+            }
+
+            fn encoded_len(&self) -> usize {
+                1 $(+ self.$idx.encoded_len())+
+            }
+        }
+
+        impl<$($name: Unpack),+> Unpack for ($($name,)+) {
+            fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+                Self::decode_body_with(marker, reader, &DecodeOptions::default())
+            }
+
+            fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+                let len = read_list_size(marker, reader)?;
+                if len != $len {
+                    return Err(DecodeError::UnexpectedNumberOfElements($len, len))
+                }
+
+                let options = &options.descend()?;
+                Ok(($($name::decode_with(reader, options)?,)+))
+            }
+        }
+    }
+}
+
+impl_pack_unpack_for_tuple!(2; A 0, B 1);
+impl_pack_unpack_for_tuple!(3; A 0, B 1, C 2);
+impl_pack_unpack_for_tuple!(4; A 0, B 1, C 2, D 3);
+impl_pack_unpack_for_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_pack_unpack_for_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_pack_unpack_for_tuple!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_pack_unpack_for_tuple!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+#[cfg(feature = "smallvec")]
+impl<P: Pack, const N: usize> Pack for smallvec::SmallVec<[P; N]> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let len = Length::from_usize(self.len()).expect("SmallVec has invalid size");
+        let mut written = len.encode_as_list_size(writer)?;
+        written += write_body_by_iter_with(&mut self.iter(), writer, options)?;
+        Ok(written)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::encoded_len).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+/// Decodes a list into a `SmallVec`, which keeps up to `N` items inline and allocates only for
+/// longer lists.
+impl<P: Unpack, const N: usize> Unpack for smallvec::SmallVec<[P; N]> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_list_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = smallvec::SmallVec::with_capacity(preallocated(len));
+        for _ in 0..len {
+            result.push(P::decode_with(reader, options)?);
+        }
+
+        Ok(result)
+    }
+}
+
 impl<P: Unpack> Unpack for HashMap<String, P> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let len = read_dict_size(marker, reader)?;
-        let mut result = HashMap::with_capacity(len);
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_dict_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = HashMap::with_capacity(preallocated(len));
         for _ in 0..len {
-            let key = String::decode(reader)?;
-            let val = P::decode(reader)?;
+            let key = String::decode_with(reader, options)?;
+            let val = P::decode_with(reader, options)?;
             result.insert(key, val);
         }
 
@@ -227,39 +686,146 @@ impl<P: Unpack> Unpack for HashMap<String, P> {
 
 impl<P: Pack> Pack for HashMap<String, P> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        self.keys().try_for_each(|key| validate_key_len(key.len()))?;
+
         let len = Length::from_usize(self.len()).expect("HashMap has invalid length");
         let mut written = len.encode_as_dict_size(writer)?;
 
         for (key, val) in self {
             written +=
                 key.encode(writer)?
-                    + val.encode(writer)?;
+                    + val.encode_with(writer, options)?;
         }
 
         Ok(written)
     }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len())
+            + self.iter().map(|(k, v)| k.size_hint() + v.size_hint()).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self.iter().map(|(k, v)| k.encoded_len() + v.encoded_len()).sum::<usize>()
+    }
+}
+
+/// Encodes the entries sorted by key, such that equal maps always lead to the same bytes.
+impl<P: Pack> Pack for BTreeMap<String, P> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        self.keys().try_for_each(|key| validate_key_len(key.len()))?;
+
+        let len = Length::from_usize(self.len()).expect("BTreeMap has invalid length");
+        let mut written = len.encode_as_dict_size(writer)?;
+
+        for (key, val) in self {
+            written +=
+                key.encode(writer)?
+                    + val.encode_with(writer, options)?;
+        }
+
+        Ok(written)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len())
+            + self.iter().map(|(k, v)| k.size_hint() + v.size_hint()).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self.iter().map(|(k, v)| k.encoded_len() + v.encoded_len()).sum::<usize>()
+    }
+}
+
+impl<P: Unpack> Unpack for BTreeMap<String, P> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_dict_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = BTreeMap::new();
+        for _ in 0..len {
+            let key = String::decode_with(reader, options)?;
+            let val = P::decode_with(reader, options)?;
+            result.insert(key, val);
+        }
+
+        Ok(result)
+    }
 }
 
 impl<P: Unpack> Unpack for Dictionary<P> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let inner =
-            <HashMap<String, Value<P>>>::decode_body(marker, reader)?;
-        Ok(Dictionary::from_inner(inner))
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_dict_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = PropertyMap::with_capacity(preallocated(len));
+        for _ in 0..len {
+            let key = String::decode_with(reader, options)?;
+            let val = Value::decode_with(reader, options)?;
+            result.insert(key, val);
+        }
+
+        Ok(Dictionary::from_map(result))
     }
 }
 
 impl<P: Pack> Pack for Dictionary<P> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
-        self.inner().encode(writer)
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        self.validate_keys()?;
+        let len = Length::from_usize(self.len()).expect("Dictionary has invalid length");
+        let mut written = len.encode_as_dict_size(writer)?;
+
+        for (key, val) in self.properties() {
+            written +=
+                key.encode(writer)?
+                    + val.encode_with(writer, options)?;
+        }
+
+        Ok(written)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len())
+            + self.properties().map(|(k, v)| k.size_hint() + v.size_hint()).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self.properties().map(|(k, v)| k.encoded_len() + v.encoded_len()).sum::<usize>()
     }
 }
 
 impl<P: Unpack + Hash + Eq> Unpack for HashSet<P> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let len = read_list_size(marker, reader)?;
-        let mut result = HashSet::with_capacity(len);
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_collection_len(read_list_size(marker, reader)?)?;
+        let options = &options.descend()?;
+        let mut result = HashSet::with_capacity(preallocated(len));
         for _ in 0..len {
-            let p = P::decode(reader)?;
+            let p = P::decode_with(reader, options)?;
             result.insert(p);
         }
 
@@ -269,24 +835,75 @@ impl<P: Unpack + Hash + Eq> Unpack for HashSet<P> {
 
 impl<P: Pack> Pack for HashSet<P> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
         let len = Length::from_usize(self.len()).expect("HashSet has invalid length");
         let mut written = len.encode_as_list_size(writer)?;
-        written += write_body_by_iter(&mut self.iter(), writer)?;
+        written += write_body_by_iter_with(&mut self.iter(), writer, options)?;
 
         Ok(written)
     }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::encoded_len).sum::<usize>()
+    }
+}
+
+/// Encodes the items as a list in ascending order rather than in the internal order of the heap,
+/// such that equal heaps always lead to the same bytes.
+impl<P: Pack + Ord> Pack for BinaryHeap<P> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let mut items: Vec<&P> = self.iter().collect();
+        items.sort();
+
+        let len = Length::from_usize(items.len()).expect("BinaryHeap has invalid length");
+        let mut written = len.encode_as_list_size(writer)?;
+        written += write_body_by_iter_with(&mut items.iter(), writer, options)?;
+
+        Ok(written)
+    }
+
+    fn size_hint(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(P::encoded_len).sum::<usize>()
+    }
+}
+
+/// Decodes a list of any order into a new heap.
+impl<P: Unpack + Ord> Unpack for BinaryHeap<P> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        Vec::decode_body_with(marker, reader, options).map(BinaryHeap::from)
+    }
 }
 
 impl Unpack for Bytes {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
-        let len = match marker {
-            Marker::Bytes8 => read_size_8(reader)?,
-            Marker::Bytes16 => read_size_16(reader)?,
-            Marker::Bytes32 => read_size_32(reader)?,
-            _ => Err(DecodeError::UnexpectedMarker(marker))?,
-        };
-        let mut res = vec![0; len];
-        reader.read_exact(&mut res)?;
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let len = options.check_string_len(read_bytes_size(marker, reader)?)?;
+        let mut res = Vec::new();
+        if reader.take(len as u64).read_to_end(&mut res)? < len {
+            return Err(DecodeError::from(io::Error::from(io::ErrorKind::UnexpectedEof)))
+        }
         Ok(Bytes(res))
     }
 }
@@ -308,6 +925,15 @@ impl Pack for Bytes {
         written += writer.write(self.0.as_slice())?;
         Ok(written)
     }
+
+    fn size_hint(&self) -> usize {
+        // tiny sizes are encoded as `Bytes8` as well:
+        header_len(self.0.len()).max(2) + self.0.len()
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.size_hint()
+    }
 }
 
 impl Unpack for f64 {
@@ -318,25 +944,71 @@ impl Unpack for f64 {
             Err(DecodeError::UnexpectedMarker(marker))
         }
     }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        options.check_float(Self::decode_body(marker, reader)?)
+    }
 }
 
 impl Pack for f64 {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
         Ok(encode_f64(*self, writer)?)
     }
+
+    fn size_hint(&self) -> usize {
+        9
+    }
+
+    fn encoded_len(&self) -> usize {
+        9
+    }
+}
+
+/// PackStream has no 32 bit float, hence an `f32` is read from a `Float64` and converted to the
+/// nearest `f32`. This is lossy for values which are not an `f32` to begin with; values too large
+/// become an infinity. Every `f32` survives being encoded and decoded again.
+impl Unpack for f32 {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Ok(f64::decode_body(marker, reader)? as f32)
+    }
+
+    /// The float is checked against the options after narrowing it, as a finite `f64` can become
+    /// infinite or subnormal as an `f32`.
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        options.check_f32(f64::decode_body_with(marker, reader, options)? as f32)
+    }
 }
 
 impl Pack for f32 {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
         (*self as f64).encode(writer)
     }
+
+    fn size_hint(&self) -> usize {
+        9
+    }
+
+    fn encoded_len(&self) -> usize {
+        9
+    }
 }
 
 impl Unpack for bool {
-    fn decode_body<T: Read>(marker: Marker, _: &mut T) -> Result<Self, DecodeError> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
         match marker {
             Marker::True => Ok(true),
             Marker::False => Ok(false),
+            Marker::PlusTinyInt(_) | Marker::MinusTinyInt(_) | Marker::Int8 if options.bool_from_int => {
+                match i64::decode_body(marker, reader)? {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    i => Err(DecodeError::IntegerNotBool(i)),
+                }
+            }
             _ => Err(DecodeError::UnexpectedMarker(marker))
         }
     }
@@ -352,25 +1024,49 @@ impl Pack for bool {
             Ok(1)
         }
     }
+
+    fn size_hint(&self) -> usize {
+        1
+    }
+
+    fn encoded_len(&self) -> usize {
+        1
+    }
 }
 
 impl<P: Pack> Pack for Option<P> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
         if let Some(p) = self {
-            P::encode(p, writer)
+            P::encode_with(p, writer, options)
         } else {
             Marker::Null.encode(writer)?;
             Ok(1)
         }
     }
+
+    fn size_hint(&self) -> usize {
+        self.as_ref().map_or(1, P::size_hint)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.as_ref().map_or(1, P::encoded_len)
+    }
 }
 
 impl<P: Unpack> Unpack for Option<P> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
         match marker {
             Marker::Null => Ok(None),
             _ => {
-                P::decode_body(marker, reader).map(Some)
+                P::decode_body_with(marker, reader, options).map(Some)
             }
         }
     }
@@ -378,41 +1074,51 @@ impl<P: Unpack> Unpack for Option<P> {
 
 impl<S: Unpack> Unpack for Value<S> {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
         match marker {
             Marker::Null => Ok(Value::Null),
             Marker::True => Ok(Value::Boolean(true)),
             Marker::False => Ok(Value::Boolean(false)),
 
-            Marker::Float64 => Ok(Value::Float(f64::decode_body(marker, reader)?)),
+            Marker::Float64 => Ok(Value::Float(f64::decode_body_with(marker, reader, options)?)),
 
             Marker::PlusTinyInt(_) |
             Marker::MinusTinyInt(_) |
             Marker::Int8 |
             Marker::Int16 |
             Marker::Int32 |
-            Marker::Int64 => Ok(Value::Integer(i64::decode_body(marker, reader)?)),
+            Marker::Int64 => {
+                let i = i64::decode_body(marker, reader)?;
+                match IntWidth::of_marker(marker) {
+                    Some(width) if options.preserve_int_width => Ok(Value::IntegerWide(i, width)),
+                    _ => Ok(Value::Integer(i)),
+                }
+            },
 
             Marker::TinyString(_) |
             Marker::String8 |
             Marker::String16 |
-            Marker::String32 => Ok(Value::String(String::decode_body(marker, reader)?)),
+            Marker::String32 => Ok(Value::String(String::decode_body_with(marker, reader, options)?)),
 
             Marker::TinyList(_) |
             Marker::List8 |
             Marker::List16 |
-            Marker::List32 => Ok(Value::List(Vec::decode_body(marker, reader)?)),
+            Marker::List32 => Ok(Value::List(Vec::decode_body_with(marker, reader, options)?)),
 
             Marker::TinyDictionary(_) |
             Marker::Dictionary8 |
             Marker::Dictionary16 |
-            Marker::Dictionary32 => Ok(Value::Dictionary(Dictionary::decode_body(marker, reader)?)),
+            Marker::Dictionary32 => Ok(Value::Dictionary(Dictionary::decode_body_with(marker, reader, options)?)),
 
             Marker::Bytes8 |
             Marker::Bytes16 |
-            Marker::Bytes32 => Ok(Value::Bytes(Bytes::decode_body(marker, reader)?)),
+            Marker::Bytes32 => Ok(Value::Bytes(Bytes::decode_body_with(marker, reader, options)?)),
 
             Marker::Structure(_, _) => {
-                Ok(Value::Structure(S::decode_body(marker, reader)?))
+                Ok(Value::Structure(S::decode_body_with(marker, reader, options)?))
             }
         }
     }
@@ -420,25 +1126,63 @@ impl<S: Unpack> Unpack for Value<S> {
 
 impl<S: Pack> Pack for Value<S> {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
         match self {
             Value::Null => Ok(Marker::Null.encode(writer)?),
             Value::Boolean(b) => bool::encode(b, writer),
             Value::Integer(i) => i64::encode(i, writer),
+            Value::IntegerWide(i, width) => encode_int_as(*i, *width, writer),
             Value::Float(f) => f64::encode(f, writer),
             Value::String(s) => String::encode(s, writer),
             Value::Bytes(bs) => Bytes::encode(bs, writer),
-            Value::Dictionary(d) => <Dictionary<S>>::encode(d, writer),
-            Value::List(l) => <Vec<Value<S>>>::encode(l, writer),
+            Value::Dictionary(d) => <Dictionary<S>>::encode_with(d, writer, options),
+            Value::List(l) => <Vec<Value<S>>>::encode_with(l, writer, options),
             Value::Structure(s) => {
-                s.encode(writer)
+                s.encode_with(writer, options)
             }
         }
     }
+
+    fn size_hint(&self) -> usize {
+        match self {
+            Value::Null => 1,
+            Value::Boolean(b) => b.size_hint(),
+            Value::Integer(i) => i.size_hint(),
+            Value::IntegerWide(_, width) => 1 + width.bytes(),
+            Value::Float(f) => f.size_hint(),
+            Value::String(s) => s.size_hint(),
+            Value::Bytes(bs) => bs.size_hint(),
+            Value::Dictionary(d) => d.size_hint(),
+            Value::List(l) => l.size_hint(),
+            Value::Structure(s) => s.size_hint(),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Value::Null => 1,
+            Value::Boolean(b) => b.encoded_len(),
+            Value::Integer(i) => i.encoded_len(),
+            Value::IntegerWide(_, width) => 1 + width.bytes(),
+            Value::Float(f) => f.encoded_len(),
+            Value::String(s) => s.encoded_len(),
+            Value::Bytes(bs) => bs.encoded_len(),
+            Value::Dictionary(d) => d.encoded_len(),
+            Value::List(l) => l.encoded_len(),
+            Value::Structure(s) => s.encoded_len(),
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod test {
-    use std::collections::HashMap;
+    use std::borrow::Cow;
+    use std::collections::{BTreeMap, BinaryHeap, HashMap};
+    use std::rc::Rc;
+    use std::sync::Arc;
     use std::fmt::Debug;
     use std::io::Cursor;
 
@@ -446,6 +1190,8 @@ pub mod test {
     use crate::packable::{Pack, Unpack};
     use crate::structure::NoStruct;
     use crate::value::Value;
+    use crate::{DecodeError, EncodeError, DecodeOptions, Marker, Dictionary, Bytes};
+    use crate::ll::types::fixed::IntWidth;
 
     pub fn unpack_pack_test<T: Unpack + Pack>(mut buffer: &[u8]) {
         let compare = Vec::from(buffer);
@@ -466,13 +1212,13 @@ pub mod test {
             let mut buffer: Vec<u8> = Vec::new();
             value
                 .encode(&mut buffer)
-                .expect(&format!("cannot encode '{:?}'", value));
+                .unwrap_or_else(|_| panic!("cannot encode '{:?}'", value));
 
 
             let mut cursor = Cursor::new(buffer.clone());
             let res =
                 T::decode(&mut cursor)
-                    .expect(&format!("cannot decode back to '{:?}'", value));
+                    .unwrap_or_else(|_| panic!("cannot decode back to '{:?}'", value));
             assert_eq!(value,
                        &res,
                        "'{:?}' got packed->unpacked into '{:?}'",
@@ -495,7 +1241,7 @@ pub mod test {
     }
 
     pub fn unpack_to_test<T: Unpack + Debug + PartialEq>(bytes: &[u8], value: T) {
-        assert!(bytes.len() > 0, "Input bytes cannot be empty.");
+        assert!(!bytes.is_empty(), "Input bytes cannot be empty.");
 
         let mut cursor : Cursor<&[u8]> = Cursor::new(bytes);
         let res = T::decode(&mut cursor).unwrap();
@@ -577,12 +1323,64 @@ pub mod test {
                  "ß++°",
                  "Lorem ipsum dolor sit amet, consetetur sadipscing elitr, sed diam nonumy eirmod tempor invidunt ut labore et dolore magna aliquyam erat, sed diam voluptua. At vero eos et accusam et justo duo dolores et ea rebum. Stet clita kasd gubergren, no sea takimata sanctus est Lorem ipsum dolor sit amet. Lorem ipsum dolor sit amet, consetetur sadipscing elitr, sed diam nonumy eirmod tempor invidunt ut labore et dolore magna aliquyam erat, sed diam voluptua. At vero eos et accusam et justo duo dolores et ea rebum. Stet clita kasd gubergren, no sea takimata sanctus est Lorem ipsum dolor sit amet.")
                 .into_iter()
-                .map(|s| String::from(s))
+                .map(String::from)
                 .collect();
 
         pack_unpack_test(&strings);
     }
 
+    #[test]
+    fn pack_references() {
+        let string = String::from("x");
+        assert_eq!(<&i64>::to_vec(&&42).unwrap(), 42i64.to_vec().unwrap());
+        assert_eq!(<&String>::to_vec(&&string).unwrap(), string.to_vec().unwrap());
+        assert_eq!(<&&String>::encoded_len(&&&string), string.encoded_len());
+
+        let list: Vec<&String> = vec!(&string, &string);
+        assert_eq!(list.to_vec().unwrap(), vec!(string.clone(), string).to_vec().unwrap());
+    }
+
+    #[test]
+    fn pack_unpack_chars() {
+        pack_unpack_test(&['a', 'ß', '€', '🦀', '\0']);
+        encoded_len_test(&['a', 'ß', '€', '🦀']);
+        pack_to_test('ß', &[0x82, 0xC3, 0x9F]);
+
+        for s in &["", "ab", "ß€"] {
+            match char::decode(&mut String::from(*s).to_vec().unwrap().as_slice()) {
+                Err(DecodeError::ExpectedSingleChar(e)) => assert_eq!(&e, s),
+                r => panic!("Expected ExpectedSingleChar, got {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn pack_unpack_pointers() {
+        pack_unpack_test(&[Box::new(0i64), Box::new(-42), Box::new(i64::MAX)]);
+        pack_unpack_test(&[Arc::new(String::from("shared")), Arc::new(String::new())]);
+        pack_unpack_test(&[Rc::new(vec!(1i64, 2, 3))]);
+        encoded_len_test(&[Arc::new(String::from("shared"))]);
+
+        assert_eq!(Box::new(42i64).to_vec().unwrap(), 42i64.to_vec().unwrap());
+        assert_eq!(Arc::new(String::from("x")).to_vec().unwrap(), String::from("x").to_vec().unwrap());
+    }
+
+    #[test]
+    fn pack_unpack_cow_strings() {
+        let owned = String::from("ß++° owned");
+        let strings: Vec<Cow<'static, str>> =
+            vec!(Cow::Borrowed("hello world"), Cow::Borrowed(""), Cow::Owned(owned.clone()));
+
+        pack_unpack_test(&strings);
+        encoded_len_test(&strings);
+
+        let borrowed: Cow<'_, str> = Cow::Borrowed(owned.as_str());
+        assert_eq!(borrowed.to_vec().unwrap(), owned.to_vec().unwrap());
+        assert!(matches!(
+            <Cow<'static, str>>::decode(&mut owned.to_vec().unwrap().as_slice()),
+            Ok(Cow::Owned(s)) if s == owned));
+    }
+
     #[test]
     fn pack_unpack_f64() {
         pack_unpack_test(&[0.3, 0.42, -1.0, 0.33333, -455402.1]);
@@ -605,6 +1403,123 @@ pub mod test {
         pack_unpack_test(&[true, false]);
     }
 
+    #[test]
+    fn reject_floats() {
+        let nonfinite = DecodeOptions { reject_nonfinite_floats: true, ..DecodeOptions::default() };
+        let subnormal = DecodeOptions { reject_subnormal_floats: true, ..DecodeOptions::default() };
+        let tiny = f64::MIN_POSITIVE / 2.0;
+
+        for x in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY, tiny, 1.5] {
+            let bytes = x.to_vec().unwrap();
+            // permissive by default:
+            assert!(f64::decode(&mut bytes.as_slice()).is_ok());
+
+            match f64::decode_with(&mut bytes.as_slice(), &nonfinite) {
+                Err(DecodeError::NonFiniteFloat(y)) => assert!(!x.is_finite() && (y == *x || y.is_nan())),
+                Ok(y) => assert!(x.is_finite() && y == *x),
+                r => panic!("Expected NonFiniteFloat, got {:?}", r),
+            }
+
+            match f64::decode_with(&mut bytes.as_slice(), &subnormal) {
+                Err(DecodeError::SubnormalFloat(y)) => assert!(x.is_subnormal() && y == *x),
+                Ok(_) => assert!(!x.is_subnormal()),
+                r => panic!("Expected SubnormalFloat, got {:?}", r),
+            }
+        }
+
+        // within values and for f32 as well:
+        let bytes = Value::<NoStruct>::List(vec!(Value::Float(f64::NAN))).to_vec().unwrap();
+        assert!(matches!(
+            <Value<NoStruct>>::decode_with(&mut bytes.as_slice(), &nonfinite),
+            Err(DecodeError::NonFiniteFloat(_))));
+        let bytes = f64::INFINITY.to_vec().unwrap();
+        assert!(matches!(f32::decode_with(&mut bytes.as_slice(), &nonfinite), Err(DecodeError::NonFiniteFloat(_))));
+
+        // finite as `f64` only:
+        let bytes = 1e300f64.to_vec().unwrap();
+        assert!(matches!(f32::decode_with(&mut bytes.as_slice(), &nonfinite), Err(DecodeError::NonFiniteFloat(_))));
+        let bytes = 1e-40f64.to_vec().unwrap();
+        assert!(f64::decode_with(&mut bytes.as_slice(), &subnormal).is_ok());
+        assert!(matches!(f32::decode_with(&mut bytes.as_slice(), &subnormal), Err(DecodeError::SubnormalFloat(_))));
+    }
+
+    #[test]
+    fn unpack_bool_from_int() {
+        let options = DecodeOptions { bool_from_int: true, ..DecodeOptions::default() };
+
+        assert!(!bool::decode_with(&mut [0x00u8].as_ref(), &options).unwrap());
+        assert!(bool::decode_with(&mut [0x01u8].as_ref(), &options).unwrap());
+        assert!(bool::decode_with(&mut [0xC8u8, 0x01].as_ref(), &options).unwrap());
+        assert!(bool::decode_with(&mut [0xC2u8].as_ref(), &options).unwrap());
+
+        match bool::decode_with(&mut [0x02u8].as_ref(), &options) {
+            Err(DecodeError::IntegerNotBool(2)) => (),
+            r => panic!("Expected IntegerNotBool, got {:?}", r),
+        }
+
+        match bool::decode(&mut [0x01u8].as_ref()) {
+            Err(DecodeError::UnexpectedMarker(Marker::PlusTinyInt(1))) => (),
+            r => panic!("Expected UnexpectedMarker, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn size_hint_bounds_encoded_len() {
+        let mut dict = Dictionary::new();
+        dict.add_property("name", "a name which is longer than fifteen bytes");
+        dict.add_property("ids", vec!(Value::Integer(1), Value::Integer(-3000000000)));
+        dict.add_property("blob", Value::Bytes(Bytes(vec!(0x01, 0x02))));
+        let value : Value<NoStruct> = Value::Dictionary(dict);
+
+        let bytes = value.to_vec().unwrap();
+        assert!(value.size_hint() >= bytes.len(), "{} < {}", value.size_hint(), bytes.len());
+        assert_eq!(String::from("hello").size_hint(), 6);
+    }
+
+    #[cfg(feature = "os")]
+    #[test]
+    fn pack_unpack_os_strings() {
+        use std::ffi::OsString;
+        use std::path::PathBuf;
+
+        pack_unpack_test(&[OsString::from("hello"), OsString::new()]);
+        pack_unpack_test(&[PathBuf::from("/tmp/ß.txt"), PathBuf::from("relative/dir")]);
+        encoded_len_test(&[PathBuf::from("/tmp/ß.txt")]);
+        assert_eq!(PathBuf::from("a").to_vec().unwrap(), String::from("a").to_vec().unwrap());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+
+            let invalid = OsString::from_vec(vec!(0x66, 0x6F, 0xFF));
+            match invalid.to_vec() {
+                Err(EncodeError::NonUtf8Path(path)) => assert_eq!(path, invalid),
+                r => panic!("Expected NonUtf8Path, got {:?}", r),
+            }
+            assert!(PathBuf::from(invalid).to_vec().is_err());
+        }
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn unpack_smallvec_spills() {
+        use smallvec::SmallVec;
+
+        let small : SmallVec<[i64; 4]> = SmallVec::decode(&mut [0x92u8, 0x01, 0x02].as_ref()).unwrap();
+        assert_eq!(small.as_slice(), &[1, 2]);
+        assert!(!small.spilled());
+
+        let large_list : Vec<i64> = (0..20).collect();
+        let bytes = large_list.to_vec().unwrap();
+        let large : SmallVec<[i64; 4]> = SmallVec::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(large.as_slice(), large_list.as_slice());
+        assert!(large.spilled());
+
+        pack_unpack_test::<SmallVec<[String; 2]>>(&[
+            SmallVec::new(),
+            SmallVec::from_vec(vec!(String::from("a"), String::from("b"), String::from("c")))]);
+    }
+
     #[test]
     fn pack_unpack_vec_int() {
         pack_unpack_test(
@@ -655,6 +1570,22 @@ pub mod test {
         )
     }
 
+    #[test]
+    fn pack_unpack_binary_heap() {
+        let heap: BinaryHeap<i64> = vec!(3, -1, 42, 3, 0).into_iter().collect();
+        let bytes = heap.to_vec().unwrap();
+        assert_eq!(bytes, vec!(-1i64, 0, 3, 3, 42).to_vec().unwrap());
+        assert_eq!(bytes.len(), heap.encoded_len());
+
+        let decoded = BinaryHeap::<i64>::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded.into_sorted_vec(), heap.into_sorted_vec());
+
+        // the order of the list does not matter:
+        let bytes = vec!(5i64, 1, 9).to_vec().unwrap();
+        let mut decoded = BinaryHeap::<i64>::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded.pop(), Some(9));
+    }
+
     #[test]
     fn pack_unpack_hashmap_int() {
         pack_unpack_test(
@@ -681,4 +1612,256 @@ pub mod test {
         let mut buffer: Vec<u8> = Vec::new();
         value.encode(&mut buffer).unwrap();
     }
-}
\ No newline at end of file
+
+    pub fn encoded_len_test<T: Pack + Debug>(values: &[T]) {
+        for value in values {
+            let mut buffer = Vec::new();
+            value.encode(&mut buffer).unwrap();
+            assert_eq!(value.encoded_len(), buffer.len(), "encoded_len of '{:?}'", value);
+        }
+    }
+
+    #[test]
+    fn encoded_len_matches_encode() {
+        encoded_len_test(&[
+            0i64, 126, 127, 128, -16, -17, -128, -129, 32767, 32768, -32769,
+            i32::MAX as i64, i32::MIN as i64 - 1, i64::MAX, i64::MIN,
+        ]);
+        encoded_len_test(&[0i32, 127, -200, 40000, i32::MIN]);
+        encoded_len_test(&[true, false]);
+        encoded_len_test(&[0.5f64, f64::NAN]);
+        encoded_len_test(&[0.5f32]);
+        encoded_len_test(&[0usize, 15, 16, 255, 256, 65535, 65536].iter()
+            .map(|n| "x".repeat(*n)).collect::<Vec<_>>());
+        encoded_len_test(&[0usize, 15, 16, 256].iter()
+            .map(|n| Bytes(vec![0; *n])).collect::<Vec<_>>());
+        encoded_len_test(&[None, Some(300i64)]);
+        encoded_len_test(&[vec!(), vec!(1i64; 16), vec!(1000i64; 300)]);
+
+        let mut map = HashMap::new();
+        map.insert(String::from("a"), 1i64);
+        map.insert(String::from("b"), 100000i64);
+        encoded_len_test(&[map]);
+
+        let mut dict = Dictionary::new();
+        dict.add_property("list", vec!(Value::Integer(-1), Value::Null, Value::Float(1.0)));
+        dict.add_property("bytes", Value::Bytes(Bytes(vec!(1, 2, 3))));
+        encoded_len_test(&[
+            Value::<NoStruct>::Dictionary(dict),
+            Value::Boolean(false),
+            Value::from("hello"),
+        ]);
+    }
+
+    #[test]
+    fn preserve_int_width_through_value() {
+        let bytes : &[u8] = &[0x93, 0xCA, 0x00, 0x00, 0x00, 0x01, 0xC8, 0xFF, 0x2A];
+        let options = DecodeOptions { preserve_int_width: true, ..DecodeOptions::default() };
+        let value = <Value<NoStruct>>::decode_with(&mut &bytes[..], &options).unwrap();
+
+        assert_eq!(value, Value::List(vec!(
+            Value::IntegerWide(1, IntWidth::Int32),
+            Value::IntegerWide(-1, IntWidth::Int8),
+            Value::IntegerWide(42, IntWidth::Tiny))));
+        assert_eq!(value.to_vec().unwrap(), bytes);
+        assert_eq!(value.encoded_len(), bytes.len());
+
+        let value = <Value<NoStruct>>::decode(&mut &bytes[..]).unwrap();
+        assert_eq!(value.to_vec().unwrap(), vec!(0x93, 0x01, 0xFF, 0x2A));
+    }
+
+    #[test]
+    fn integer_wide_out_of_width() {
+        let value : Value<NoStruct> = Value::IntegerWide(300, IntWidth::Int8);
+        match value.encode(&mut Vec::new()) {
+            Err(EncodeError::IntegerOutOfRange(300, 1)) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn pack_unpack_unsigned() {
+        pack_unpack_test(&[0u8, 127, 128, u8::MAX]);
+        pack_unpack_test(&[0u16, 200, u16::MAX]);
+        pack_unpack_test(&[0u32, 70000, u32::MAX]);
+        pack_unpack_test(&[0u64, u32::MAX as u64 + 1, i64::MAX as u64]);
+
+        pack_to_test(u8::MAX, &[0xC9, 0x00, 0xFF]);
+        pack_to_test(i64::MAX as u64, &[0xCB, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        encoded_len_test(&[0u64, 300, i64::MAX as u64]);
+    }
+
+    #[test]
+    fn pack_unpack_f32() {
+        pack_unpack_test(&[
+            0.0f32, -0.0, 1.5, -3.25, 0.1, f32::MAX, f32::MIN, f32::MIN_POSITIVE,
+            f32::EPSILON, 1e-45, f32::from_bits(1), f32::INFINITY, f32::NEG_INFINITY,
+        ]);
+        assert!(f32::decode(&mut f32::NAN.to_vec().unwrap().as_slice()).unwrap().is_nan());
+
+        // narrowing an `f64` which is not an `f32`:
+        assert_eq!(f32::decode(&mut 0.1f64.to_vec().unwrap().as_slice()).unwrap(), 0.1f32);
+        assert_eq!(f32::decode(&mut f64::MAX.to_vec().unwrap().as_slice()).unwrap(), f32::INFINITY);
+    }
+
+    #[test]
+    fn pack_unpack_i8_i16() {
+        pack_unpack_test(&[0i8, -16, -17, i8::MIN, i8::MAX]);
+        pack_unpack_test(&[0i16, 127, -129, i16::MIN, i16::MAX]);
+
+        pack_to_test(-100i8, &[0xC8, 0x9C]);
+        pack_to_test(-2i16, &[0xFE]);
+        unpack_to_test(&[0xCA, 0x00, 0x00, 0x00, 0x05], 5i8);
+        encoded_len_test(&[i16::MIN, -1, 200]);
+    }
+
+    #[test]
+    fn unpack_i8_from_int32_out_of_range() {
+        // 1000 as `Int32`:
+        match i8::decode(&mut [0xCA, 0x00, 0x00, 0x03, 0xE8].as_ref()) {
+            Err(DecodeError::IntegerOutOfRange(1000, "i8")) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+
+        match i16::decode(&mut [0xCA, 0xFF, 0xFF, 0x00, 0x00].as_ref()) {
+            Err(DecodeError::IntegerOutOfRange(-65536, "i16")) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn pack_u64_above_i64() {
+        match u64::MAX.encode(&mut Vec::new()) {
+            Err(EncodeError::IntegerOutOfRange(i, 8)) => assert_eq!(i, u64::MAX as i128),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn unpack_unsigned_out_of_range() {
+        match u64::decode(&mut [0xFF].as_ref()) {
+            Err(DecodeError::IntegerOutOfRange(-1, "u64")) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+
+        match u8::decode(&mut [0xC9, 0x01, 0x00].as_ref()) {
+            Err(DecodeError::IntegerOutOfRange(256, "u8")) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn unpack_gigantic_sizes_without_body() {
+        // a `List32` and a `String32` of 2^31 - 1 elements or bytes, with nothing following:
+        let list : &[u8] = &[0xD6, 0x7F, 0xFF, 0xFF, 0xFF];
+        let string : &[u8] = &[0xD2, 0x7F, 0xFF, 0xFF, 0xFF];
+        let bytes : &[u8] = &[0xCE, 0x7F, 0xFF, 0xFF, 0xFF];
+
+        assert!(<Vec<i64>>::decode(&mut Cursor::new(list)).unwrap_err().is_io());
+        assert!(<Value<NoStruct>>::decode(&mut Cursor::new(list)).unwrap_err().is_io());
+        assert!(String::decode(&mut Cursor::new(string)).unwrap_err().is_io());
+        assert!(Bytes::decode(&mut Cursor::new(bytes)).unwrap_err().is_io());
+
+        let options = DecodeOptions {
+            max_collection_len: Some(1000),
+            max_string_len: Some(1000),
+            ..DecodeOptions::default()
+        };
+        for buffer in &[list, string, bytes] {
+            match <Value<NoStruct>>::decode_with(&mut Cursor::new(buffer), &options) {
+                Err(DecodeError::SizeLimitExceeded(0x7FFF_FFFF, 1000)) => (),
+                r => panic!("Expected SizeLimitExceeded, got {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn unpack_within_size_limits() {
+        let options = DecodeOptions {
+            max_collection_len: Some(2),
+            max_string_len: Some(5),
+            ..DecodeOptions::default()
+        };
+        // `{"hello": [1, 2]}`:
+        let buffer : &[u8] = &[0xA1, 0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x92, 0x01, 0x02];
+        let dict = <HashMap<String, Vec<i64>>>::decode_with(&mut Cursor::new(buffer), &options).unwrap();
+        assert_eq!(dict.get("hello"), Some(&vec!(1, 2)));
+
+        let options = DecodeOptions { max_string_len: Some(4), ..options };
+        match <HashMap<String, Vec<i64>>>::decode_with(&mut Cursor::new(buffer), &options) {
+            Err(DecodeError::SizeLimitExceeded(5, 4)) => (),
+            r => panic!("Expected SizeLimitExceeded, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn unpack_truncated_string() {
+        // a tiny string of 5 bytes with only 2 of them:
+        assert!(String::decode(&mut [0x85, 0x68, 0x65].as_ref()).unwrap_err().is_io());
+    }
+
+    #[test]
+    fn pack_unpack_arrays() {
+        pack_unpack_test(&[[1i64, -2, 300, i64::MAX], [0; 4]]);
+        pack_unpack_test(&[[1.5f64, 0.0, -2.25]]);
+        pack_unpack_test(&[[0xABu8; 16]]);
+        pack_unpack_test::<[String; 0]>(&[[]]);
+        pack_to_test([1i64, 2], &[0x92, 0x01, 0x02]);
+        encoded_len_test(&[[1i64, -2, 300, i64::MAX]]);
+    }
+
+    #[test]
+    fn unpack_array_of_wrong_length() {
+        match <[i64; 4]>::decode(&mut [0x93, 0x01, 0x02, 0x03].as_ref()) {
+            Err(DecodeError::UnexpectedNumberOfElements(4, 3)) => (),
+            r => panic!("Expected UnexpectedNumberOfElements, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn pack_unpack_tuples() {
+        pack_unpack_test(&[
+            (42i64, String::from("hello"), true),
+            (-1, String::new(), false),
+        ]);
+        pack_to_test((1i64, String::from("a"), false), &[0x93, 0x01, 0x81, 0x61, 0xC3]);
+        pack_unpack_test(&[(1u8, 2i16, 3u32, 4.5f64, (5i64, 6i64), vec!(7i64), None::<String>, Bytes(vec!(8)))]);
+        encoded_len_test(&[(1000i64, String::from("hello"), [true; 3])]);
+    }
+
+    #[test]
+    fn unpack_tuple_of_wrong_length() {
+        match <(i64, String, bool)>::decode(&mut [0x94, 0x01, 0x80, 0xC2, 0x02].as_ref()) {
+            Err(DecodeError::UnexpectedNumberOfElements(3, 4)) => (),
+            r => panic!("Expected UnexpectedNumberOfElements, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn pack_btree_map_in_key_order() {
+        let entries = [("b", 2i64), ("c", 3), ("a", 1)];
+
+        let forward: BTreeMap<String, i64> =
+            entries.iter().map(|(k, v)| (String::from(*k), *v)).collect();
+        let backward: BTreeMap<String, i64> =
+            entries.iter().rev().map(|(k, v)| (String::from(*k), *v)).collect();
+
+        let expected = [0xA3, 0x81, 0x61, 0x01, 0x81, 0x62, 0x02, 0x81, 0x63, 0x03];
+        pack_to_test(forward.clone(), &expected);
+        pack_to_test(backward, &expected);
+
+        pack_unpack_test(&[forward.clone(), BTreeMap::new()]);
+        encoded_len_test(&[forward]);
+    }
+
+    #[test]
+    fn pack_map_with_empty_key_writes_nothing() {
+        let btree: BTreeMap<String, i64> = vec!((String::from("a"), 1), (String::new(), 2)).into_iter().collect();
+        let hash: HashMap<String, i64> = btree.clone().into_iter().collect();
+
+        let mut buffer = Vec::new();
+        assert!(matches!(btree.encode(&mut buffer), Err(EncodeError::InvalidKey(0))));
+        assert!(matches!(hash.encode(&mut buffer), Err(EncodeError::InvalidKey(0))));
+        assert!(buffer.is_empty());
+    }
+}
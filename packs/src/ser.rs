@@ -0,0 +1,397 @@
+//! # Overview
+//! A `serde` serializer into PackStream, behind the `serde` feature, such that any type with
+//! `#[derive(Serialize)]` can be encoded without deriving `Pack` as well. The value is built as a
+//! `Value<GenericStruct>` first and then encoded.
+//!
+//! The serde data model maps to PackStream as follows:
+//!
+//! | serde | PackStream |
+//! | :--- | :--- |
+//! | `bool`, integers, floats | boolean, integer, float; a `u64` above `i64::MAX` cannot be encoded |
+//! | `char`, string | string |
+//! | bytes | byte array |
+//! | `None`, unit | null |
+//! | `Some(v)`, newtype struct | `v` itself |
+//! | sequence, tuple | list |
+//! | map | dictionary; keys have to serialize as strings |
+//! | struct, tuple struct, unit struct | structure with the fields in order |
+//! | unit variant | string of the variant's name |
+//! | newtype, tuple and struct variant | dictionary with the variant's name as its only key |
+//!
+//! The tag of a structure is looked up by the name of the struct or the variant, falling back to a
+//! default tag:
+//! ```
+//! use serde::Serialize;
+//! use packs::*;
+//! use packs::ser::Serializer;
+//!
+//! #[derive(Serialize)]
+//! struct Person {
+//!     name: String,
+//!     age: Option<u32>,
+//! }
+//!
+//! let person = Person { name: String::from("Ada"), age: None };
+//! let bytes = Serializer::new().with_tag("Person", 0x50).to_vec(&person).unwrap();
+//!
+//! assert_eq!(bytes, &[0xB2, 0x50, 0x83, b'A', b'd', b'a', 0xC0]);
+//! ```
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::io::Write;
+
+use serde::ser::{self, Serialize};
+
+use crate::{Pack, Value, GenericStruct, Dictionary, Bytes, EncodeError};
+
+impl ser::Error for EncodeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        EncodeError::Custom(msg.to_string())
+    }
+}
+
+/// Serializes any `Serialize` value into a `Value<GenericStruct>`, and from there into PackStream;
+/// see the [module documentation](crate::ser) for the mapping.
+#[derive(Debug, Clone, Default)]
+pub struct Serializer {
+    tags: HashMap<&'static str, u8>,
+    default_tag: u8,
+}
+
+impl Serializer {
+    /// A serializer writing every structure with the tag `0x00`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes structs and variants called `name` with the given `tag`.
+    pub fn with_tag(mut self, name: &'static str, tag: u8) -> Self {
+        self.tags.insert(name, tag);
+        self
+    }
+
+    /// The tag of all structs and variants without a tag given by
+    /// [`with_tag`](crate::ser::Serializer::with_tag).
+    pub fn with_default_tag(mut self, tag: u8) -> Self {
+        self.default_tag = tag;
+        self
+    }
+
+    fn tag(&self, name: &str) -> u8 {
+        self.tags.get(name).copied().unwrap_or(self.default_tag)
+    }
+
+    pub fn to_value<T: Serialize + ?Sized>(&self, value: &T) -> Result<Value<GenericStruct>, EncodeError> {
+        value.serialize(self)
+    }
+
+    pub fn to_writer<T: Serialize + ?Sized, W: Write>(&self, value: &T, writer: &mut W) -> Result<usize, EncodeError> {
+        self.to_value(value)?.encode(writer)
+    }
+
+    pub fn to_vec<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, EncodeError> {
+        self.to_value(value)?.to_vec()
+    }
+}
+
+/// Encodes the `value` with a default [`Serializer`](crate::ser::Serializer).
+pub fn to_writer<T: Serialize + ?Sized, W: Write>(value: &T, writer: &mut W) -> Result<usize, EncodeError> {
+    Serializer::new().to_writer(value, writer)
+}
+
+/// Encodes the `value` with a default [`Serializer`](crate::ser::Serializer).
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    Serializer::new().to_vec(value)
+}
+
+impl<'a> ser::Serializer for &'a Serializer {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    type SerializeSeq = SerializeList<'a>;
+    type SerializeTuple = SerializeList<'a>;
+    type SerializeTupleStruct = SerializeStructure<'a>;
+    type SerializeTupleVariant = SerializeVariant<'a>;
+    type SerializeMap = SerializeDictionary<'a>;
+    type SerializeStruct = SerializeStructure<'a>;
+    type SerializeStructVariant = SerializeVariant<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        <i64>::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| EncodeError::IntegerOutOfRange(i128::from(v), 8))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::String(String::from(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Bytes(Bytes(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Structure(GenericStruct { tag_byte: self.tag(name), fields: Vec::new() }))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        Ok(variant_value(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeList { ser: self, items: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeVariant { ser: self, variant, tag: None, fields: Vec::with_capacity(len) })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeDictionary { ser: self, dict: Dictionary::with_capacity(len.unwrap_or(0)), key: None })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SerializeStructure { ser: self, tag: self.tag(name), fields: Vec::with_capacity(len) })
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeVariant { ser: self, variant, tag: Some(self.tag(variant)), fields: Vec::with_capacity(len) })
+    }
+}
+
+/// A dictionary with the single entry `variant: value`.
+fn variant_value(variant: &str, value: Value<GenericStruct>) -> Value<GenericStruct> {
+    let mut dict = Dictionary::with_capacity(1);
+    dict.add_property(variant, value);
+    Value::Dictionary(dict)
+}
+
+/// A structure of the given fields, if there are at most 15 of them.
+fn structure(tag: u8, fields: Vec<Value<GenericStruct>>) -> Result<Value<GenericStruct>, EncodeError> {
+    if fields.len() > 15 {
+        return Err(EncodeError::TooManyStructFields(fields.len()))
+    }
+    Ok(Value::Structure(GenericStruct { tag_byte: tag, fields }))
+}
+
+/// Serializes sequences and tuples as lists.
+pub struct SerializeList<'a> {
+    ser: &'a Serializer,
+    items: Vec<Value<GenericStruct>>,
+}
+
+impl<'a> ser::SerializeSeq for SerializeList<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.items.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::List(self.items))
+    }
+}
+
+impl<'a> ser::SerializeTuple for SerializeList<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes structs and tuple structs as structures.
+pub struct SerializeStructure<'a> {
+    ser: &'a Serializer,
+    tag: u8,
+    fields: Vec<Value<GenericStruct>>,
+}
+
+impl<'a> ser::SerializeStruct for SerializeStructure<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.fields.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        structure(self.tag, self.fields)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SerializeStructure<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.fields.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        structure(self.tag, self.fields)
+    }
+}
+
+/// Serializes tuple variants as `{variant: [fields]}` and struct variants as
+/// `{variant: Structure(fields)}`.
+pub struct SerializeVariant<'a> {
+    ser: &'a Serializer,
+    variant: &'static str,
+    /// The tag of a struct variant, `None` for a tuple variant.
+    tag: Option<u8>,
+    fields: Vec<Value<GenericStruct>>,
+}
+
+impl<'a> SerializeVariant<'a> {
+    fn finish(self) -> Result<Value<GenericStruct>, EncodeError> {
+        let value =
+            match self.tag {
+                Some(tag) => structure(tag, self.fields)?,
+                None => Value::List(self.fields),
+            };
+        Ok(variant_value(self.variant, value))
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SerializeVariant<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.fields.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for SerializeVariant<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.fields.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+/// Serializes maps as dictionaries.
+pub struct SerializeDictionary<'a> {
+    ser: &'a Serializer,
+    dict: Dictionary<GenericStruct>,
+    key: Option<String>,
+}
+
+impl<'a> ser::SerializeMap for SerializeDictionary<'a> {
+    type Ok = Value<GenericStruct>;
+    type Error = EncodeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        match key.serialize(self.ser)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(EncodeError::Custom(String::from("dictionary keys have to be strings"))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().ok_or_else(|| EncodeError::Custom(String::from("value without a key")))?;
+        self.dict.add_property(&key, value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Dictionary(self.dict))
+    }
+}
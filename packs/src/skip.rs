@@ -0,0 +1,176 @@
+//! # Overview
+//! Functions which step over encoded PackStream values without decoding them, e.g. to forward
+//! some values verbatim and to drop others.
+use std::io::{self, Cursor, Read};
+use crate::{DecodeError, Marker};
+use crate::ll::io::{TeeReader, CountingWriter};
+use crate::ll::types::lengths::{body_len_of, BodyLen, marker_is_dictionary};
+
+/// Returns how many bytes the first value in `bytes` occupies, without decoding it. The value has
+/// to be complete; a truncated value is reported as `ReadIOError`:
+/// ```
+/// use packs::skip::peek_value_len;
+///
+/// let bytes : &[u8] = &[
+///     0x92, 0x01, 0x81, 0x61, // the list [1, "a"]
+///     0xC0, // a following null
+/// ];
+/// let len = peek_value_len(bytes).unwrap();
+///
+/// assert_eq!(len, 4);
+/// assert_eq!(&bytes[len..], &[0xC0]);
+/// ```
+pub fn peek_value_len(bytes: &[u8]) -> Result<usize, DecodeError> {
+    let mut reader = Cursor::new(bytes);
+    skip_in_reader(&mut reader)?;
+    Ok(reader.position() as usize)
+}
+
+/// Reads one complete value from `reader` and drops it, without building a `Value` of it or
+/// holding its bytes in memory. Returns the number of bytes read:
+/// ```
+/// use packs::Unpack;
+/// use packs::skip::skip_value;
+///
+/// let mut reader : &[u8] = &[
+///     0xA1, 0x81, 0x61, 0xCC, 0x02, 0xFF, 0xFF, // {"a": <2 bytes>}
+///     0x2A, // a following 42
+/// ];
+///
+/// assert_eq!(skip_value(&mut reader).unwrap(), 7);
+/// assert_eq!(i64::decode(&mut reader).unwrap(), 42);
+/// ```
+/// A reader which ends within the value is reported as `ReadIOError`.
+pub fn skip_value<T: Read>(reader: &mut T) -> Result<usize, DecodeError> {
+    let mut counting = TeeReader::new(reader, CountingWriter::new(io::sink()));
+    skip_in_reader(&mut counting)?;
+    let (_, counter) = counting.into_parts();
+    Ok(counter.count())
+}
+
+/// Skips the next complete value of `reader`.
+fn skip_in_reader<T: Read>(reader: &mut T) -> Result<(), DecodeError> {
+    walk_value(reader, |marker, reader| {
+        let body_len = body_len_of(marker, reader)?;
+        if let BodyLen::Fixed(n) = body_len {
+            let skipped = io::copy(&mut reader.take(n as u64), &mut io::sink())?;
+            if skipped < n as u64 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+            }
+        }
+        Ok(body_len)
+    })
+}
+
+/// Walks over the next complete value of `reader` and all values nested in it, one marker after
+/// another. Nested values are counted rather than recursed into, such that deeply nested input
+/// cannot overflow the stack. `step` is called with each marker right after it has been read; it
+/// has to read the size information and a `BodyLen::Fixed` body, and returns the `BodyLen`.
+pub(crate) fn walk_value<T, F>(reader: &mut T, mut step: F) -> Result<(), DecodeError>
+    where T: Read,
+          F: FnMut(Marker, &mut T) -> Result<BodyLen, DecodeError> {
+    // the number of values still to walk over:
+    let mut pending = 1usize;
+
+    while pending > 0 {
+        pending -= 1;
+        let marker = Marker::decode(reader)?;
+        match step(marker, reader)? {
+            BodyLen::Fixed(_) => (),
+            BodyLen::Count(n) if marker_is_dictionary(marker) => pending += 2 * n,
+            BodyLen::Count(n) | BodyLen::Nested(n, _) => pending += n,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::skip::{peek_value_len, skip_value};
+    use crate::{Value, Pack, Unpack, GenericStruct, Dictionary, Bytes};
+
+    #[test]
+    fn peek_len_of_each_kind() {
+        let mut dict = Dictionary::new();
+        dict.add_property("a", 1);
+        dict.add_property("b", vec!(Value::Null, Value::from("x".repeat(20))));
+
+        let values : Vec<Value<GenericStruct>> = vec!(
+            Value::Null,
+            Value::Boolean(true),
+            Value::Integer(-5),
+            Value::Integer(1000),
+            Value::Integer(i64::MIN),
+            Value::Float(1.5),
+            Value::from(""),
+            Value::from("x".repeat(300)),
+            Value::Bytes(Bytes(vec!(1, 2, 3))),
+            Value::List(vec!()),
+            Value::List(vec!(Value::Integer(1); 20)),
+            Value::Dictionary(dict),
+            Value::Structure(GenericStruct {
+                tag_byte: 0x01,
+                fields: vec!(Value::from("a"), Value::List(vec!(Value::Float(0.0)))),
+            }),
+        );
+
+        for value in values {
+            let mut bytes = value.to_vec().unwrap();
+            let len = bytes.len();
+            bytes.extend_from_slice(&[0xC0, 0x01]);
+
+            assert_eq!(peek_value_len(&bytes).unwrap(), len, "length of {:?}", value);
+
+            let mut reader = bytes.as_slice();
+            assert_eq!(skip_value(&mut reader).unwrap(), len, "skipping {:?}", value);
+            assert_eq!(reader, &[0xC0, 0x01]);
+        }
+    }
+
+    #[test]
+    fn skip_mixed_value_to_next_value() {
+        let mut dict = Dictionary::new();
+        dict.add_property("blob", Value::Bytes(Bytes(vec![0xAB; 70000])));
+        dict.add_property("list", vec!(Value::Integer(1), Value::from("two"), Value::Null));
+        let value : Value<GenericStruct> = Value::Structure(GenericStruct {
+            tag_byte: 0x10,
+            fields: vec!(Value::Dictionary(dict), Value::Float(2.5)),
+        });
+
+        let mut bytes = value.to_vec().unwrap();
+        let len = bytes.len();
+        String::from("next").encode(&mut bytes).unwrap();
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(skip_value(&mut reader).unwrap(), len);
+        assert_eq!(String::decode(&mut reader).unwrap(), "next");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn peek_len_of_truncated_value() {
+        // a list of two elements with only one given:
+        assert!(peek_value_len(&[0x92, 0x01]).unwrap_err().is_io());
+        // a string of 3 bytes with only two given:
+        assert!(peek_value_len(&[0x83, 0x61, 0x62]).unwrap_err().is_io());
+        assert!(peek_value_len(&[]).unwrap_err().is_io());
+        assert!(skip_value(&mut [0x83, 0x61, 0x62].as_ref()).unwrap_err().is_io());
+    }
+
+    #[test]
+    fn peek_len_of_deeply_nested_value() {
+        let mut bytes = vec![0x91; 1_000_000];
+        bytes.push(0xC0);
+        assert_eq!(peek_value_len(&bytes).unwrap(), bytes.len());
+    }
+
+    #[test]
+    fn skip_deeply_nested_value() {
+        let mut bytes = vec![0x91; 1_000_000];
+        bytes.extend_from_slice(&[0xC0, 0x01]);
+        let mut reader = bytes.as_slice();
+        assert_eq!(skip_value(&mut reader).unwrap(), 1_000_001);
+        assert_eq!(reader, &[0x01]);
+    }
+}
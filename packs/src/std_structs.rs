@@ -1,7 +1,7 @@
 pub use crate::std_structs::node::Node;
 pub use crate::std_structs::relationship::Relationship;
 pub use crate::std_structs::unbound_relationship::UnboundRelationship;
-pub use crate::std_structs::path::Path;
+pub use crate::std_structs::path::{Path, PathElement, PathError};
 pub use crate::std_structs::date::Date;
 pub use crate::std_structs::time::Time;
 pub use crate::std_structs::local_time::LocalTime;
@@ -12,6 +12,8 @@ pub use crate::std_structs::duration::Duration;
 pub use crate::std_structs::point2d::Point2D;
 pub use crate::std_structs::point3d::Point3D;
 use crate::*;
+use thiserror::Error;
+use std::convert::TryFrom;
 
 pub mod node;
 pub mod relationship;
@@ -29,31 +31,33 @@ pub mod point3d;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
 pub enum StdStruct {
-    #[tag = 0x4E]
+    #[tag(Node::TAG)]
     Node(Node),
-    #[tag = 0x52]
+    #[tag(Relationship::TAG)]
     Relationship(Relationship),
-    #[tag = 0x72]
+    #[tag(UnboundRelationship::TAG)]
     UnboundRelationship(UnboundRelationship),
-    #[tag = 0x50]
+    #[tag(Path::TAG)]
     Path(Path),
-    #[tag = 0x44]
+    #[tag(Date::TAG)]
     Date(Date),
-    #[tag = 0x54]
+    #[tag(Time::TAG)]
     Time(Time),
-    #[tag = 0x74]
+    #[tag(LocalTime::TAG)]
     LocalTime(LocalTime),
-    #[tag = 0x46]
+    #[tag(DateTime::TAG_V1)]
+    #[tag(DateTime::TAG_V2)]
     DateTime(DateTime),
-    #[tag = 0x66]
+    #[tag(DateTimeZoneId::TAG_V1)]
+    #[tag(DateTimeZoneId::TAG_V2)]
     DateTimeZoneId(DateTimeZoneId),
-    #[tag = 0x64]
+    #[tag(LocalDateTime::TAG)]
     LocalDateTime(LocalDateTime),
-    #[tag = 0x45]
+    #[tag(Duration::TAG)]
     Duration(Duration),
-    #[tag = 0x58]
+    #[tag(Point2D::TAG)]
     Point2D(Point2D),
-    #[tag = 0x59]
+    #[tag(Point3D::TAG)]
     Point3D(Point3D),
 }
 
@@ -62,22 +66,320 @@ pub enum StdStruct {
 /// `Send`, since [`Node`](crate::std_structs::node) can use `StdStructPrimitive` and hence is no longer
 /// recursive.
 pub enum StdStructPrimitive {
-    #[tag = 0x44]
+    #[tag(Date::TAG)]
     Date(Date),
-    #[tag = 0x54]
+    #[tag(Time::TAG)]
     Time(Time),
-    #[tag = 0x74]
+    #[tag(LocalTime::TAG)]
     LocalTime(LocalTime),
-    #[tag = 0x46]
+    #[tag(DateTime::TAG_V1)]
+    #[tag(DateTime::TAG_V2)]
     DateTime(DateTime),
-    #[tag = 0x66]
+    #[tag(DateTimeZoneId::TAG_V1)]
+    #[tag(DateTimeZoneId::TAG_V2)]
     DateTimeZoneId(DateTimeZoneId),
-    #[tag = 0x64]
+    #[tag(LocalDateTime::TAG)]
     LocalDateTime(LocalDateTime),
-    #[tag = 0x45]
+    #[tag(Duration::TAG)]
     Duration(Duration),
-    #[tag = 0x58]
+    #[tag(Point2D::TAG)]
     Point2D(Point2D),
-    #[tag = 0x59]
+    #[tag(Point3D::TAG)]
     Point3D(Point3D),
-}
\ No newline at end of file
+}
+
+impl From<StdStructPrimitive> for StdStruct {
+    fn from(s: StdStructPrimitive) -> Self {
+        match s {
+            StdStructPrimitive::Date(s) => StdStruct::Date(s),
+            StdStructPrimitive::Time(s) => StdStruct::Time(s),
+            StdStructPrimitive::LocalTime(s) => StdStruct::LocalTime(s),
+            StdStructPrimitive::DateTime(s) => StdStruct::DateTime(s),
+            StdStructPrimitive::DateTimeZoneId(s) => StdStruct::DateTimeZoneId(s),
+            StdStructPrimitive::LocalDateTime(s) => StdStruct::LocalDateTime(s),
+            StdStructPrimitive::Duration(s) => StdStruct::Duration(s),
+            StdStructPrimitive::Point2D(s) => StdStruct::Point2D(s),
+            StdStructPrimitive::Point3D(s) => StdStruct::Point3D(s),
+        }
+    }
+}
+
+/// Fails for the graph structures `Node`, `Relationship`, `UnboundRelationship` and `Path`, handing
+/// the structure back.
+impl TryFrom<StdStruct> for StdStructPrimitive {
+    type Error = StdStruct;
+
+    fn try_from(s: StdStruct) -> Result<Self, Self::Error> {
+        match s {
+            StdStruct::Date(s) => Ok(StdStructPrimitive::Date(s)),
+            StdStruct::Time(s) => Ok(StdStructPrimitive::Time(s)),
+            StdStruct::LocalTime(s) => Ok(StdStructPrimitive::LocalTime(s)),
+            StdStruct::DateTime(s) => Ok(StdStructPrimitive::DateTime(s)),
+            StdStruct::DateTimeZoneId(s) => Ok(StdStructPrimitive::DateTimeZoneId(s)),
+            StdStruct::LocalDateTime(s) => Ok(StdStructPrimitive::LocalDateTime(s)),
+            StdStruct::Duration(s) => Ok(StdStructPrimitive::Duration(s)),
+            StdStruct::Point2D(s) => Ok(StdStructPrimitive::Point2D(s)),
+            StdStruct::Point3D(s) => Ok(StdStructPrimitive::Point3D(s)),
+            s => Err(s),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    #[error("Property '{0}' holds a value of kind {1:?}, which is not a legal property value")]
+    IllegalPropertyValue(String, ValueKind),
+    #[error("Property '{0}' holds a {1}, which is not a legal property value")]
+    IllegalPropertyStructure(String, &'static str),
+}
+
+impl StdStruct {
+    /// Checks that the properties of the nodes and relationships in this structure only hold legal
+    /// property values: primitive values, temporal or spatial structures, or lists of those. A
+    /// dictionary, a list nested into a list, or a node, relationship or path is reported together
+    /// with the path of keys leading to it, e.g. `properties.friend` or `nodes[1].properties.tags[0]`.
+    ///
+    /// The properties of `Node`, `Relationship` and `Path` cannot hold graph structures by their
+    /// type, but those of `UnboundRelationship` can, as well as lists and dictionaries:
+    /// ```
+    /// use packs::*;
+    /// use packs::std_structs::{StdStruct, UnboundRelationship, Node, ValidationError};
+    ///
+    /// let mut rel = UnboundRelationship { id: 1, _type: String::from("KNOWS"), properties: Default::default() };
+    /// rel.properties.insert(String::from("since"), Value::Integer(2020));
+    /// assert!(StdStruct::UnboundRelationship(rel.clone()).validate_properties().is_ok());
+    ///
+    /// rel.properties.insert(String::from("friend"), Value::Structure(StdStruct::Node(Node::new(2))));
+    /// assert_eq!(
+    ///     StdStruct::UnboundRelationship(rel).validate_properties(),
+    ///     Err(ValidationError::IllegalPropertyStructure(String::from("properties.friend"), "Node")));
+    /// ```
+    pub fn validate_properties(&self) -> Result<(), ValidationError> {
+        match self {
+            StdStruct::Node(node) =>
+                validate_property_map(node.properties.properties(), "properties", &|_| None),
+            StdStruct::Relationship(rel) =>
+                validate_property_map(rel.properties.properties(), "properties", &|_| None),
+            StdStruct::UnboundRelationship(rel) =>
+                validate_property_map(rel.properties.iter(), "properties", &graph_struct_name),
+            StdStruct::Path(path) => {
+                for (i, node) in path.nodes.iter().enumerate() {
+                    validate_property_map(node.properties.properties(), &format!("nodes[{}].properties", i), &|_| None)?;
+                }
+                for (i, rel) in path.rels.iter().enumerate() {
+                    validate_property_map(rel.properties.properties(), &format!("rels[{}].properties", i), &|_| None)?;
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The name of a structure which cannot be a property value.
+pub(crate) fn graph_struct_name(s: &StdStruct) -> Option<&'static str> {
+    match s {
+        StdStruct::Node(_) => Some("Node"),
+        StdStruct::Relationship(_) => Some("Relationship"),
+        StdStruct::UnboundRelationship(_) => Some("UnboundRelationship"),
+        StdStruct::Path(_) => Some("Path"),
+        _ => None,
+    }
+}
+
+fn validate_property_map<'a, S: 'a, I, F>(properties: I, path: &str, illegal_struct: &F) -> Result<(), ValidationError>
+    where I: Iterator<Item=(&'a String, &'a Value<S>)>,
+          F: Fn(&S) -> Option<&'static str> {
+    for (key, value) in properties {
+        validate_property_value(value, &format!("{}.{}", path, key), true, illegal_struct)?;
+    }
+
+    Ok(())
+}
+
+fn validate_property_value<S, F>(value: &Value<S>, path: &str, allow_list: bool, illegal_struct: &F) -> Result<(), ValidationError>
+    where F: Fn(&S) -> Option<&'static str> {
+    match value {
+        Value::List(list) if allow_list => {
+            for (i, item) in list.iter().enumerate() {
+                validate_property_value(item, &format!("{}[{}]", path, i), false, illegal_struct)?;
+            }
+            Ok(())
+        },
+        Value::List(_) | Value::Dictionary(_) =>
+            Err(ValidationError::IllegalPropertyValue(String::from(path), value.kind())),
+        Value::Structure(s) => match illegal_struct(s) {
+            Some(name) => Err(ValidationError::IllegalPropertyStructure(String::from(path), name)),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Converts the structures of `value`, and of the values nested into it, by `f`. `path` names
+/// `value` in the errors of `f`, like for [`validate_properties`](crate::std_structs::StdStruct::validate_properties).
+pub(crate) fn map_structures<S, U, F>(value: Value<S>, path: &str, f: &F) -> Result<Value<U>, ValidationError>
+    where F: Fn(S, &str) -> Result<U, ValidationError> {
+    Ok(match value {
+        Value::Null => Value::Null,
+        Value::Boolean(b) => Value::Boolean(b),
+        Value::Integer(i) => Value::Integer(i),
+        Value::IntegerWide(i, width) => Value::IntegerWide(i, width),
+        Value::Float(x) => Value::Float(x),
+        Value::Bytes(bytes) => Value::Bytes(bytes),
+        Value::String(s) => Value::String(s),
+        Value::List(list) =>
+            Value::List(list.into_iter().enumerate()
+                .map(|(i, item)| map_structures(item, &format!("{}[{}]", path, i), f))
+                .collect::<Result<_, _>>()?),
+        Value::Dictionary(dict) =>
+            Value::Dictionary(dict.into_properties()
+                .map(|(key, item)| {
+                    let item = map_structures(item, &format!("{}.{}", path, key), f)?;
+                    Ok((key, item))
+                })
+                .collect::<Result<_, _>>()?),
+        Value::Structure(s) => Value::Structure(f(s, path)?),
+    })
+}
+
+/// The tag bytes of all standard structures together with the name of their
+/// [`StdStruct`](crate::std_structs::StdStruct) variant. Structures with several versions, like
+/// `DateTime`, appear once per tag.
+const TAGS: &[(u8, &str)] = &[
+    (Node::TAG, "Node"),
+    (Relationship::TAG, "Relationship"),
+    (UnboundRelationship::TAG, "UnboundRelationship"),
+    (Path::TAG, "Path"),
+    (Date::TAG, "Date"),
+    (Time::TAG, "Time"),
+    (LocalTime::TAG, "LocalTime"),
+    (DateTime::TAG_V1, "DateTime"),
+    (DateTime::TAG_V2, "DateTime"),
+    (DateTimeZoneId::TAG_V1, "DateTimeZoneId"),
+    (DateTimeZoneId::TAG_V2, "DateTimeZoneId"),
+    (LocalDateTime::TAG, "LocalDateTime"),
+    (Duration::TAG, "Duration"),
+    (Point2D::TAG, "Point2D"),
+    (Point3D::TAG, "Point3D"),
+];
+
+/// The name of the standard structure with tag byte `tag`, e.g. for logging:
+/// ```
+/// use packs::std_structs::tag_name;
+///
+/// assert_eq!(tag_name(0x4E), Some("Node"));
+/// assert_eq!(tag_name(0x49), Some("DateTime"));
+/// assert_eq!(tag_name(0x00), None);
+/// ```
+pub fn tag_name(tag: u8) -> Option<&'static str> {
+    TAGS.iter().find(|(t, _)| *t == tag).map(|(_, name)| *name)
+}
+
+/// All tag bytes of the standard structures together with their names, in the order of the
+/// [`StdStruct`](crate::std_structs::StdStruct) variants.
+pub fn all_tags() -> &'static [(u8, &'static str)] {
+    TAGS
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::std_structs::*;
+    use crate::{Pack, Unpack, Marker, DecodeError, Value, ValueKind};
+
+    #[test]
+    fn tags_match_std_struct() {
+        for (tag, name) in all_tags() {
+            // a structure without fields is rejected by the variant, not for its tag:
+            match StdStruct::decode_body(Marker::Structure(0, *tag), &mut &[][..]) {
+                Err(DecodeError::UnexpectedNumberOfFields(_, 0)) => (),
+                r => panic!("Expected {} ({:#04X}) to be a StdStruct, got {:?}", name, tag, r),
+            }
+        }
+
+        let mut tags: Vec<u8> = all_tags().iter().map(|(tag, _)| *tag).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        assert_eq!(tags.len(), all_tags().len());
+    }
+
+    /// The name of the variant; a new variant has to be added here, to the samples below and to
+    /// `all_tags`.
+    fn variant_name(s: &StdStruct) -> &'static str {
+        match s {
+            StdStruct::Node(_) => "Node",
+            StdStruct::Relationship(_) => "Relationship",
+            StdStruct::UnboundRelationship(_) => "UnboundRelationship",
+            StdStruct::Path(_) => "Path",
+            StdStruct::Date(_) => "Date",
+            StdStruct::Time(_) => "Time",
+            StdStruct::LocalTime(_) => "LocalTime",
+            StdStruct::DateTime(_) => "DateTime",
+            StdStruct::DateTimeZoneId(_) => "DateTimeZoneId",
+            StdStruct::LocalDateTime(_) => "LocalDateTime",
+            StdStruct::Duration(_) => "Duration",
+            StdStruct::Point2D(_) => "Point2D",
+            StdStruct::Point3D(_) => "Point3D",
+        }
+    }
+
+    #[test]
+    fn every_std_struct_has_tags() {
+        let samples = vec!(
+            StdStruct::Node(Node::new(1)),
+            StdStruct::Relationship(Relationship {
+                id: 1, start_node_id: 2, end_node_id: 3, _type: String::from("KNOWS"), properties: Default::default() }),
+            StdStruct::UnboundRelationship(UnboundRelationship {
+                id: 1, _type: String::from("KNOWS"), properties: Default::default() }),
+            StdStruct::Path(Path { nodes: Vec::new(), rels: Vec::new(), ids: Vec::new() }),
+            StdStruct::Date(Date { days: 1 }),
+            StdStruct::Time(Time { nanoseconds: 1, tz_offset_seconds: 0 }),
+            StdStruct::LocalTime(LocalTime { nanoseconds: 1 }),
+            StdStruct::DateTime(DateTime { seconds: 1, nanoseconds: 0, tz_offset_minutes: 0 }),
+            StdStruct::DateTimeZoneId(DateTimeZoneId { seconds: 1, nanoseconds: 0, tz_id: 0 }),
+            StdStruct::LocalDateTime(LocalDateTime { seconds: 1, nanoseconds: 0 }),
+            StdStruct::Duration(Duration { months: 0, days: 1, seconds: 0, nanoseconds: 0 }),
+            StdStruct::Point2D(Point2D { srid: 1, x: 0.0, y: 0.0 }),
+            StdStruct::Point3D(Point3D { srid: 1, x: 0.0, y: 0.0, z: 0.0 }));
+
+        for sample in &samples {
+            let name = variant_name(sample);
+            let tag = sample.to_vec().unwrap()[1];
+            assert!(all_tags().contains(&(tag, name)), "{} ({:#04X}) is missing from all_tags", name, tag);
+        }
+
+        // every variant is listed, in the order of the variants:
+        let mut tagged: Vec<&str> = all_tags().iter().map(|(_, name)| *name).collect();
+        tagged.dedup();
+        assert_eq!(tagged, samples.iter().map(variant_name).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn validate_nested_properties() {
+        let mut node = Node::new(1);
+        node.properties.add_property("name", "Alice");
+        node.properties.add_property("born", Value::Structure(StdStructPrimitive::Date(Date { days: 1 })));
+        node.properties.add_property("tags", vec!(Value::from("a"), Value::from("b")));
+        assert_eq!(StdStruct::Node(node.clone()).validate_properties(), Ok(()));
+
+        let mut bad = node.clone();
+        bad.properties.add_property("tags", vec!(Value::from("a"), Value::List(Vec::new())));
+        let path = Path { nodes: vec!(node, bad), rels: Vec::new(), ids: Vec::new() };
+        assert_eq!(
+            StdStruct::Path(path).validate_properties(),
+            Err(ValidationError::IllegalPropertyValue(String::from("nodes[1].properties.tags[1]"), ValueKind::List)));
+
+        let mut rel = UnboundRelationship { id: 1, _type: String::from("KNOWS"), properties: Default::default() };
+        rel.properties.insert(
+            String::from("via"),
+            Value::List(vec!(Value::Structure(StdStruct::Node(Node::new(2))))));
+        assert_eq!(
+            StdStruct::UnboundRelationship(rel.clone()).validate_properties(),
+            Err(ValidationError::IllegalPropertyStructure(String::from("properties.via[0]"), "Node")));
+
+        rel.properties.insert(String::from("via"), Value::Dictionary(Default::default()));
+        assert_eq!(
+            StdStruct::UnboundRelationship(rel).validate_properties(),
+            Err(ValidationError::IllegalPropertyValue(String::from("properties.via"), ValueKind::Dictionary)));
+    }
+}
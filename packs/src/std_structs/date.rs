@@ -1,7 +1,11 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x44]
+#[tag(Date::TAG)]
 pub struct Date {
     pub days: i64,
-}
\ No newline at end of file
+}
+
+impl Date {
+    pub const TAG: u8 = 0x44;
+}
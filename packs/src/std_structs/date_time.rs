@@ -1,7 +1,11 @@
 use crate::*;
+use std::io::{Read, Write};
 
-#[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x46]
+#[derive(Debug, Clone, PartialEq)]
+/// A date time with a time zone offset. `seconds` are the seconds since the epoch in local time,
+/// i.e. with the offset applied. Depending on the [`PackStreamVersion`](crate::PackStreamVersion)
+/// this is encoded with the tag `0x46` and local seconds (`V1`) or with the tag `0x49` and
+/// UTC seconds (`V2`).
 pub struct DateTime {
     pub seconds: i64,
     pub nanoseconds: i64,
@@ -9,7 +13,204 @@ pub struct DateTime {
 }
 
 impl DateTime {
+    pub const TAG_V1: u8 = 0x46;
+    pub const TAG_V2: u8 = 0x49;
+
     pub fn utc_nanoseconds(&self) -> i64 {
         (self.seconds * 1000000000) + self.nanoseconds - (self.tz_offset_minutes * 60 * 1000000000)
     }
-}
\ No newline at end of file
+
+    /// The nanoseconds since the epoch in UTC, or `None` if they do not fit into an `i64`.
+    pub fn checked_utc_nanoseconds(&self) -> Option<i64> {
+        self.utc_seconds()?
+            .checked_mul(1000000000)?
+            .checked_add(self.nanoseconds)
+    }
+
+    fn utc_seconds(&self) -> Option<i64> {
+        self.seconds.checked_sub(self.tz_offset_minutes.checked_mul(60)?)
+    }
+}
+
+impl Pack for DateTime {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let (tag, seconds) = match options.version {
+            PackStreamVersion::V1 => (DateTime::TAG_V1, self.seconds),
+            PackStreamVersion::V2 => {
+                let seconds = self.utc_seconds().ok_or_else(||
+                    EncodeError::IntegerOutOfRange(
+                        self.seconds as i128 - self.tz_offset_minutes as i128 * 60, 8))?;
+                (DateTime::TAG_V2, seconds)
+            },
+        };
+
+        Ok(Marker::Structure(3, tag).encode(writer)?
+            + seconds.encode(writer)?
+            + self.nanoseconds.encode(writer)?
+            + self.tz_offset_minutes.encode(writer)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + 3 * 9
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.encoded_len_with(&EncodeOptions::default())
+    }
+
+    fn encoded_len_with(&self, options: &EncodeOptions) -> usize {
+        let seconds = match options.version {
+            PackStreamVersion::V1 => Some(self.seconds),
+            PackStreamVersion::V2 => self.utc_seconds(),
+        };
+
+        // overflowing UTC seconds fail the encoding before anything is written:
+        seconds.map_or(0, |seconds|
+            2 + seconds.encoded_len() + self.nanoseconds.encoded_len() + self.tz_offset_minutes.encoded_len())
+    }
+}
+
+impl Unpack for DateTime {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        match marker {
+            Marker::Structure(u, tag) => {
+                if u != 3 {
+                    return Err(DecodeError::UnexpectedNumberOfFields(3, u))
+                }
+
+                let expected = match options.version {
+                    PackStreamVersion::V1 => DateTime::TAG_V1,
+                    PackStreamVersion::V2 => DateTime::TAG_V2,
+                };
+                if tag != expected {
+                    return Err(DecodeError::UnexpectedTagByte(tag))
+                }
+
+                let seconds = i64::decode(reader)?;
+                let nanoseconds = i64::decode(reader)?;
+                let tz_offset_minutes = i64::decode(reader)?;
+
+                let seconds = match options.version {
+                    PackStreamVersion::V1 => seconds,
+                    PackStreamVersion::V2 => tz_offset_minutes.checked_mul(60)
+                        .and_then(|offset| seconds.checked_add(offset))
+                        .ok_or(DecodeError::IntegerOutOfRange(seconds, "DateTime seconds"))?,
+                };
+
+                Ok(DateTime { seconds, nanoseconds, tz_offset_minutes })
+            },
+            _ => Err(DecodeError::UnexpectedMarker(marker))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::packable::test::{pack_unpack_test, pack_to_test};
+    use crate::std_structs::date_time::DateTime;
+    use crate::std_structs::StdStruct;
+    use crate::*;
+
+    fn date_time() -> DateTime {
+        DateTime { seconds: 7200, nanoseconds: 42, tz_offset_minutes: 60 }
+    }
+
+    #[test]
+    fn pack_unpack() {
+        pack_unpack_test::<DateTime>(&[date_time()]);
+    }
+
+    #[test]
+    fn pack_into_v1() {
+        pack_to_test(date_time(), &[0xB3, 0x46, 0xC9, 0x1C, 0x20, 0x2A, 0x3C]);
+    }
+
+    #[test]
+    fn pack_into_v2() {
+        let options = EncodeOptions { version: PackStreamVersion::V2 };
+        let mut buffer = Vec::new();
+        date_time().encode_with(&mut buffer, &options).unwrap();
+
+        // 7200 local seconds at an offset of 60 minutes are 3600 UTC seconds:
+        assert_eq!(buffer, vec!(0xB3, 0x49, 0xC9, 0x0E, 0x10, 0x2A, 0x3C));
+    }
+
+    #[test]
+    fn encoded_len_v2() {
+        let options = EncodeOptions { version: PackStreamVersion::V2 };
+        // 100 local seconds are a tiny int, -3500 UTC seconds are not:
+        let date_time = DateTime { seconds: 100, nanoseconds: 42, tz_offset_minutes: 60 };
+
+        let mut buffer = Vec::new();
+        date_time.encode_with(&mut buffer, &options).unwrap();
+
+        assert_eq!(date_time.encoded_len_with(&options), buffer.len());
+        assert_eq!(date_time.encoded_len(), date_time.to_vec().unwrap().len());
+        assert_ne!(date_time.encoded_len(), buffer.len());
+    }
+
+    #[test]
+    fn pack_unpack_v2() {
+        let encode_options = EncodeOptions { version: PackStreamVersion::V2 };
+        let decode_options = DecodeOptions { version: PackStreamVersion::V2, ..DecodeOptions::default() };
+        let value = Value::Structure(StdStruct::DateTime(date_time()));
+
+        let mut buffer = Vec::new();
+        value.encode_with(&mut buffer, &encode_options).unwrap();
+        let res = <Value<StdStruct>>::decode_with(&mut buffer.as_slice(), &decode_options).unwrap();
+
+        assert_eq!(value, res);
+    }
+
+    #[test]
+    fn unpack_rejects_other_version() {
+        let mut buffer = Vec::new();
+        date_time().encode_with(&mut buffer, &EncodeOptions { version: PackStreamVersion::V2 }).unwrap();
+
+        match <Value<StdStruct>>::decode(&mut buffer.as_slice()) {
+            Err(DecodeError::UnexpectedTagByte(0x49)) => (),
+            r => panic!("Expected UnexpectedTagByte, got {:?}", r),
+        }
+
+        let mut buffer = Vec::new();
+        date_time().encode(&mut buffer).unwrap();
+        let options = DecodeOptions { version: PackStreamVersion::V2, ..DecodeOptions::default() };
+
+        match DateTime::decode_with(&mut buffer.as_slice(), &options) {
+            Err(DecodeError::UnexpectedTagByte(0x46)) => (),
+            r => panic!("Expected UnexpectedTagByte, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn overflowing_seconds() {
+        let encode_options = EncodeOptions { version: PackStreamVersion::V2 };
+        let decode_options = DecodeOptions { version: PackStreamVersion::V2, ..DecodeOptions::default() };
+        let date_time = DateTime { seconds: i64::MAX, nanoseconds: 0, tz_offset_minutes: -60 };
+        assert_eq!(date_time.checked_utc_nanoseconds(), None);
+        assert_eq!(date_time.encoded_len_with(&encode_options), 0);
+
+        let mut buffer = Vec::new();
+        match date_time.encode_with(&mut buffer, &encode_options) {
+            Err(EncodeError::IntegerOutOfRange(_, 8)) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+
+        // i64::MAX UTC seconds at an offset of 60 minutes:
+        let mut buffer = vec!(0xB3, 0x49, 0xCB);
+        buffer.extend_from_slice(&i64::MAX.to_be_bytes());
+        buffer.extend_from_slice(&[0x00, 0x3C]);
+        match DateTime::decode_with(&mut buffer.as_slice(), &decode_options) {
+            Err(DecodeError::IntegerOutOfRange(i64::MAX, _)) => (),
+            r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::*;
+use std::io::{Read, Write};
 
-#[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x66]
+#[derive(Debug, Clone, PartialEq)]
+/// A date time with a time zone id. Depending on the [`PackStreamVersion`](crate::PackStreamVersion)
+/// this is encoded with the tag `0x66` (`V1`) or `0x69` (`V2`). Since zone ids are not resolved,
+/// `seconds` are written and read unchanged; under `V2` they denote UTC seconds.
 pub struct DateTimeZoneId {
     pub seconds: i64,
     pub nanoseconds: i64,
@@ -9,8 +12,87 @@ pub struct DateTimeZoneId {
 }
 
 impl DateTimeZoneId {
+    pub const TAG_V1: u8 = 0x66;
+    pub const TAG_V2: u8 = 0x69;
+
     pub fn utc_nanoseconds(&self) -> i64 {
         // (seconds * 1000000000) + nanoseconds - get_offset_in_nanoseconds(tz_id)
         todo!()
     }
-}
\ No newline at end of file
+
+    fn tag(version: PackStreamVersion) -> u8 {
+        match version {
+            PackStreamVersion::V1 => DateTimeZoneId::TAG_V1,
+            PackStreamVersion::V2 => DateTimeZoneId::TAG_V2,
+        }
+    }
+}
+
+impl Pack for DateTimeZoneId {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        Ok(Marker::Structure(3, DateTimeZoneId::tag(options.version)).encode(writer)?
+            + self.seconds.encode(writer)?
+            + self.nanoseconds.encode(writer)?
+            + self.tz_id.encode(writer)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + 3 * 9
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.seconds.encoded_len() + self.nanoseconds.encoded_len() + self.tz_id.encoded_len()
+    }
+}
+
+impl Unpack for DateTimeZoneId {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        match marker {
+            Marker::Structure(u, tag) => {
+                if u != 3 {
+                    return Err(DecodeError::UnexpectedNumberOfFields(3, u))
+                }
+
+                if tag != DateTimeZoneId::tag(options.version) {
+                    return Err(DecodeError::UnexpectedTagByte(tag))
+                }
+
+                Ok(DateTimeZoneId {
+                    seconds: i64::decode(reader)?,
+                    nanoseconds: i64::decode(reader)?,
+                    tz_id: i64::decode(reader)?,
+                })
+            },
+            _ => Err(DecodeError::UnexpectedMarker(marker))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::std_structs::date_time_zone_id::DateTimeZoneId;
+    use crate::*;
+
+    #[test]
+    fn pack_unpack_v2() {
+        let value = DateTimeZoneId { seconds: 3600, nanoseconds: 0, tz_id: 1 };
+
+        let mut buffer = Vec::new();
+        value.encode_with(&mut buffer, &EncodeOptions { version: PackStreamVersion::V2 }).unwrap();
+        assert_eq!(buffer, vec!(0xB3, 0x69, 0xC9, 0x0E, 0x10, 0x00, 0x01));
+
+        let res =
+            DateTimeZoneId::decode_with(
+                &mut buffer.as_slice(),
+                &DecodeOptions { version: PackStreamVersion::V2, ..DecodeOptions::default() }).unwrap();
+        assert_eq!(value, res);
+    }
+}
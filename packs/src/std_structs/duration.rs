@@ -1,10 +1,14 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x45]
+#[tag(Duration::TAG)]
 pub struct Duration {
     pub months: i64,
     pub days: i64,
     pub seconds: i64,
     pub nanoseconds: i64,
-}
\ No newline at end of file
+}
+
+impl Duration {
+    pub const TAG: u8 = 0x45;
+}
@@ -1,8 +1,12 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x64]
+#[tag(LocalDateTime::TAG)]
 pub struct LocalDateTime {
     pub seconds: i64,
     pub nanoseconds: i64,
 }
+
+impl LocalDateTime {
+    pub const TAG: u8 = 0x64;
+}
@@ -1,7 +1,11 @@
 use crate::*;
 
 #[derive(Clone, PartialEq, Debug, Pack, Unpack)]
-#[tag = 0x74]
+#[tag(LocalTime::TAG)]
 pub struct LocalTime {
     pub nanoseconds: i64,
-}
\ No newline at end of file
+}
+
+impl LocalTime {
+    pub const TAG: u8 = 0x74;
+}
@@ -3,7 +3,7 @@ use crate::*;
 use crate::std_structs::{StdStructPrimitive};
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x4E]
+#[tag(Node::TAG)]
 pub struct Node {
     pub id: i64,
     pub labels: HashSet<String>,
@@ -11,6 +11,8 @@ pub struct Node {
 }
 
 impl Node {
+    pub const TAG: u8 = 0x4E;
+
     pub fn new(id: i64) -> Self {
         Node {
             id,
@@ -2,63 +2,184 @@ use crate::std_structs::relationship::Relationship;
 use crate::std_structs::node::Node;
 use crate::*;
 
+use thiserror::Error;
+use std::convert::TryFrom;
+
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x50]
+#[tag(Path::TAG)]
+/// A path through the graph. Instead of repeating nodes and relationships, a path holds each of
+/// them once and denotes the sequence by `ids`: pairs of a relationship index and a node index,
+/// each pair being one step from the previous node. The path starts at the first node.
+///
+/// Relationship indices start at `1`; a negative index denotes that the relationship is traversed
+/// against its direction. Node indices start at `0`.
 pub struct Path {
     pub nodes: Vec<Node>,
     pub rels: Vec<Relationship>,
     pub ids: Vec<i64>
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// An element of a [`Path`](crate::std_structs::Path) in traversal order, see
+/// [`walk`](crate::std_structs::Path::walk).
+pub enum PathElement<'a> {
+    Node(&'a Node),
+    Rel(&'a Relationship),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PathError {
+    #[error("Path has no start node")]
+    NoStartNode,
+    #[error("Expected pairs of ids, but got {0} ids")]
+    OddNumberOfIds(usize),
+    #[error("Invalid relationship index {0}")]
+    InvalidRelationshipIndex(i64),
+    #[error("Invalid node index {0}")]
+    InvalidNodeIndex(i64),
+}
+
+impl Path {
+    pub const TAG: u8 = 0x50;
+
+    /// Reconstructs the alternating sequence of nodes and relationships from `ids`, starting and
+    /// ending with a node:
+    /// ```
+    /// use packs::std_structs::{Path, Node, Relationship, PathElement};
+    /// # use packs::Dictionary;
+    /// # fn rel(id: i64, start_node_id: i64, end_node_id: i64) -> Relationship {
+    /// #     Relationship { id, start_node_id, end_node_id, _type: String::from("KNOWS"), properties: Dictionary::new() }
+    /// # }
+    ///
+    /// // a -> b <- c
+    /// let path = Path {
+    ///     nodes: vec!(Node::new(0), Node::new(1), Node::new(2)),
+    ///     rels: vec!(rel(10, 0, 1), rel(11, 2, 1)),
+    ///     ids: vec!(1, 1, -2, 2),
+    /// };
+    ///
+    /// let ids : Vec<i64> =
+    ///     path.walk().unwrap().into_iter().map(|e| match e {
+    ///         PathElement::Node(n) => n.id,
+    ///         PathElement::Rel(r) => r.id,
+    ///     }).collect();
+    ///
+    /// assert_eq!(ids, vec!(0, 10, 1, 11, 2));
+    /// ```
+    /// The direction in which a relationship is traversed is not part of the elements; it can be
+    /// read from the relationship's `start_node_id` and `end_node_id`.
+    pub fn walk(&self) -> Result<Vec<PathElement<'_>>, PathError> {
+        let start = self.nodes.first().ok_or(PathError::NoStartNode)?;
+        if !self.ids.len().is_multiple_of(2) {
+            return Err(PathError::OddNumberOfIds(self.ids.len()))
+        }
+
+        let mut elements = Vec::with_capacity(self.ids.len() + 1);
+        elements.push(PathElement::Node(start));
+
+        for step in self.ids.chunks(2) {
+            let (rel_index, node_index) = (step[0], step[1]);
+
+            let rel =
+                usize::try_from(rel_index.unsigned_abs()).ok()
+                    .filter(|i| *i > 0)
+                    .and_then(|i| self.rels.get(i - 1))
+                    .ok_or(PathError::InvalidRelationshipIndex(rel_index))?;
+            let node =
+                usize::try_from(node_index).ok()
+                    .and_then(|i| self.nodes.get(i))
+                    .ok_or(PathError::InvalidNodeIndex(node_index))?;
+
+            elements.push(PathElement::Rel(rel));
+            elements.push(PathElement::Node(node));
+        }
+
+        Ok(elements)
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use crate::packable::test::pack_unpack_test;
     use crate::{Value, Dictionary};
-    use crate::std_structs::path::Path;
+    use crate::std_structs::path::{Path, PathElement, PathError};
     use crate::std_structs::node::Node;
     use crate::std_structs::relationship::Relationship;
 
+    fn path() -> Path {
+        Path {
+            nodes: vec!(
+                Node {
+                    id: 0,
+                    labels: vec!(String::from("Person"), String::from("Author")).into_iter().collect(),
+                    properties: Dictionary::new() },
+                Node {
+                    id: 1,
+                    labels: vec!(String::from("Book")).into_iter().collect(),
+                    properties: vec![(String::from("title"), Value::from("Puh der Bär"))].into_iter().collect()},
+                Node {
+                    id: 4,
+                    labels: vec!(String::from("Person")).into_iter().collect(),
+                    properties: vec![
+                        (String::from("name"), Value::from("Oliver")),
+                        (String::from("age"), Value::from(i32::MAX as i64 + 1))]
+                        .into_iter().collect(),
+                }
+            ),
+
+            rels: vec!(
+                Relationship {
+                    id: 0,
+                    start_node_id: 0,
+                    end_node_id: 1,
+                    _type: String::from("HAS_WRITTEN"),
+                    properties: Dictionary::new() },
+
+                Relationship {
+                    id: 1,
+                    start_node_id: 4,
+                    end_node_id: 1,
+                    _type: String::from("HAS_READ"),
+                    properties: Dictionary::new() },
+            ),
+            ids: vec!(0i64, 0i64, 1i64),
+        }
+    }
+
     #[test]
     fn pack_unpack() {
-        pack_unpack_test::<Path>(&[
-            Path {
-                nodes: vec!(
-                    Node {
-                        id: 0,
-                        labels: vec!(String::from("Person"), String::from("Author")).into_iter().collect(),
-                        properties: Dictionary::new() },
-                    Node {
-                        id: 1,
-                        labels: vec!(String::from("Book")).into_iter().collect(),
-                        properties: vec![(String::from("title"), Value::from("Puh der Bär"))].into_iter().collect()},
-                    Node {
-                        id: 4,
-                        labels: vec!(String::from("Person")).into_iter().collect(),
-                        properties: vec![
-                            (String::from("name"), Value::from("Oliver")),
-                            (String::from("age"), Value::from(i32::max_value() as i64 + 1))]
-                            .into_iter().collect(),
-                    }
-                ),
-
-                rels: vec!(
-                    Relationship {
-                        id: 0,
-                        start_node_id: 0,
-                        end_node_id: 1,
-                        _type: String::from("HAS_WRITTEN"),
-                        properties: Dictionary::new() },
-
-                    Relationship {
-                        id: 1,
-                        start_node_id: 4,
-                        end_node_id: 1,
-                        _type: String::from("HAS_READ"),
-                        properties: Dictionary::new() },
-                ),
-                ids: vec!(0i64, 0i64, 1i64),
-            }
-        ])
+        pack_unpack_test::<Path>(&[path()])
+    }
+
+    #[test]
+    fn walk() {
+        // (0)-[:HAS_WRITTEN]->(1)<-[:HAS_READ]-(4)
+        let path = Path { ids: vec!(1, 1, -2, 2), ..path() };
+        let elements = path.walk().unwrap();
+
+        assert_eq!(
+            elements,
+            vec!(
+                PathElement::Node(&path.nodes[0]),
+                PathElement::Rel(&path.rels[0]),
+                PathElement::Node(&path.nodes[1]),
+                PathElement::Rel(&path.rels[1]),
+                PathElement::Node(&path.nodes[2])));
+    }
+
+    #[test]
+    fn walk_invalid_ids() {
+        let with_ids = |ids: Vec<i64>| Path { ids, ..path() };
+
+        // the ids of the fixture are no valid steps:
+        assert_eq!(path().walk(), Err(PathError::OddNumberOfIds(3)));
+        assert_eq!(with_ids(vec!(0, 0)).walk(), Err(PathError::InvalidRelationshipIndex(0)));
+        assert_eq!(with_ids(vec!(3, 1)).walk(), Err(PathError::InvalidRelationshipIndex(3)));
+        assert_eq!(with_ids(vec!(1, 3)).walk(), Err(PathError::InvalidNodeIndex(3)));
+        assert_eq!(with_ids(vec!(-1, -1)).walk(), Err(PathError::InvalidNodeIndex(-1)));
+
+        let path = with_ids(vec!());
+        assert_eq!(path.walk().unwrap(), vec!(PathElement::Node(&path.nodes[0])));
     }
 }
 
@@ -1,9 +1,13 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x58]
+#[tag(Point2D::TAG)]
 pub struct Point2D {
     pub srid: i64,
     pub x: f64,
     pub y: f64,
-}
\ No newline at end of file
+}
+
+impl Point2D {
+    pub const TAG: u8 = 0x58;
+}
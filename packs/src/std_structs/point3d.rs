@@ -1,10 +1,14 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x59]
+#[tag(Point3D::TAG)]
 pub struct Point3D {
     pub srid: i64,
     pub x: f64,
     pub y: f64,
     pub z: f64,
-}
\ No newline at end of file
+}
+
+impl Point3D {
+    pub const TAG: u8 = 0x59;
+}
@@ -1,8 +1,8 @@
 use crate::*;
-use crate::std_structs::{StdStructPrimitive};
+use crate::std_structs::{StdStruct, StdStructPrimitive, UnboundRelationship, map_structures};
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x52]
+#[tag(Relationship::TAG)]
 pub struct Relationship {
     pub id: i64,
     pub start_node_id: i64,
@@ -12,6 +12,8 @@ pub struct Relationship {
 }
 
 impl Relationship {
+    pub const TAG: u8 = 0x52;
+
     pub fn new(id: i64, _type: &str, from: i64, to: i64) -> Self {
         Relationship {
             id,
@@ -21,6 +23,25 @@ impl Relationship {
             properties: Dictionary::new(),
         }
     }
+
+    /// The relationship without its start and end node, as it appears in a
+    /// [`Path`](crate::std_structs::Path) on the wire. See
+    /// [`UnboundRelationship::bind`](crate::std_structs::UnboundRelationship::bind) for the way back.
+    pub fn unbind(&self) -> UnboundRelationship {
+        let properties = self.properties.properties()
+            .map(|(key, value)| {
+                let value = map_structures(value.clone(), "", &|s, _| Ok(StdStruct::from(s)))
+                    .expect("Primitive structures always convert");
+                (key.clone(), value)
+            })
+            .collect();
+
+        UnboundRelationship {
+            id: self.id,
+            _type: self._type.clone(),
+            properties,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -28,6 +49,7 @@ pub mod test {
     use crate::packable::test::pack_unpack_test;
     use crate::Value;
     use crate::std_structs::relationship::Relationship;
+    use crate::std_structs::{StdStructPrimitive, Date};
 
     #[test]
     fn pack_unpack() {
@@ -41,4 +63,18 @@ pub mod test {
             }
         ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unbind_bind_round_trip() {
+        let mut rel = Relationship::new(42, "KNOWS", 1, 2);
+        rel.properties.add_property("since", Value::Structure(StdStructPrimitive::Date(Date { days: 17 })));
+        rel.properties.add_property("tags", vec!(Value::from("a"), Value::from(1)));
+
+        let unbound = rel.unbind();
+        assert_eq!(unbound.id, 42);
+        assert_eq!(unbound._type, "KNOWS");
+        assert_eq!(unbound.properties.len(), 2);
+
+        assert_eq!(unbound.bind(1, 2), Ok(rel));
+    }
+}
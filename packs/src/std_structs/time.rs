@@ -1,13 +1,15 @@
 use crate::*;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x54]
+#[tag(Time::TAG)]
 pub struct Time {
     pub nanoseconds: i64,
     pub tz_offset_seconds: i64,
 }
 
 impl Time {
+    pub const TAG: u8 = 0x54;
+
     pub fn utc_nanoseconds(&self) -> i64 {
         self.nanoseconds - (self.tz_offset_seconds * 1000000000)
     }
@@ -1,15 +1,58 @@
 use crate::*;
 use std::collections::HashMap;
-use crate::std_structs::StdStruct;
+use crate::std_structs::{StdStruct, StdStructPrimitive, Relationship, ValidationError, graph_struct_name, map_structures};
+use std::convert::TryFrom;
 
 #[derive(Debug, Clone, PartialEq, Pack, Unpack)]
-#[tag = 0x72]
+#[tag(UnboundRelationship::TAG)]
 pub struct UnboundRelationship {
     pub id: i64,
     pub _type: String,
     pub properties: HashMap<String, Value<StdStruct>>,
 }
 
+impl UnboundRelationship {
+    pub const TAG: u8 = 0x72;
+
+    /// Adds the `start` and `end` node ids back, the inverse of
+    /// [`Relationship::unbind`](crate::std_structs::Relationship::unbind). Unlike the properties of a
+    /// relationship, those of an unbound relationship can hold graph structures by their type; these
+    /// are reported as `IllegalPropertyStructure`:
+    /// ```
+    /// use packs::*;
+    /// use packs::std_structs::{Relationship, Node, StdStruct, ValidationError};
+    ///
+    /// let rel = Relationship::new(7, "KNOWS", 1, 2);
+    /// let mut unbound = rel.unbind();
+    /// assert_eq!(unbound.clone().bind(1, 2), Ok(rel));
+    ///
+    /// unbound.properties.insert(String::from("friend"), Value::Structure(StdStruct::Node(Node::new(3))));
+    /// assert_eq!(
+    ///     unbound.bind(1, 2),
+    ///     Err(ValidationError::IllegalPropertyStructure(String::from("properties.friend"), "Node")));
+    /// ```
+    pub fn bind(self, start: i64, end: i64) -> Result<Relationship, ValidationError> {
+        let properties = self.properties.into_iter()
+            .map(|(key, value)| {
+                let value = map_structures(value, &format!("properties.{}", key), &|s, path| {
+                    StdStructPrimitive::try_from(s).map_err(|s| ValidationError::IllegalPropertyStructure(
+                        String::from(path),
+                        graph_struct_name(&s).unwrap_or("structure")))
+                })?;
+                Ok((key, value))
+            })
+            .collect::<Result<_, ValidationError>>()?;
+
+        Ok(Relationship {
+            id: self.id,
+            start_node_id: start,
+            end_node_id: end,
+            _type: self._type,
+            properties,
+        })
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use crate::packable::test::pack_unpack_test;
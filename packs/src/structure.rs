@@ -1,8 +1,11 @@
-use crate::{EncodeError, Marker,DecodeError, Value, Pack, Unpack};
-use crate::ll::types::sized::write_body_by_iter;
+use crate::{EncodeError, Marker,DecodeError, Value, Pack, Unpack, DecodeOptions, EncodeOptions};
+use crate::ll::types::sized::write_body_by_iter_with;
+use crate::options::preallocated;
+use std::convert::TryFrom;
 use std::io::{Read, Write};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An anonymous, generic variant for structure values. It does denote different structures by
 /// a `tag_byte` field; all fields are written and read as [`Value`](crate::value::Value) in the
 /// order in which they were given. This allows for parsing of any structure which is validly
@@ -35,20 +38,45 @@ pub struct GenericStruct {
     pub fields: Vec<Value<GenericStruct>>,
 }
 
+impl GenericStruct {
+    /// The same structure with tag byte `tag`; the fields are kept as they are.
+    pub fn with_tag(mut self, tag: u8) -> Self {
+        self.tag_byte = tag;
+        self
+    }
+}
+
 impl Pack for GenericStruct {
     fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
         Marker::Structure(self.fields.len(), self.tag_byte).encode(writer)?;
-        Ok(2 + write_body_by_iter(&mut self.fields.iter(), writer)?)
+        Ok(2 + write_body_by_iter_with(&mut self.fields.iter(), writer, options)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + self.fields.iter().map(Pack::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.fields.iter().map(Pack::encoded_len).sum::<usize>()
     }
 }
 
 impl Unpack for GenericStruct {
     fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
         match marker {
             Marker::Structure(sz, tag_byte) => {
+                let options = &options.descend()?;
                 let mut res = Vec::with_capacity(sz);
                 for _ in 0..sz {
-                    let val = <Value<GenericStruct>>::decode(reader)?;
+                    let val = <Value<GenericStruct>>::decode_with(reader, options)?;
                     res.push(val);
                 }
 
@@ -62,6 +90,327 @@ impl Unpack for GenericStruct {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// A structure with exactly `N` fields. Like [`GenericStruct`](crate::structure::GenericStruct) it
+/// reads any tag byte and its fields as [`Value`](crate::value::Value), but keeps them in an array
+/// instead of a `Vec`. Decoding a structure with a different number of fields fails with
+/// `UnexpectedNumberOfFields`.
+/// ```
+/// # use packs::{Value, FixedStruct, Pack, Unpack};
+/// let s = FixedStruct::new(0x01, [Value::Integer(1), Value::Integer(2)]);
+///
+/// let mut buffer = Vec::new();
+/// s.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xB2, 0x01, 0x01, 0x02));
+/// assert_eq!(s, FixedStruct::<2>::decode(&mut buffer.as_slice()).unwrap());
+/// assert!(FixedStruct::<3>::decode(&mut buffer.as_slice()).is_err());
+/// ```
+/// A structure can have at most 15 fields, hence using `FixedStruct` with `N > 15` fails to
+/// compile:
+/// ```compile_fail
+/// # use packs::{Value, FixedStruct};
+/// let s = FixedStruct::new(0x01, [(); 16].map(|_| Value::Null));
+/// ```
+pub struct FixedStruct<const N: usize> {
+    pub tag_byte: u8,
+    pub fields: [Value<GenericStruct>; N],
+}
+
+impl<const N: usize> FixedStruct<N> {
+    const VALID_SIZE: () = assert!(N <= 15, "A structure cannot have more than 15 fields.");
+
+    pub fn new(tag_byte: u8, fields: [Value<GenericStruct>; N]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+        FixedStruct {
+            tag_byte,
+            fields,
+        }
+    }
+}
+
+impl<const N: usize> Pack for FixedStruct<N> {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+        Marker::Structure(N, self.tag_byte).encode(writer)?;
+        Ok(2 + write_body_by_iter_with(&mut self.fields.iter(), writer, options)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + self.fields.iter().map(Pack::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.fields.iter().map(Pack::encoded_len).sum::<usize>()
+    }
+}
+
+impl<const N: usize> Unpack for FixedStruct<N> {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+        match marker {
+            Marker::Structure(sz, tag_byte) => {
+                if sz != N {
+                    return Err(DecodeError::UnexpectedNumberOfFields(N, sz))
+                }
+                let options = &options.descend()?;
+
+                // stop reading at the first error and report it afterwards:
+                let mut error = None;
+                let fields = std::array::from_fn(|_| {
+                    if error.is_some() {
+                        return Value::Null
+                    }
+                    <Value<GenericStruct>>::decode_with(reader, options).unwrap_or_else(|e| {
+                        error = Some(e);
+                        Value::Null
+                    })
+                });
+
+                match error {
+                    Some(e) => Err(e),
+                    None => Ok(FixedStruct { tag_byte, fields }),
+                }
+            },
+            _ => Err(DecodeError::UnexpectedMarker(marker))
+        }
+    }
+}
+
+/// The marker byte of an [`ExtStruct`](crate::structure::ExtStruct) with more than 15 fields. It is
+/// one of the bytes the specification reserves, hence every standard decoder rejects it.
+pub const EXT_STRUCT_MARKER: u8 = 0xDF;
+
+#[derive(Debug, Clone, PartialEq)]
+/// **A non-standard extension, which only interoperates with this crate.** Like
+/// [`GenericStruct`](crate::structure::GenericStruct), but without the limit of 15 fields. Up to 15
+/// fields it is a standard structure. With more, it is encoded as the reserved marker byte
+/// [`EXT_STRUCT_MARKER`](crate::structure::EXT_STRUCT_MARKER), the number of fields as an unsigned
+/// LEB128 varint, the tag byte and then the fields:
+/// ```
+/// # use packs::{Value, ExtStruct, GenericStruct, Pack, Unpack, DecodeError};
+/// let s = ExtStruct { tag_byte: 0x01, fields: (0..20).map(Value::Integer).collect() };
+///
+/// let mut buffer = Vec::new();
+/// s.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(&buffer[..3], &[0xDF, 20, 0x01]);
+/// assert_eq!(s, ExtStruct::decode(&mut buffer.as_slice()).unwrap());
+/// ```
+/// The marker byte cannot be mistaken for a standard structure; any other decoder, including
+/// `GenericStruct` and `Value`, fails on it:
+/// ```
+/// # use packs::{Value, ExtStruct, GenericStruct, Pack, Unpack, DecodeError};
+/// # let s = ExtStruct { tag_byte: 0x01, fields: (0..20).map(Value::Integer).collect() };
+/// let buffer = s.to_vec().unwrap();
+///
+/// assert!(matches!(
+///     <Value<GenericStruct>>::decode(&mut buffer.as_slice()),
+///     Err(DecodeError::ReservedMarkerByte(0xDF))));
+/// ```
+/// Only the outermost structure is extended; its fields are read as `Value<GenericStruct>`. Do not
+/// send an `ExtStruct` with more than 15 fields to a Bolt server or any other PackStream
+/// implementation.
+pub struct ExtStruct {
+    pub tag_byte: u8,
+    pub fields: Vec<Value<GenericStruct>>,
+}
+
+impl ExtStruct {
+    /// The length of the header written before the fields.
+    fn header_len(&self) -> usize {
+        if self.fields.len() <= 15 {
+            2
+        } else {
+            2 + varint_len(self.fields.len())
+        }
+    }
+
+    fn decode_fields<T: Read>(len: usize, tag_byte: u8, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let options = &options.descend()?;
+        let mut fields = Vec::with_capacity(preallocated(len));
+        for _ in 0..len {
+            fields.push(<Value<GenericStruct>>::decode_with(reader, options)?);
+        }
+
+        Ok(ExtStruct { tag_byte, fields })
+    }
+}
+
+impl Pack for ExtStruct {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<T: Write>(&self, writer: &mut T, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let written =
+            if self.fields.len() <= 15 {
+                Marker::Structure(self.fields.len(), self.tag_byte).encode(writer)?
+            } else {
+                writer.write_all(&[EXT_STRUCT_MARKER])?;
+                let written = 1 + encode_varint(self.fields.len(), writer)?;
+                writer.write_all(&[self.tag_byte])?;
+                written + 1
+            };
+
+        Ok(written + write_body_by_iter_with(&mut self.fields.iter(), writer, options)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.header_len() + self.fields.iter().map(Pack::size_hint).sum::<usize>()
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.header_len() + self.fields.iter().map(Pack::encoded_len).sum::<usize>()
+    }
+}
+
+/// Reads standard structures as well as extended ones. Since the extended marker byte is no
+/// [`Marker`](crate::ll::marker::Marker), only `decode` and `decode_with` read them; `decode_body`
+/// gets a standard marker.
+impl Unpack for ExtStruct {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<T: Read>(marker: Marker, reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        match marker {
+            Marker::Structure(len, tag_byte) => Self::decode_fields(len, tag_byte, reader, options),
+            _ => Err(DecodeError::UnexpectedMarker(marker))
+        }
+    }
+
+    fn decode<T: Read>(reader: &mut T) -> Result<Self, DecodeError> {
+        Self::decode_with(reader, &DecodeOptions::default())
+    }
+
+    fn decode_with<T: Read>(reader: &mut T, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        let mut first = [0; 1];
+        reader.read_exact(&mut first)?;
+        if first[0] == EXT_STRUCT_MARKER {
+            let len = options.check_collection_len(decode_varint(reader)?)?;
+            let mut tag_byte = [0; 1];
+            reader.read_exact(&mut tag_byte)?;
+            Self::decode_fields(len, tag_byte[0], reader, options)
+        } else {
+            let marker = Marker::decode(&mut first.as_ref().chain(&mut *reader))?;
+            Self::decode_body_with(marker, reader, options)
+        }
+    }
+}
+
+fn varint_len(mut n: usize) -> usize {
+    let mut len = 1;
+    while n >= 0x80 {
+        n >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// Writes `n` as an unsigned LEB128 varint, seven bits per byte, least significant first.
+fn encode_varint<T: Write>(mut n: usize, writer: &mut T) -> Result<usize, EncodeError> {
+    let mut buffer = Vec::with_capacity(varint_len(n));
+    while n >= 0x80 {
+        buffer.push((n & 0x7F) as u8 | 0x80);
+        n >>= 7;
+    }
+    buffer.push(n as u8);
+    writer.write_all(&buffer)?;
+    Ok(buffer.len())
+}
+
+fn decode_varint<T: Read>(reader: &mut T) -> Result<usize, DecodeError> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0; 1];
+        reader.read_exact(&mut byte)?;
+        let bits = u64::from(byte[0] & 0x7F);
+        if bits << shift >> shift != bits {
+            return Err(DecodeError::CannotReadSizeInfo)
+        }
+        n |= bits << shift;
+        if byte[0] & 0x80 == 0 {
+            return usize::try_from(n).map_err(|_| DecodeError::CannotReadSizeInfo)
+        }
+    }
+
+    Err(DecodeError::CannotReadSizeInfo)
+}
+
+/// Builds an encoded structure from fields given one at a time, for when the number of fields is
+/// only known after the last one. Since the number of fields is part of the marker, the fields are
+/// collected in a buffer and written after the marker on
+/// [`finish`](crate::structure::DeferredStructWriter::finish):
+/// ```
+/// use packs::DeferredStructWriter;
+///
+/// let mut s = DeferredStructWriter::new(0x01);
+/// for i in 0..3i64 {
+///     s.write_field(&i).unwrap();
+/// }
+///
+/// let mut buffer = Vec::new();
+/// s.finish(&mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xB3, 0x01, 0x00, 0x01, 0x02));
+/// ```
+pub struct DeferredStructWriter {
+    tag_byte: u8,
+    fields: usize,
+    buffer: Vec<u8>,
+}
+
+impl DeferredStructWriter {
+    pub fn new(tag_byte: u8) -> Self {
+        DeferredStructWriter {
+            tag_byte,
+            fields: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Encodes `field` as the next field.
+    pub fn write_field<P: Pack>(&mut self, field: &P) -> Result<usize, EncodeError> {
+        self.write_field_with(field, &EncodeOptions::default())
+    }
+
+    pub fn write_field_with<P: Pack>(&mut self, field: &P, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        let written = field.encode_with(&mut self.buffer, options)?;
+        self.fields += 1;
+        Ok(written)
+    }
+
+    /// The number of fields written so far.
+    pub fn field_count(&self) -> usize {
+        self.fields
+    }
+
+    /// Writes the structure marker and the fields to `writer`, returning the number of bytes
+    /// written. Fails with `TooManyStructFields`, before writing anything, if more than 15 fields
+    /// have been written.
+    pub fn finish<W: Write>(self, writer: &mut W) -> Result<usize, EncodeError> {
+        if self.fields > 15 {
+            return Err(EncodeError::TooManyStructFields(self.fields))
+        }
+
+        Marker::Structure(self.fields, self.tag_byte).encode(writer)?;
+        writer.write_all(&self.buffer)?;
+        Ok(2 + self.buffer.len())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 /// A void implementation with `Pack` and `Unpack` which can be used as a placeholder to deny any
 /// structures.
@@ -80,4 +429,121 @@ impl Unpack for NoStruct {
             _ => Err(DecodeError::UnexpectedMarker(marker))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::packable::test::{pack_unpack_test, pack_to_test, unpack_to_test};
+    use crate::structure::{FixedStruct, GenericStruct, DeferredStructWriter, ExtStruct};
+    use crate::{Value, Unpack, Pack, DecodeError, EncodeError};
+
+    #[test]
+    fn pack_unpack_fixed_struct() {
+        pack_unpack_test(&[
+            FixedStruct::new(0x01, [Value::Integer(1), Value::from("a"), Value::Null]),
+            FixedStruct::new(0x7F, [Value::Float(0.5), Value::Boolean(true), Value::Integer(-300)]),
+        ]);
+    }
+
+    #[test]
+    fn fixed_struct_reads_generic_struct() {
+        let generic = GenericStruct { tag_byte: 0x02, fields: vec!(Value::Integer(4), Value::Null) };
+        let mut buffer = Vec::new();
+        generic.encode(&mut buffer).unwrap();
+
+        let fixed = FixedStruct::<2>::decode(&mut buffer.as_slice()).unwrap();
+        assert_eq!(fixed.tag_byte, 0x02);
+        assert_eq!(fixed.fields, [Value::Integer(4), Value::Null]);
+
+        match FixedStruct::<1>::decode(&mut buffer.as_slice()) {
+            Err(DecodeError::UnexpectedNumberOfFields(1, 2)) => (),
+            r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn pack_unpack_struct_without_fields() {
+        let empty = || GenericStruct { tag_byte: 0x0F, fields: vec!() };
+        pack_to_test(empty(), &[0xB0, 0x0F]);
+        unpack_to_test(&[0xB0, 0x0F], empty());
+        unpack_to_test(&[0xB0, 0x0F], Value::Structure(empty()));
+        assert_eq!(empty().encoded_len(), 2);
+
+        pack_to_test(FixedStruct::new(0x0E, []), &[0xB0, 0x0E]);
+        unpack_to_test(&[0xB0, 0x0E], FixedStruct::new(0x0E, []));
+    }
+
+    #[test]
+    fn deferred_struct_writer() {
+        let mut s = DeferredStructWriter::new(0x4E);
+        s.write_field(&42i64).unwrap();
+        s.write_field(&vec!(String::from("Person"))).unwrap();
+        s.write_field(&Value::<GenericStruct>::Null).unwrap();
+        assert_eq!(s.field_count(), 3);
+
+        let mut buffer = Vec::new();
+        let written = s.finish(&mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+
+        assert_eq!(
+            GenericStruct::decode(&mut buffer.as_slice()).unwrap(),
+            GenericStruct {
+                tag_byte: 0x4E,
+                fields: vec!(Value::Integer(42), Value::List(vec!(Value::from("Person"))), Value::Null),
+            });
+
+        let mut s = DeferredStructWriter::new(0x01);
+        for _ in 0..16 {
+            s.write_field(&true).unwrap();
+        }
+        let mut buffer = Vec::new();
+        assert!(matches!(s.finish(&mut buffer), Err(EncodeError::TooManyStructFields(16))));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn pack_unpack_ext_struct() {
+        pack_unpack_test(&[
+            ExtStruct { tag_byte: 0x01, fields: (0..20).map(Value::Integer).collect() },
+            ExtStruct { tag_byte: 0x02, fields: (0..300).map(|i| Value::from(i.to_string())).collect() },
+            ExtStruct { tag_byte: 0x03, fields: vec!(Value::Null; 15) },
+            ExtStruct { tag_byte: 0x04, fields: Vec::new() },
+        ]);
+    }
+
+    #[test]
+    fn ext_struct_encoding() {
+        let s = ExtStruct { tag_byte: 0x01, fields: (0..20).map(Value::Integer).collect() };
+        let buffer = s.to_vec().unwrap();
+        assert_eq!(&buffer[..3], &[0xDF, 0x14, 0x01]);
+        assert_eq!(buffer.len(), 3 + 20);
+        assert_eq!(buffer.len(), s.encoded_len());
+
+        // two bytes of varint for 300 fields:
+        let s = ExtStruct { tag_byte: 0x02, fields: vec!(Value::Null; 300) };
+        let buffer = s.to_vec().unwrap();
+        assert_eq!(&buffer[..4], &[0xDF, 0xAC, 0x02, 0x02]);
+        assert_eq!(buffer.len(), s.encoded_len());
+
+        // up to 15 fields it is a standard structure:
+        let s = ExtStruct { tag_byte: 0x03, fields: vec!(Value::Null; 15) };
+        let generic = GenericStruct { tag_byte: 0x03, fields: vec!(Value::Null; 15) };
+        assert_eq!(s.to_vec().unwrap(), generic.to_vec().unwrap());
+    }
+
+    #[test]
+    fn ext_struct_is_not_a_standard_structure() {
+        let buffer = ExtStruct { tag_byte: 0x01, fields: vec!(Value::Null; 20) }.to_vec().unwrap();
+        match GenericStruct::decode(&mut buffer.as_slice()) {
+            Err(DecodeError::ReservedMarkerByte(0xDF)) => (),
+            r => panic!("Expected ReservedMarkerByte, got {:?}", r),
+        }
+
+        // a varint running beyond 64 bits:
+        let bytes: &[u8] = &[0xDF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x01];
+        match ExtStruct::decode(&mut &bytes[..]) {
+            Err(DecodeError::CannotReadSizeInfo) => (),
+            r => panic!("Expected CannotReadSizeInfo, got {:?}", r),
+        }
+    }
+}
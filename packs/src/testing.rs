@@ -0,0 +1,81 @@
+//! # Overview
+//! Assertions and helpers for tests of code which decodes PackStream values.
+use crate::error::DecodeError;
+use crate::packable::Unpack;
+use crate::structure::GenericStruct;
+use crate::value::{Value, ValueKind};
+use crate::value::bytes::Bytes;
+
+/// Asserts that `value` is a structure with tag `tag` whose fields are of the kinds given by
+/// `field_kinds`, in order. Panics with a message naming the first mismatch otherwise:
+/// ```
+/// use packs::*;
+/// use packs::testing::assert_struct_shape;
+///
+/// // a structure with tag `0x4E` and the fields `1`, `["Person"]` and `{}`:
+/// let bytes : &[u8] = &[0xB3, 0x4E, 0x01, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6F, 0x6E, 0xA0];
+/// let value = <Value<GenericStruct>>::decode(&mut &bytes[..]).unwrap();
+///
+/// assert_struct_shape(&value, 0x4E, &[ValueKind::Integer, ValueKind::List, ValueKind::Dictionary]);
+/// ```
+/// ```should_panic
+/// # use packs::*;
+/// # use packs::testing::assert_struct_shape;
+/// let value = Value::Structure(GenericStruct { tag_byte: 0x4E, fields: vec!(Value::Null) });
+///
+/// assert_struct_shape(&value, 0x4E, &[ValueKind::Integer]);
+/// ```
+pub fn assert_struct_shape(value: &Value<GenericStruct>, tag: u8, field_kinds: &[ValueKind]) {
+    let s = match value {
+        Value::Structure(s) => s,
+        v => panic!("Expected a structure, got a value of kind {:?}: {:?}", v.kind(), v),
+    };
+
+    assert_eq!(s.tag_byte, tag, "Expected a structure with tag {:#04X}, got {:#04X}", tag, s.tag_byte);
+    assert_eq!(
+        s.fields.len(), field_kinds.len(),
+        "Expected a structure with {} fields, got {}", field_kinds.len(), s.fields.len());
+
+    for (i, (field, kind)) in s.fields.iter().zip(field_kinds).enumerate() {
+        assert_eq!(
+            field.kind(), *kind,
+            "Expected field {} to be of kind {:?}, got {:?}", i, kind, field);
+    }
+}
+
+/// Decodes a value from a line of hex digits as parsed by
+/// [`Bytes::from_hex`](crate::value::bytes::Bytes::from_hex), e.g. to keep golden files with one
+/// encoded value per line readable. Only the first value of the line is decoded:
+/// ```
+/// use packs::testing::decode_hex_line;
+///
+/// assert_eq!(decode_hex_line::<i64>("C9 00 80").unwrap(), 128);
+/// assert_eq!(decode_hex_line::<String>("8568656C6C6F").unwrap(), "hello");
+/// ```
+pub fn decode_hex_line<U: Unpack>(line: &str) -> Result<U, DecodeError> {
+    let bytes = Bytes::from_hex(line)?;
+    U::decode(&mut bytes.0.as_slice())
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::testing::decode_hex_line;
+    use crate::{DecodeError, Value, GenericStruct};
+
+    #[test]
+    fn decode_hex_lines() {
+        let lines = "C9 00 80\n  2A\t\nC0\n";
+        let values: Vec<Value<GenericStruct>> =
+            lines.lines().map(decode_hex_line).collect::<Result<_, _>>().unwrap();
+        assert_eq!(values, vec!(Value::Integer(128), Value::Integer(42), Value::Null));
+
+        for line in &["C9 0", "XY", "C9 00 8Ä", "+F"] {
+            match decode_hex_line::<i64>(line) {
+                Err(DecodeError::InvalidHex(l)) => assert_eq!(&l, line),
+                r => panic!("Expected InvalidHex, got {:?}", r),
+            }
+        }
+
+        assert!(decode_hex_line::<i64>("C9 00").unwrap_err().is_io());
+    }
+}
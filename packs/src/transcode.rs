@@ -0,0 +1,119 @@
+//! # Overview
+//! Functions which rewrite encoded PackStream values without decoding them into Rust values.
+use std::io::{self, Read};
+use crate::{DecodeError, Marker, Pack, Unpack};
+use crate::ll::io::SliceReader;
+use crate::ll::types::lengths::{body_len_of, BodyLen};
+use crate::skip::walk_value;
+
+/// Rewrites the PackStream values in `input` into `output` such that every integer is encoded
+/// with its minimal marker. Everything else is copied byte by byte; this includes the size
+/// information of strings, lists, etc., even if it is not minimal.
+/// ```
+/// use packs::transcode::compact_bytes;
+///
+/// let input : &[u8] = &[
+///     0x92, // a list of two elements:
+///     0xCB, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A, // 42 as `Int64`
+///     0xD0, 0x01, 0x61, // "a" as `String8`
+/// ];
+/// let mut output = Vec::new();
+/// compact_bytes(input, &mut output).unwrap();
+///
+/// assert_eq!(output, vec!(0x92, 0x2A, 0xD0, 0x01, 0x61));
+/// ```
+/// The values are processed one after another, such that no value is ever held in memory.
+/// Reports an error if `input` does not consist of complete PackStream values; `output` might
+/// have been written to already.
+pub fn compact_bytes(input: &[u8], output: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let mut reader = SliceReader::new(input);
+    while reader.remaining() > 0 {
+        compact_value(&mut reader, output)?;
+    }
+
+    Ok(())
+}
+
+/// Compacts the next complete value of `reader`, see [`walk_value`](crate::skip::walk_value).
+fn compact_value(reader: &mut SliceReader, output: &mut Vec<u8>) -> Result<(), DecodeError> {
+    walk_value(reader, |marker, reader| {
+        match marker {
+            Marker::PlusTinyInt(_) | Marker::MinusTinyInt(_)
+            | Marker::Int8 | Marker::Int16 | Marker::Int32 | Marker::Int64 => {
+                i64::decode_body(marker, reader)?
+                    .encode(output)
+                    .expect("Writing into a Vec cannot fail.");
+                return Ok(BodyLen::Fixed(0))
+            },
+            _ => (),
+        }
+
+        marker.encode(output).expect("Writing into a Vec cannot fail.");
+        let start = reader.remaining_slice();
+        let body_len = body_len_of(marker, reader)?;
+        let size_len = start.len() - reader.remaining();
+        output.extend_from_slice(&start[..size_len]);
+
+        if let BodyLen::Fixed(n) = body_len {
+            if reader.remaining() < n {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+            }
+            let at = output.len();
+            output.resize(at + n, 0);
+            reader.read_exact(&mut output[at..])?;
+        }
+
+        Ok(body_len)
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::transcode::compact_bytes;
+    use crate::{Value, Unpack, GenericStruct};
+
+    #[test]
+    fn compact_nested_values() {
+        let input : &[u8] = &[
+            0xA1, // dictionary with one entry
+            0x81, 0x6B, // "k"
+            0xB2, 0x01, // structure with two fields and tag 0x01
+            0xC9, 0xFF, 0xFF, // -1 as `Int16`
+            0xCC, 0x01, 0xC9, // a byte array containing 0xC9
+            0xCA, 0x00, 0x00, 0x01, 0x00, // 256 as `Int32`
+        ];
+        let mut output = Vec::new();
+        compact_bytes(input, &mut output).unwrap();
+
+        assert_eq!(
+            output,
+            vec!(0xA1, 0x81, 0x6B, 0xB2, 0x01, 0xFF, 0xCC, 0x01, 0xC9, 0xC9, 0x01, 0x00));
+
+        let mut original = input;
+        let mut compacted = output.as_slice();
+        for _ in 0..2 {
+            assert_eq!(
+                <Value<GenericStruct>>::decode(&mut original).unwrap(),
+                <Value<GenericStruct>>::decode(&mut compacted).unwrap());
+        }
+    }
+
+    #[test]
+    fn compact_truncated_input() {
+        let mut output = Vec::new();
+        assert!(compact_bytes(&[0x92, 0x01], &mut output).unwrap_err().is_io());
+        assert!(compact_bytes(&[0xD0, 0x05, 0x61], &mut output).unwrap_err().is_io());
+    }
+
+    #[test]
+    fn compact_deeply_nested_input() {
+        let mut input = vec![0x91; 1_000_000];
+        input.push(0xC9);
+        input.extend_from_slice(&[0x00, 0x01]);
+        let mut output = Vec::new();
+        compact_bytes(&input, &mut output).unwrap();
+
+        assert_eq!(&output[..1_000_000], &input[..1_000_000]);
+        assert_eq!(&output[1_000_000..], &[0x01]);
+    }
+}
@@ -1,5 +1,14 @@
-use crate::{Pack, EncodeError, Unpack, DecodeError};
-use std::io::{Write, Read};
+use crate::{Pack, EncodeError, Unpack, DecodeError, Value, Dictionary};
+use crate::ll::types::lengths::{Length, read_string_size};
+use crate::ll::io::{TeeReader, SliceReader};
+use crate::ll::marker::Marker;
+use crate::value::dictionary::validate_key_len;
+#[cfg(feature = "json")]
+use crate::GenericStruct;
+#[cfg(feature = "json")]
+use crate::value::json::generic_json_string;
+use std::io::{self, Write, Read};
+use std::borrow::Cow;
 
 /// Encodes a given key and value as a property as used by `Dictionary`. This can be used as a flat
 /// shortcut to encode any key-value pair using PackStream. Keys are strings and encoded values can
@@ -15,4 +24,409 @@ pub fn decode_property<T: Read, V: Unpack>(reader: &mut T) -> Result<(String, V)
     let key = String::decode(reader)?;
     let value = V::decode(reader)?;
     Ok((key, value))
-}
\ No newline at end of file
+}
+
+/// Encodes key-value pairs as a `Dictionary` with its entries sorted by key, such that the same
+/// pairs always lead to the same bytes, no matter in which order they are given. This can be used
+/// to encode a dictionary for signing it.
+///
+/// **Note**: The pairs are sorted in place. A key given more than once is reported as
+/// `EncodeError::DuplicateKey`, as the dictionary could not be read back the same.
+/// ```
+/// use packs::Value;
+/// use packs::NoStruct;
+/// use packs::utils::encode_canonical_dict;
+///
+/// let mut pairs : Vec<(String, Value<NoStruct>)> = vec!(
+///     (String::from("b"), Value::Integer(2)),
+///     (String::from("a"), Value::Integer(1)));
+///
+/// let mut buffer = Vec::new();
+/// encode_canonical_dict(&mut pairs, &mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xA2, 0x81, 0x61, 0x01, 0x81, 0x62, 0x02));
+/// assert_eq!(pairs[0].0, "a");
+/// ```
+pub fn encode_canonical_dict<S: Pack, T: Write>(pairs: &mut [(String, Value<S>)], writer: &mut T) -> Result<usize, EncodeError> {
+    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    // the keys are checked first, such that nothing is written for an invalid one; after sorting,
+    // equal keys are neighbours:
+    for (i, (key, _)) in pairs.iter().enumerate() {
+        validate_key_len(key.len())?;
+        if i > 0 && pairs[i - 1].0 == *key {
+            return Err(EncodeError::DuplicateKey(key.clone()))
+        }
+    }
+
+    let len = Length::from_usize(pairs.len()).expect("Dictionary has invalid length");
+    let mut written = len.encode_as_dict_size(writer)?;
+    for (key, value) in pairs.iter() {
+        written += key.encode(writer)? + value.encode(writer)?;
+    }
+
+    Ok(written)
+}
+
+/// Encodes only the entries of `map` for which `keep` returns `true`, without changing `map`. The
+/// kept entries are counted first, since the dictionary's size precedes its entries:
+/// ```
+/// use packs::{Dictionary, NoStruct, Value};
+/// use packs::utils::encode_dict_filtered;
+///
+/// let mut map = Dictionary::<NoStruct>::new();
+/// map.add_property("name", "Jane");
+/// map.add_property("age", Value::Null);
+///
+/// let mut buffer = Vec::new();
+/// encode_dict_filtered(&map, |_, value| *value != Value::Null, &mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0xA1, 0x84, 0x6E, 0x61, 0x6D, 0x65, 0x84, 0x4A, 0x61, 0x6E, 0x65));
+/// assert_eq!(map.len(), 2);
+/// ```
+pub fn encode_dict_filtered<S: Pack, T: Write, F: Fn(&str, &Value<S>) -> bool>(map: &Dictionary<S>, keep: F, writer: &mut T) -> Result<usize, EncodeError> {
+    // the kept keys are checked first, such that nothing is written for an invalid one:
+    let mut count = 0;
+    for (key, _) in map.properties().filter(|(key, value)| keep(key, value)) {
+        validate_key_len(key.len())?;
+        count += 1;
+    }
+
+    let len = Length::from_usize(count).expect("Dictionary has invalid length");
+    let mut written = len.encode_as_dict_size(writer)?;
+    for (key, value) in map.properties().filter(|(key, value)| keep(key, value)) {
+        written += key.encode(writer)? + value.encode(writer)?;
+    }
+
+    Ok(written)
+}
+
+/// Decodes a value and returns it together with the bytes it has been decoded from. Since the same
+/// value can be encoded in different ways, these bytes may differ from the ones `encode` would
+/// write for the value.
+/// ```
+/// use packs::utils::decode_with_raw;
+///
+/// // 42 as `Int16`, which is not the minimal encoding:
+/// let mut bytes : &[u8] = &[0xC9, 0x00, 0x2A, 0x01];
+/// let (value, raw) = decode_with_raw::<_, i64>(&mut bytes).unwrap();
+///
+/// assert_eq!(value, 42);
+/// assert_eq!(raw, vec!(0xC9, 0x00, 0x2A));
+/// assert_eq!(bytes, &[0x01]);
+/// ```
+pub fn decode_with_raw<T: Read, U: Unpack>(reader: &mut T) -> Result<(U, Vec<u8>), DecodeError> {
+    let mut tee = TeeReader::new(reader, Vec::new());
+    let value = U::decode(&mut tee)?;
+    let (_, raw) = tee.into_parts();
+    Ok((value, raw))
+}
+
+/// Writes a list of already encoded elements, i.e. the list header followed by the elements as
+/// they are. Each element needs to be a complete encoded value; only emptiness is checked, as
+/// `EmptyElement`, before anything is written.
+/// ```
+/// use packs::Pack;
+/// use packs::utils::wrap_as_list;
+///
+/// let a = 42i64.to_vec().unwrap();
+/// let b = String::from("b").to_vec().unwrap();
+///
+/// let mut buffer = Vec::new();
+/// wrap_as_list(&[&a, &b], &mut buffer).unwrap();
+///
+/// assert_eq!(buffer, vec!(0x92, 0x2A, 0x81, 0x62));
+/// ```
+pub fn wrap_as_list<T: Write>(elements: &[&[u8]], writer: &mut T) -> Result<usize, EncodeError> {
+    if let Some(i) = elements.iter().position(|e| e.is_empty()) {
+        return Err(EncodeError::EmptyElement(i))
+    }
+
+    let len = Length::from_usize(elements.len()).expect("List has invalid length");
+    let mut written = len.encode_as_list_size(writer)?;
+    for element in elements {
+        writer.write_all(element)?;
+        written += element.len();
+    }
+
+    Ok(written)
+}
+
+/// Decodes the body of a string, denoted by `marker`, directly from a byte slice without copying
+/// it. Returns the string together with the rest of `bytes` following it.
+///
+/// The string borrows from `bytes`. Invalid UTF-8 is reported as `InvalidUtf8`, like decoding a
+/// `String` does, and a body shorter than its size as `ReadIOError`.
+///
+/// A borrowed string cannot outlive the buffer it is decoded from; use `Cow::into_owned` to keep it
+/// beyond that:
+/// ```
+/// use std::borrow::Cow;
+/// use packs::Marker;
+/// use packs::utils::decode_cow_str;
+///
+/// let mut bytes : &[u8] = &[0x85, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x2A];
+/// let marker = Marker::decode(&mut bytes).unwrap();
+/// let (s, rest) = decode_cow_str(marker, bytes).unwrap();
+///
+/// assert!(matches!(s, Cow::Borrowed("hello")));
+/// assert_eq!(rest, &[0x2A]);
+/// ```
+pub fn decode_cow_str(marker: Marker, bytes: &[u8]) -> Result<(Cow<'_, str>, &[u8]), DecodeError> {
+    let mut reader = SliceReader::new(bytes);
+    let len = read_string_size(marker, &mut reader)?;
+    let body = reader.remaining_slice();
+    if body.len() < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+    }
+
+    let (s, rest) = body.split_at(len);
+    let s = std::str::from_utf8(s).map_err(DecodeError::InvalidUtf8)?;
+    Ok((Cow::Borrowed(s), rest))
+}
+
+/// Decodes a single structure directly into the concrete type `P`, e.g. a `Node`, rather than into
+/// a `Value` holding a sum of structures which has to be matched afterwards. Any marker other than
+/// a structure is reported as `UnexpectedMarker`; a structure of another tag is reported by `P`.
+/// ```
+/// use packs::Pack;
+/// use packs::std_structs::Node;
+/// use packs::utils::decode_structure_typed;
+///
+/// let bytes = Node::new(42).to_vec().unwrap();
+/// let node = decode_structure_typed::<_, Node>(&mut bytes.as_slice()).unwrap();
+///
+/// assert_eq!(node, Node::new(42));
+/// assert!(decode_structure_typed::<_, Node>(&mut [0x2A].as_ref()).is_err());
+/// ```
+pub fn decode_structure_typed<T: Read, P: Unpack>(reader: &mut T) -> Result<P, DecodeError> {
+    match Marker::decode(reader)? {
+        marker @ Marker::Structure(_, _) => P::decode_body(marker, reader),
+        marker => Err(DecodeError::UnexpectedMarker(marker)),
+    }
+}
+
+/// Decodes successive values from `reader` until its end and writes each as one line of compact
+/// JSON to `writer`, see [`into_json_string`](crate::value::Value::into_json_string). Structures
+/// are read as [`GenericStruct`](crate::structure::GenericStruct), so rendering them cannot fail.
+/// Returns the number of values transcoded. Requires the feature `json`.
+///
+/// The input has to end between two values; a value which cannot be decoded, including one cut
+/// off by the end of the input, is reported as `Record` with the index of the value. A failure of
+/// `writer` is reported as `WriteIOError`:
+/// ```
+/// use packs::DecodeError;
+/// use packs::utils::transcode_to_ndjson;
+///
+/// let bytes : &[u8] = &[0x2A, 0xA1, 0x81, 0x61, 0xC0, 0x92, 0x01, 0x81, 0x62];
+/// let mut json = Vec::new();
+///
+/// assert_eq!(transcode_to_ndjson(bytes, &mut json).unwrap(), 3);
+/// assert_eq!(String::from_utf8(json).unwrap(), "42\n{\"a\":null}\n[1,\"b\"]\n");
+///
+/// // the second value is cut off:
+/// let bytes : &[u8] = &[0x2A, 0x92, 0x01];
+/// assert!(matches!(
+///     transcode_to_ndjson(bytes, &mut Vec::new()),
+///     Err(DecodeError::Record(1, _))));
+/// ```
+#[cfg(feature = "json")]
+pub fn transcode_to_ndjson<R: Read, W: Write>(mut reader: R, mut writer: W) -> Result<usize, DecodeError> {
+    let mut count = 0;
+    loop {
+        // the end of the input is only fine before the first byte of a value:
+        let mut first = [0];
+        let read =
+            loop {
+                match reader.read(&mut first) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    r => break r.map_err(|e| DecodeError::from(e).in_record(count))?,
+                }
+            };
+        if read == 0 {
+            return Ok(count)
+        }
+
+        let value =
+            <Value<GenericStruct>>::decode(&mut (&first[..]).chain(&mut reader))
+                .map_err(|e| e.in_record(count))?;
+        writeln!(writer, "{}", generic_json_string(&value)).map_err(DecodeError::WriteIOError)?;
+        count += 1;
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::utils::{decode_with_raw, wrap_as_list, decode_cow_str, encode_canonical_dict, encode_dict_filtered};
+    #[cfg(feature = "json")]
+    use crate::{GenericStruct, utils::transcode_to_ndjson};
+    use crate::Marker;
+    use std::borrow::Cow;
+    use crate::{Value, Unpack, Pack, Dictionary, NoStruct, EncodeError, DecodeError};
+
+    #[test]
+    fn raw_bytes_decode_to_same_value() {
+        let bytes : &[u8] = &[
+            0xA2,
+            0x81, 0x61, 0xD0, 0x03, 0x61, 0x62, 0x63, // "a": "abc" as String8
+            0x81, 0x62, 0x92, 0xC8, 0x01, 0xC0, // "b": [1 as Int8, null]
+            0x2A]; // a trailing value
+
+        let mut reader = bytes;
+        let (value, raw) = decode_with_raw::<_, Value<NoStruct>>(&mut reader).unwrap();
+
+        assert_eq!(raw.as_slice(), &bytes[..bytes.len() - 1]);
+        assert_eq!(reader, &[0x2A]);
+
+        let redecoded = <Value<NoStruct>>::decode(&mut raw.as_slice()).unwrap();
+        assert_eq!(value, redecoded);
+
+        let mut expected = Dictionary::new();
+        expected.add_property("a", "abc");
+        expected.add_property("b", vec!(Value::Integer(1), Value::Null));
+        assert_eq!(value, Value::Dictionary(expected));
+    }
+
+    #[test]
+    fn wrap_many_elements() {
+        let elements : Vec<Vec<u8>> = (0..20).map(|i: i64| i.to_vec().unwrap()).collect();
+        let refs : Vec<&[u8]> = elements.iter().map(Vec::as_slice).collect();
+
+        let mut buffer = Vec::new();
+        let written = wrap_as_list(&refs, &mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+
+        let list = <Vec<i64>>::decode(&mut buffer.as_slice()).unwrap();
+        assert_eq!(list, (0..20).collect::<Vec<i64>>());
+
+        let mut buffer = Vec::new();
+        assert!(matches!(wrap_as_list(&[&[0x01], &[]], &mut buffer), Err(EncodeError::EmptyElement(1))));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_cow_str_borrows_valid_utf8() {
+        // "abc" as String8:
+        let bytes : &[u8] = &[0x03, 0x61, 0x62, 0x63, 0xC0];
+        let (s, rest) = decode_cow_str(Marker::String8, bytes).unwrap();
+        assert!(matches!(s, Cow::Borrowed("abc")));
+        assert_eq!(rest, &[0xC0]);
+
+        let (s, rest) = decode_cow_str(Marker::TinyString(0), &[]).unwrap();
+        assert!(matches!(s, Cow::Borrowed("")));
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn decode_cow_str_rejects_invalid_utf8() {
+        let bytes = [0x82, 0x61, 0xFF];
+        let err = decode_cow_str(Marker::TinyString(2), &bytes[1..]).unwrap_err();
+        let expected = String::decode(&mut bytes.as_ref()).unwrap_err();
+
+        match (err, expected) {
+            (DecodeError::InvalidUtf8(e), DecodeError::InvalidUtf8(f)) => assert_eq!(e, f),
+            r => panic!("Expected two InvalidUtf8 errors, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn decode_cow_str_fails_on_truncated_body_or_other_marker() {
+        assert!(decode_cow_str(Marker::TinyString(3), &[0x61]).unwrap_err().is_io());
+        assert!(decode_cow_str(Marker::TinyList(3), &[0x61, 0x62, 0x63]).is_err());
+    }
+
+    #[test]
+    fn encode_dict_without_null_properties() {
+        let mut map = Dictionary::<NoStruct>::new();
+        map.add_property("a", Value::Null);
+        map.add_property("b", 1i64);
+        map.add_property("c", Value::Null);
+        map.add_property("d", Value::List(vec!(Value::Null)));
+
+        let mut buffer = Vec::new();
+        let written = encode_dict_filtered(&map, |_, value| *value != Value::Null, &mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+
+        let decoded = <Dictionary<NoStruct>>::decode(&mut buffer.as_slice()).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded.get_property("b"), Some(&Value::Integer(1)));
+        assert_eq!(decoded.get_property("d"), map.get_property("d"));
+        assert_eq!(map.len(), 4);
+
+        let mut buffer = Vec::new();
+        encode_dict_filtered(&map, |_, _| false, &mut buffer).unwrap();
+        assert_eq!(buffer, vec!(0xA0));
+
+        // an invalid key is only reported if it is kept, before anything is written:
+        map.add_property("", 2i64);
+        let mut buffer = Vec::new();
+        assert!(matches!(
+            encode_dict_filtered(&map, |_, value| *value != Value::Null, &mut buffer),
+            Err(EncodeError::InvalidKey(0))));
+        assert!(buffer.is_empty());
+        encode_dict_filtered(&map, |key, _| !key.is_empty(), &mut buffer).unwrap();
+    }
+
+    #[test]
+    fn encode_canonical_dict_sorts_and_rejects_duplicates() {
+        let mut pairs : Vec<(String, Value<NoStruct>)> = vec!(
+            (String::from("bb"), Value::Integer(3)),
+            (String::from("a"), Value::Integer(1)),
+            (String::from("b"), Value::Integer(2)));
+
+        let mut buffer = Vec::new();
+        let written = encode_canonical_dict(&mut pairs, &mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+        assert_eq!(buffer, vec!(0xA3, 0x81, 0x61, 0x01, 0x81, 0x62, 0x02, 0x82, 0x62, 0x62, 0x03));
+
+        // the same pairs in another order lead to the same bytes:
+        pairs.reverse();
+        let mut reordered = Vec::new();
+        encode_canonical_dict(&mut pairs, &mut reordered).unwrap();
+        assert_eq!(reordered, buffer);
+
+        pairs.push((String::from("b"), Value::Integer(4)));
+        let mut buffer = Vec::new();
+        assert!(matches!(
+            encode_canonical_dict(&mut pairs, &mut buffer),
+            Err(EncodeError::DuplicateKey(key)) if key == "b"));
+        assert!(buffer.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn transcode_records_to_ndjson() {
+        let records: Vec<Value<GenericStruct>> = vec!(
+            Value::Structure(GenericStruct { tag_byte: 0x71, fields: vec!(Value::from("a\nb")) }),
+            Value::Float(0.5),
+            Value::Null,
+        );
+        let mut bytes = Vec::new();
+        for record in &records {
+            record.encode(&mut bytes).unwrap();
+        }
+
+        let mut json = Vec::new();
+        assert_eq!(transcode_to_ndjson(bytes.as_slice(), &mut json).unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"$tag\":113,\"fields\":[\"a\\nb\"]}\n0.5\nnull\n");
+
+        assert_eq!(transcode_to_ndjson(&[][..], &mut Vec::new()).unwrap(), 0);
+
+        // a reserved marker as fourth record:
+        bytes.push(0xC4);
+        match transcode_to_ndjson(bytes.as_slice(), &mut Vec::new()) {
+            Err(DecodeError::Record(3, e)) => assert!(e.is_protocol()),
+            r => panic!("Expected Record(3, _), got {:?}", r),
+        }
+
+        // a writer which fails is no reading error:
+        let mut full = [0u8; 4];
+        bytes.pop();
+        match transcode_to_ndjson(bytes.as_slice(), &mut full[..]) {
+            Err(e @ DecodeError::WriteIOError(_)) => assert!(e.is_io()),
+            r => panic!("Expected WriteIOError, got {:?}", r),
+        }
+    }
+}
@@ -1,10 +1,26 @@
+use std::cmp::Ordering;
 use std::fmt::Debug;
 use crate::value::bytes::Bytes;
 use crate::value::dictionary::Dictionary;
+use crate::error::{NotAList, NotADictionary, CoerceError, KeyPathError};
+use crate::ll::types::fixed::IntWidth;
 use std::iter::FromIterator;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use crate::{Pack, Unpack, EncodeError, DecodeError, DecodeOptions, GenericStruct};
+use crate::options::DEFAULT_MAX_DEPTH;
+use crate::ll::io::{BoundedWriter, LimitedReader};
+#[cfg(feature = "sha2")]
+use crate::ll::io::DigestWriter;
 
 pub mod bytes;
 pub mod dictionary;
+pub mod big_int;
+pub mod display;
+pub mod visitor;
+pub(crate) mod json;
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 
 #[derive(Debug, Clone, PartialEq)]
@@ -69,6 +85,20 @@ pub mod dictionary;
 /// assert_eq!(f64::extract(value).unwrap(), 42.42);
 /// ```
 ///
+/// ## Comparing
+/// A value can be compared with an `i64`, `f64`, `bool` or string directly, in both directions;
+/// it is equal if it is of the corresponding variant with an equal content:
+/// ```
+/// use packs::*;
+///
+/// assert_eq!(Value::<NoStruct>::Integer(42), 42);
+/// assert_eq!(true, Value::<NoStruct>::Boolean(true));
+/// assert_eq!(Value::<NoStruct>::Float(0.5), 0.5);
+/// assert_eq!(Value::<NoStruct>::from("hello"), "hello");
+/// assert_eq!(String::from("hello"), Value::<NoStruct>::from("hello"));
+/// assert_ne!(Value::<NoStruct>::Float(42.0), 42);
+/// ```
+///
 /// ## The Null
 /// PackStream has `Value::Null` as a possible value, denoting the absence of a value. From
 /// within Rust, this transforms any `Value -> T` into a `Value -> Option<T>`, returning `None`
@@ -77,7 +107,7 @@ pub mod dictionary;
 ///
 /// 1. The trait `Extract<T>` has an implementation for `Option<E: Extract<T>>` to treat `Value::Null` as `None`.
 /// 2. The traits `ExtractMut<T>` and `ExtractRef<T>` provide functions with default implementations to extract
-/// any `Value::Null` as a `None` and treat every other `v` as `Some(v)`.
+///    any `Value::Null` as a `None` and treat every other `v` as `Some(v)`.
 /// 3. Otherwise, any extract of `Value::Null` will fail with `None`.
 /// ```
 /// use packs::*;
@@ -97,6 +127,11 @@ pub enum Value<S> {
     Null,
     Boolean(bool),
     Integer(i64),
+    /// An integer together with the width it has been encoded with. It is only decoded with
+    /// [`DecodeOptions::preserve_int_width`](crate::options::DecodeOptions::preserve_int_width) and
+    /// encoded with exactly this width. Note that it is not equal to a `Value::Integer` of the same
+    /// value, but extracts into an `i64` all the same.
+    IntegerWide(i64, IntWidth),
     Float(f64),
     Bytes(Bytes),
     String(String),
@@ -105,6 +140,752 @@ pub enum Value<S> {
     Structure(S)
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// The kind of a [`Value`](crate::value::Value), i.e. its variant without its content.
+pub enum ValueKind {
+    Null,
+    Boolean,
+    Integer,
+    Float,
+    Bytes,
+    String,
+    List,
+    Dictionary,
+    Structure,
+}
+
+impl<S: Unpack> Value<S> {
+    /// Decodes a value whose lists, dictionaries and structures are nested at most `max_depth`
+    /// levels deep, reporting `DepthLimitExceeded` otherwise. The plain `decode` uses a limit of
+    /// [`DEFAULT_MAX_DEPTH`](crate::options::DEFAULT_MAX_DEPTH).
+    /// ```
+    /// use packs::{Value, NoStruct, DecodeError};
+    ///
+    /// let bytes : &[u8] = &[0x91, 0x91, 0x90]; // [[[]]]
+    ///
+    /// assert!(Value::<NoStruct>::decode_with_limit(&mut &bytes[..], 3).is_ok());
+    /// assert!(matches!(
+    ///     Value::<NoStruct>::decode_with_limit(&mut &bytes[..], 2),
+    ///     Err(DecodeError::DepthLimitExceeded)));
+    /// ```
+    pub fn decode_with_limit<T: Read>(reader: &mut T, max_depth: usize) -> Result<Self, DecodeError> {
+        let options = DecodeOptions { max_depth: Some(max_depth), ..DecodeOptions::default() };
+        Self::decode_with(reader, &options)
+    }
+}
+
+impl Value<GenericStruct> {
+    /// Decodes a value from an untrusted source, reading at most `max_bytes` bytes from `reader`.
+    /// Besides the byte budget, which is reported as `ByteLimitExceeded` once exhausted, the
+    /// decoding is bounded by:
+    ///
+    /// | option | value |
+    /// | :--- | :--- |
+    /// | `max_depth` | [`DEFAULT_MAX_DEPTH`](crate::options::DEFAULT_MAX_DEPTH) |
+    /// | `max_collection_len` | `max_bytes`, since every item takes at least one byte |
+    /// | `max_string_len` | `max_bytes` |
+    ///
+    /// Hence neither the size a value declares nor its nesting can make the decoding allocate
+    /// more or recurse deeper than the input could actually fill. Any structure is read as a
+    /// [`GenericStruct`](crate::structure::GenericStruct):
+    /// ```
+    /// use packs::{Value, DecodeError};
+    ///
+    /// // a `List32` claiming 2^31 - 1 items:
+    /// let bytes : &[u8] = &[0xD6, 0x7F, 0xFF, 0xFF, 0xFF];
+    /// assert!(matches!(
+    ///     Value::decode_limited(&mut &bytes[..], 1024),
+    ///     Err(DecodeError::SizeLimitExceeded(0x7FFF_FFFF, 1024))));
+    ///
+    /// // [1, 2, 3]:
+    /// let bytes : &[u8] = &[0x93, 0x01, 0x02, 0x03];
+    /// assert!(matches!(
+    ///     Value::decode_limited(&mut &bytes[..], 3),
+    ///     Err(DecodeError::ByteLimitExceeded(3))));
+    /// assert!(Value::decode_limited(&mut &bytes[..], 4).is_ok());
+    /// ```
+    pub fn decode_limited<T: Read>(reader: &mut T, max_bytes: usize) -> Result<Self, DecodeError> {
+        let options = DecodeOptions {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_collection_len: Some(max_bytes),
+            max_string_len: Some(max_bytes),
+            ..DecodeOptions::default()
+        };
+
+        let mut limited = LimitedReader::new(reader, max_bytes);
+        Self::decode_with(&mut limited, &options)
+            .map_err(|e| {
+                if limited.is_exceeded() {
+                    DecodeError::ByteLimitExceeded(max_bytes)
+                } else {
+                    e
+                }
+            })
+    }
+
+    /// Replaces the tag byte `t` of every structure by `f(t)`, for this value and all values
+    /// nested into it, including the fields of structures, e.g. to translate between protocol
+    /// versions which number their structures differently:
+    /// ```
+    /// use packs::{Value, GenericStruct};
+    ///
+    /// let inner = GenericStruct { tag_byte: 0x46, fields: vec!(Value::Integer(0)) };
+    /// let mut value = Value::List(vec!(
+    ///     Value::Structure(GenericStruct { tag_byte: 0x01, fields: vec!(Value::Structure(inner)) }),
+    /// ));
+    ///
+    /// value.remap_structure_tags(|tag| if tag == 0x46 { 0x49 } else { tag });
+    ///
+    /// let inner = GenericStruct { tag_byte: 0x49, fields: vec!(Value::Integer(0)) };
+    /// assert_eq!(value, Value::List(vec!(
+    ///     Value::Structure(GenericStruct { tag_byte: 0x01, fields: vec!(Value::Structure(inner)) }),
+    /// )));
+    /// ```
+    pub fn remap_structure_tags<F: Fn(u8) -> u8>(&mut self, f: F) {
+        self.remap_structure_tags_with(&f)
+    }
+
+    fn remap_structure_tags_with<F: Fn(u8) -> u8>(&mut self, f: &F) {
+        match self {
+            Value::Structure(s) => {
+                s.tag_byte = f(s.tag_byte);
+                for v in s.fields.iter_mut() {
+                    v.remap_structure_tags_with(f);
+                }
+            },
+            Value::List(list) => {
+                for v in list.iter_mut() {
+                    v.remap_structure_tags_with(f);
+                }
+            },
+            Value::Dictionary(dict) => {
+                for (_, v) in dict.properties_mut() {
+                    v.remap_structure_tags_with(f);
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+impl<S: Pack> Value<S> {
+    /// Encodes the value into `writer`, writing at most `max_bytes` bytes. Once the encoding would
+    /// cross the limit, it stops and reports `OutputTooLarge`; the bytes written up to then stay in
+    /// `writer`. This is the counterpart of [`decode_limited`](crate::value::Value::decode_limited)
+    /// for the encoding side:
+    /// ```
+    /// use packs::{Value, NoStruct, EncodeError};
+    ///
+    /// let value : Value<NoStruct> = Value::from("hello");
+    ///
+    /// let mut buffer = Vec::new();
+    /// assert!(matches!(
+    ///     value.encode_bounded(&mut buffer, 5),
+    ///     Err(EncodeError::OutputTooLarge { limit: 5, needed: None })));
+    ///
+    /// let mut buffer = Vec::new();
+    /// assert_eq!(value.encode_bounded(&mut buffer, 6).unwrap(), 6);
+    /// ```
+    pub fn encode_bounded<T: Write>(&self, writer: &mut T, max_bytes: usize) -> Result<usize, EncodeError> {
+        let mut bounded = BoundedWriter::new(writer, max_bytes);
+        self.encode(&mut bounded)
+            .map_err(|e| {
+                if bounded.is_exceeded() {
+                    EncodeError::OutputTooLarge { limit: max_bytes, needed: None }
+                } else {
+                    e
+                }
+            })
+    }
+}
+
+#[cfg(feature = "sha2")]
+impl<S: Pack> Value<S> {
+    /// Encodes the value into `writer` while computing the SHA-256 digest of the written bytes in
+    /// the same pass. Returns the number of bytes written together with the digest.
+    /// ```
+    /// use packs::{Value, NoStruct};
+    ///
+    /// let value : Value<NoStruct> = Value::String(String::from("hello"));
+    /// let mut buffer = Vec::new();
+    /// let (written, digest) = value.encode_with_digest(&mut buffer).unwrap();
+    ///
+    /// assert_eq!(written, buffer.len());
+    /// assert_eq!(digest.len(), 32);
+    /// ```
+    pub fn encode_with_digest<T: Write>(&self, writer: &mut T) -> Result<(usize, [u8; 32]), EncodeError> {
+        let mut digest_writer = DigestWriter::new(writer);
+        let written = self.encode(&mut digest_writer)?;
+        let (_, digest) = digest_writer.finalize();
+        Ok((written, digest))
+    }
+}
+
+impl<S> Value<S> {
+    /// An empty `Value::List` with room for `capacity` elements, e.g. to be filled by
+    /// [`push`](crate::value::Value::push).
+    pub fn list_with_capacity(capacity: usize) -> Self {
+        Value::List(Vec::with_capacity(capacity))
+    }
+
+    /// An empty `Value::Dictionary` with room for `capacity` entries, e.g. to be filled by
+    /// [`insert_entry`](crate::value::Value::insert_entry).
+    pub fn dict_with_capacity(capacity: usize) -> Self {
+        Value::Dictionary(Dictionary::with_capacity(capacity))
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Null => ValueKind::Null,
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::Integer(_) | Value::IntegerWide(_, _) => ValueKind::Integer,
+            Value::Float(_) => ValueKind::Float,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::String(_) => ValueKind::String,
+            Value::List(_) => ValueKind::List,
+            Value::Dictionary(_) => ValueKind::Dictionary,
+            Value::Structure(_) => ValueKind::Structure,
+        }
+    }
+
+    /// The name of the variant, e.g. for messages like "expected Integer, got String". An
+    /// `IntegerWide` is named `"Integer"`, as its [`kind`](crate::value::Value::kind) is `Integer`.
+    pub fn type_name(&self) -> &'static str {
+        match self.kind() {
+            ValueKind::Null => "Null",
+            ValueKind::Boolean => "Boolean",
+            ValueKind::Integer => "Integer",
+            ValueKind::Float => "Float",
+            ValueKind::Bytes => "Bytes",
+            ValueKind::String => "String",
+            ValueKind::List => "List",
+            ValueKind::Dictionary => "Dictionary",
+            ValueKind::Structure => "Structure",
+        }
+    }
+
+    /// The property `key` of a dictionary; `None` if it is missing or the value is no dictionary.
+    pub fn get(&self, key: &str) -> Option<&Value<S>> {
+        match self {
+            Value::Dictionary(dict) => dict.get_property(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value<S>> {
+        match self {
+            Value::Dictionary(dict) => dict.get_mut(key),
+            _ => None,
+        }
+    }
+
+    /// The item at index `i` of a list; `None` if it is out of range or the value is no list.
+    pub fn get_index(&self, i: usize) -> Option<&Value<S>> {
+        match self {
+            Value::List(list) => list.get(i),
+            _ => None,
+        }
+    }
+
+    pub fn get_index_mut(&mut self, i: usize) -> Option<&mut Value<S>> {
+        match self {
+            Value::List(list) => list.get_mut(i),
+            _ => None,
+        }
+    }
+
+    /// Walks a path of dictionary keys and list indices separated by `/`, with an optional leading
+    /// `/`; the empty path denotes the value itself. A segment selects the property of that name
+    /// in a dictionary and the item at that index in a list. There is no escaping, hence keys
+    /// containing `/` cannot be reached:
+    /// ```
+    /// use packs::*;
+    ///
+    /// let mut field = Dictionary::new();
+    /// field.add_property("name", "id");
+    /// let mut record = Dictionary::<NoStruct>::new();
+    /// record.add_property("fields", vec!(Value::Dictionary(field)));
+    /// let record = Value::Dictionary(record);
+    ///
+    /// assert_eq!(record.pointer("/fields/0/name"), Some(&Value::from("id")));
+    /// assert_eq!(record.get("fields").and_then(|f| f.get_index(0)), record.pointer("fields/0"));
+    /// assert_eq!(record.pointer("/fields/1/name"), None);
+    /// ```
+    pub fn pointer(&self, path: &str) -> Option<&Value<S>> {
+        path_segments(path).try_fold(self, |value, segment|
+            match value {
+                Value::List(list) => segment.parse().ok().and_then(|i: usize| list.get(i)),
+                value => value.get(segment),
+            })
+    }
+
+    pub fn pointer_mut(&mut self, path: &str) -> Option<&mut Value<S>> {
+        path_segments(path).try_fold(self, |value, segment|
+            match value {
+                Value::List(list) => segment.parse().ok().and_then(move |i: usize| list.get_mut(i)),
+                value => value.get_mut(segment),
+            })
+    }
+
+    /// The integer, also one of [`IntegerWide`](crate::value::Value::IntegerWide):
+    /// ```
+    /// # use packs::{Value, NoStruct};
+    /// assert_eq!(Value::<NoStruct>::Integer(42).as_i64(), Some(42));
+    /// assert_eq!(Value::<NoStruct>::Float(42.0).as_i64(), None);
+    /// ```
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) | Value::IntegerWide(i, _) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The float; an integer is not converted, see [`coerce`](crate::value::Value::coerce) for that:
+    /// ```
+    /// # use packs::{Value, NoStruct};
+    /// assert_eq!(Value::<NoStruct>::Float(0.5).as_f64(), Some(0.5));
+    /// assert_eq!(Value::<NoStruct>::Integer(1).as_f64(), None);
+    /// ```
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, NoStruct};
+    /// assert_eq!(Value::<NoStruct>::Boolean(true).as_bool(), Some(true));
+    /// assert_eq!(Value::<NoStruct>::Null.as_bool(), None);
+    /// ```
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, NoStruct};
+    /// assert_eq!(Value::<NoStruct>::from("hello").as_str(), Some("hello"));
+    /// assert_eq!(Value::<NoStruct>::Integer(1).as_str(), None);
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, NoStruct, Bytes};
+    /// assert_eq!(Value::<NoStruct>::Bytes(Bytes(vec!(0x01, 0x02))).as_bytes(), Some(&[0x01, 0x02][..]));
+    /// assert_eq!(Value::<NoStruct>::from("\u{1}").as_bytes(), None);
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(&bytes.0),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, NoStruct};
+    /// let value : Value<NoStruct> = vec!(Value::Integer(1), Value::Null).into_iter().collect();
+    /// assert_eq!(value.as_list(), Some(&[Value::Integer(1), Value::Null][..]));
+    /// assert_eq!(Value::<NoStruct>::Null.as_list(), None);
+    /// ```
+    pub fn as_list(&self) -> Option<&[Value<S>]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, NoStruct, Dictionary};
+    /// let mut dict = Dictionary::new();
+    /// dict.add_property("name", "Alice");
+    /// let value : Value<NoStruct> = Value::Dictionary(dict);
+    ///
+    /// assert_eq!(value.as_dict().and_then(|d| d.get_property("name")), Some(&Value::from("Alice")));
+    /// assert!(Value::<NoStruct>::Null.as_dict().is_none());
+    /// ```
+    pub fn as_dict(&self) -> Option<&Dictionary<S>> {
+        match self {
+            Value::Dictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// ```
+    /// # use packs::{Value, GenericStruct};
+    /// let value = Value::Structure(GenericStruct { tag_byte: 0x01, fields: Vec::new() });
+    /// assert_eq!(value.as_struct().map(|s| s.tag_byte), Some(0x01));
+    /// assert!(Value::<GenericStruct>::Null.as_struct().is_none());
+    /// ```
+    pub fn as_struct(&self) -> Option<&S> {
+        match self {
+            Value::Structure(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Converts the value into a value of kind `target`, as far as there is a sensible conversion.
+    /// Unlike [`Extract`](crate::value::Extract) this might lose information. A value of kind
+    /// `target` is returned as it is; otherwise these conversions are supported:
+    ///
+    /// | from | to | conversion |
+    /// | :--- | :--- | :--- |
+    /// | `Integer` | `Float` | `i as f64` |
+    /// | `Float` | `Integer` | truncated towards zero; fails if not finite or out of range |
+    /// | `Integer` | `Boolean` | `0` is `false`, `1` is `true`; fails otherwise |
+    /// | `Boolean` | `Integer` | `false` is `0`, `true` is `1` |
+    /// | `Integer`, `Float`, `Boolean` | `String` | formatted by `to_string` |
+    /// | `String` | `Integer`, `Float`, `Boolean` | parsed, ignoring surrounding whitespace; fails if it cannot be parsed |
+    ///
+    /// Any other conversion fails with a `CoerceError`. A `Value::IntegerWide` is an `Integer` here and
+    /// loses its width when converted.
+    /// ```
+    /// use packs::{Value, ValueKind, NoStruct};
+    ///
+    /// let value : Value<NoStruct> = Value::from(" 42 ");
+    /// assert_eq!(value.coerce(ValueKind::Integer).unwrap(), Value::Integer(42));
+    ///
+    /// let value : Value<NoStruct> = Value::Integer(2);
+    /// assert!(value.coerce(ValueKind::Boolean).is_err());
+    /// ```
+    pub fn coerce(self, target: ValueKind) -> Result<Value<S>, CoerceError> {
+        let from = self.kind();
+        if from == target {
+            return Ok(self)
+        }
+
+        let error = CoerceError { from, to: target };
+        let value = match self {
+            Value::IntegerWide(i, _) => Value::Integer(i),
+            v => v,
+        };
+
+        match (value, target) {
+            (Value::Integer(i), ValueKind::Float) => Ok(Value::Float(i as f64)),
+            (Value::Float(f), ValueKind::Integer) => {
+                // `i64::MAX as f64` is 2^63, which is out of range already:
+                if f.is_finite() && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+                    Ok(Value::Integer(f as i64))
+                } else {
+                    Err(error)
+                }
+            },
+            (Value::Integer(0), ValueKind::Boolean) => Ok(Value::Boolean(false)),
+            (Value::Integer(1), ValueKind::Boolean) => Ok(Value::Boolean(true)),
+            (Value::Boolean(b), ValueKind::Integer) => Ok(Value::Integer(i64::from(b))),
+            (Value::Integer(i), ValueKind::String) => Ok(Value::String(i.to_string())),
+            (Value::Float(f), ValueKind::String) => Ok(Value::String(f.to_string())),
+            (Value::Boolean(b), ValueKind::String) => Ok(Value::String(b.to_string())),
+            (Value::String(s), ValueKind::Integer) =>
+                s.trim().parse().map(Value::Integer).map_err(|_| error),
+            (Value::String(s), ValueKind::Float) =>
+                s.trim().parse().map(Value::Float).map_err(|_| error),
+            (Value::String(s), ValueKind::Boolean) =>
+                s.trim().parse().map(Value::Boolean).map_err(|_| error),
+            _ => Err(error),
+        }
+    }
+
+    /// Applies `f` to every `Value::String` in this value, including the elements of lists and the
+    /// values of dictionaries, recursively. Dictionary keys are left as they are, see
+    /// [`map_strings_keys`](crate::value::Value::map_strings_keys). Structures are not visited.
+    /// ```
+    /// use packs::{Value, NoStruct};
+    ///
+    /// let mut value : Value<NoStruct> =
+    ///     vec!(Value::from("secret"), Value::Integer(42)).into_iter().collect();
+    ///
+    /// value.map_strings(|s| *s = "*".repeat(s.len()));
+    ///
+    /// assert_eq!(value, vec!(Value::from("******"), Value::Integer(42)).into_iter().collect::<Value<NoStruct>>());
+    /// ```
+    pub fn map_strings<F: FnMut(&mut String)>(&mut self, mut f: F) {
+        self.map_strings_with(&mut f, false)
+    }
+
+    /// Like [`map_strings`](crate::value::Value::map_strings), but applies `f` to the keys of all
+    /// dictionaries as well. If two keys of a dictionary are mapped to the same key, only one of
+    /// their entries is kept.
+    pub fn map_strings_keys<F: FnMut(&mut String)>(&mut self, mut f: F) {
+        self.map_strings_with(&mut f, true)
+    }
+
+    /// Appends `v` to a `Value::List`. A `Value::Null` is treated as an empty list and turned into a
+    /// list holding `v`. Any other value is left untouched and reported as `NotAList`.
+    /// ```
+    /// use packs::{Value, NoStruct, NotAList};
+    ///
+    /// let mut value : Value<NoStruct> = Value::Null;
+    /// value.push(1).unwrap();
+    /// value.push("two").unwrap();
+    ///
+    /// assert_eq!(value, Value::List(vec!(Value::Integer(1), Value::from("two"))));
+    /// assert_eq!(Value::<NoStruct>::Integer(0).push(1), Err(NotAList));
+    /// ```
+    pub fn push<V: Into<Value<S>>>(&mut self, v: V) -> Result<(), NotAList> {
+        if let Value::Null = self {
+            *self = Value::List(Vec::new());
+        }
+
+        match self {
+            Value::List(list) => {
+                list.push(v.into());
+                Ok(())
+            },
+            _ => Err(NotAList),
+        }
+    }
+
+    /// Inserts the property `key` with value `v` into a `Value::Dictionary`, returning the former
+    /// value of the property if it was set. Analogous to [`push`](crate::value::Value::push), a
+    /// `Value::Null` is treated as an empty dictionary and any other value is reported as
+    /// `NotADictionary`.
+    pub fn insert_entry<V: Into<Value<S>>>(&mut self, key: &str, v: V) -> Result<Option<Value<S>>, NotADictionary> {
+        if let Value::Null = self {
+            *self = Value::Dictionary(Dictionary::new());
+        }
+
+        match self {
+            Value::Dictionary(dict) => Ok(dict.add_property(key, v)),
+            _ => Err(NotADictionary),
+        }
+    }
+
+    /// Sets the value at a path of dictionary keys separated by `.`, creating the dictionaries on the
+    /// way. Like for [`insert_entry`](crate::value::Value::insert_entry), a `Value::Null` on the
+    /// way is replaced by a dictionary; any other value is reported as `NotADictionary` together
+    /// with the path leading to it. An empty key, as in `"a..b"` or `""`, is reported as
+    /// `EmptySegment`:
+    /// ```
+    /// use packs::*;
+    ///
+    /// let mut config : Value<NoStruct> = Value::Null;
+    /// config.set_path("server.port", Value::from(7687)).unwrap();
+    /// config.set_path("server.host", Value::from("localhost")).unwrap();
+    ///
+    /// assert_eq!(config.get_path("server.port"), Some(&Value::Integer(7687)));
+    /// assert_eq!(
+    ///     config.set_path("server.port.tls", Value::Boolean(true)),
+    ///     Err(KeyPathError::NotADictionary(String::from("server.port"), ValueKind::Integer)));
+    /// ```
+    pub fn set_path(&mut self, path: &str, value: Value<S>) -> Result<(), KeyPathError> {
+        if path.split('.').any(str::is_empty) {
+            return Err(KeyPathError::EmptySegment(String::from(path)))
+        }
+
+        // the key at `start` is the next one, `path[..start - 1]` the path to the current value:
+        let mut current = self;
+        let mut start = 0usize;
+        for (end, _) in path.match_indices('.') {
+            current = current.dict_for_path(&path[..start.saturating_sub(1)])?
+                .get_or_insert_with(&path[start..end], || Value::Null);
+            start = end + 1;
+        }
+
+        current.dict_for_path(&path[..start.saturating_sub(1)])?.add_property(&path[start..], value);
+        Ok(())
+    }
+
+    /// The dictionary of this value at `path`, replacing `Value::Null` by an empty one.
+    fn dict_for_path(&mut self, path: &str) -> Result<&mut Dictionary<S>, KeyPathError> {
+        if let Value::Null = self {
+            *self = Value::Dictionary(Dictionary::new());
+        }
+
+        match self {
+            Value::Dictionary(dict) => Ok(dict),
+            value => Err(KeyPathError::NotADictionary(String::from(path), value.kind())),
+        }
+    }
+
+    /// The value at a path of dictionary keys separated by `.`, see
+    /// [`set_path`](crate::value::Value::set_path); `None` if a key is missing or a value on the way
+    /// is no dictionary. Unlike [`pointer`](crate::value::Value::pointer), lists are not entered.
+    pub fn get_path(&self, path: &str) -> Option<&Value<S>> {
+        path.split('.').try_fold(self, |value, key| value.get(key))
+    }
+
+    /// Removes every dictionary entry whose key fails `keep`, in this value and in all dictionaries
+    /// and lists nested into it. Structures are left untouched.
+    /// ```
+    /// use packs::{Value, NoStruct, Dictionary};
+    ///
+    /// let mut dict = Dictionary::new();
+    /// dict.add_property("name", "alice");
+    /// dict.add_property("_internal", 42);
+    /// let mut value : Value<NoStruct> = Value::List(vec!(Value::Dictionary(dict)));
+    ///
+    /// value.retain_keys(|key| !key.starts_with('_'));
+    ///
+    /// let mut expected = Dictionary::new();
+    /// expected.add_property("name", "alice");
+    /// assert_eq!(value, Value::List(vec!(Value::Dictionary(expected))));
+    /// ```
+    pub fn retain_keys<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        self.retain_keys_with(&keep)
+    }
+
+    /// Replaces every list holding exactly one item by this item, in this value and in all
+    /// dictionaries and lists nested into it; lists wrapped into each other collapse completely.
+    /// Structures are left untouched. Note that this changes the shape of the data: a list of one
+    /// item and the item itself can no longer be told apart, so it is meant to clean up input of
+    /// producers which wrap single values needlessly, not as part of decoding.
+    /// ```
+    /// use packs::{Value, NoStruct};
+    ///
+    /// let mut value : Value<NoStruct> = Value::List(vec!(
+    ///     Value::List(vec!(Value::List(vec!(Value::Integer(1))))),
+    ///     Value::List(vec!(Value::Integer(2), Value::List(vec!(Value::from("x"))))),
+    /// ));
+    ///
+    /// value.flatten_singletons();
+    ///
+    /// assert_eq!(value, Value::List(vec!(
+    ///     Value::Integer(1),
+    ///     Value::List(vec!(Value::Integer(2), Value::from("x"))),
+    /// )));
+    /// ```
+    pub fn flatten_singletons(&mut self) {
+        match self {
+            Value::List(list) => {
+                for v in list.iter_mut() {
+                    v.flatten_singletons();
+                }
+                if list.len() == 1 {
+                    *self = list.pop().unwrap();
+                }
+            },
+            Value::Dictionary(dict) => {
+                for (_, v) in dict.properties_mut() {
+                    v.flatten_singletons();
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// A total order on values, like [`f64::total_cmp`] for floats. Values of different kinds are
+    /// ordered by their kind, in the order of the [`ValueKind`](crate::value::ValueKind) variants,
+    /// i.e. `Null` first and structures last. Within a kind, floats are ordered by
+    /// `f64::total_cmp`, lists lexicographically and dictionaries by their size first, then by
+    /// their entries sorted by key. An `IntegerWide` is ordered by its value, like an `Integer`.
+    /// Structures are opaque and all equal to each other.
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use packs::{Value, NoStruct};
+    ///
+    /// let a : Value<NoStruct> = Value::Integer(7);
+    /// assert_eq!(a.total_cmp(&Value::Integer(8)), Ordering::Less);
+    /// assert_eq!(a.total_cmp(&Value::Null), Ordering::Greater);
+    /// assert_eq!(a.total_cmp(&Value::Float(0.0)), Ordering::Less);
+    /// ```
+    pub fn total_cmp(&self, other: &Value<S>) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Integer(a) | Value::IntegerWide(a, _), Value::Integer(b) | Value::IntegerWide(b, _)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.0.cmp(&b.0),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => cmp_lexicographic(a.iter(), b.iter()),
+            (Value::Dictionary(a), Value::Dictionary(b)) => {
+                let mut a: Vec<_> = a.properties().collect();
+                let mut b: Vec<_> = b.properties().collect();
+                a.sort_by_key(|(k, _)| *k);
+                b.sort_by_key(|(k, _)| *k);
+                a.len().cmp(&b.len()).then_with(|| {
+                    a.iter().zip(&b)
+                        .map(|((k, v), (l, w))| k.cmp(l).then_with(|| v.total_cmp(w)))
+                        .find(|o| *o != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                })
+            },
+            _ => (self.kind() as u8).cmp(&(other.kind() as u8)),
+        }
+    }
+
+    /// Sorts every list by [`total_cmp`](crate::value::Value::total_cmp), in this value and in all
+    /// dictionaries and lists nested into it, for data where the order of lists is irrelevant. Two
+    /// values which only differ in the order of their lists are equal afterwards. Structures are
+    /// left untouched and keep their order among each other.
+    /// ```
+    /// use packs::{Value, NoStruct};
+    ///
+    /// let mut value : Value<NoStruct> = Value::List(vec!(
+    ///     Value::from("b"), Value::Null, Value::from("a"), Value::Integer(1)));
+    ///
+    /// value.sort_lists();
+    ///
+    /// assert_eq!(value, Value::List(vec!(
+    ///     Value::Null, Value::Integer(1), Value::from("a"), Value::from("b"))));
+    /// ```
+    pub fn sort_lists(&mut self) {
+        match self {
+            Value::List(list) => {
+                for v in list.iter_mut() {
+                    v.sort_lists();
+                }
+                list.sort_by(Value::total_cmp);
+            },
+            Value::Dictionary(dict) => {
+                for (_, v) in dict.properties_mut() {
+                    v.sort_lists();
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn retain_keys_with<F: Fn(&str) -> bool>(&mut self, keep: &F) {
+        match self {
+            Value::List(list) => {
+                for v in list.iter_mut() {
+                    v.retain_keys_with(keep);
+                }
+            },
+            Value::Dictionary(dict) => {
+                dict.retain(|key, _| keep(key));
+                for (_, v) in dict.properties_mut() {
+                    v.retain_keys_with(keep);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn map_strings_with<F: FnMut(&mut String)>(&mut self, f: &mut F, keys: bool) {
+        match self {
+            Value::String(s) => f(s),
+            Value::List(list) => {
+                for v in list.iter_mut() {
+                    v.map_strings_with(f, keys);
+                }
+            },
+            Value::Dictionary(dict) => {
+                if keys {
+                    let entries = std::mem::take(dict).into_properties();
+                    for (mut key, mut value) in entries {
+                        f(&mut key);
+                        value.map_strings_with(f, keys);
+                        dict.add_property(&key, value);
+                    }
+                } else {
+                    for (_, v) in dict.properties_mut() {
+                        v.map_strings_with(f, keys);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
 impl<S> From<i64> for Value<S> {
     fn from(i: i64) -> Self {
         Value::Integer(i)
@@ -223,6 +1004,12 @@ macro_rules! impl_extract {
             }
         }
 
+        impl_extract_ref!($ty_for, $into);
+    }
+}
+
+macro_rules! impl_extract_ref {
+    ($ty_for:ty, $into:ident) => {
         impl<T> ExtractRef<T> for $ty_for {
             fn extract_ref(from: &Value<T>) -> Option<&Self> {
                 match from {
@@ -242,14 +1029,78 @@ macro_rules! impl_extract {
     }
 }
 
-impl_extract!(i64, Integer);
+impl<T> Extract<T> for i64 {
+    fn extract(from: Value<T>) -> Option<Self> {
+        match from {
+            Value::Integer(x) | Value::IntegerWide(x, _) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<T> ExtractRef<T> for i64 {
+    fn extract_ref(from: &Value<T>) -> Option<&Self> {
+        match from {
+            Value::Integer(x) | Value::IntegerWide(x, _) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<T> ExtractMut<T> for i64 {
+    fn extract_mut(from: &mut Value<T>) -> Option<&mut Self> {
+        match from {
+            Value::Integer(x) | Value::IntegerWide(x, _) => Some(x),
+            _ => None,
+        }
+    }
+}
+
 impl_extract!(f64, Float);
 impl_extract!(bool, Boolean);
 impl_extract!(Bytes, Bytes);
 impl_extract!(String, String);
-impl_extract!(Vec<Value<T>>, List);
+impl_extract_ref!(Vec<Value<T>>, List);
 impl_extract!(Dictionary<T>, Dictionary);
 
+/// Every `Value` extracts to itself; together with the implementation for `Vec<E>` this keeps
+/// `Vec<Value<T>>` extracting any list.
+impl<T> Extract<T> for Value<T> {
+    fn extract(from: Value<T>) -> Option<Self> {
+        Some(from)
+    }
+}
+
+/// Extracts a `Value::List` into a vector, like [`extract_list`](crate::value::extract_list), hence
+/// `None` if any of the items cannot be extracted to `E`:
+/// ```
+/// # use packs::{Value, NoStruct, Extract};
+/// let value : Value<NoStruct> = vec!(42, -1, 3332).into_iter().collect();
+/// assert_eq!(Some(vec!(42, -1, 3332)), <Vec<i64>>::extract(value));
+///
+/// let value : Value<NoStruct> = vec!(Value::Integer(42), Value::Boolean(false)).into_iter().collect();
+/// assert_eq!(None, <Vec<i64>>::extract(value));
+/// ```
+impl<T, E: Extract<T>> Extract<T> for Vec<E> {
+    fn extract(from: Value<T>) -> Option<Self> {
+        extract_list(from)
+    }
+}
+
+/// Extracts a `Value::Dictionary` into a `HashMap`, returning `None` if any of the values cannot be
+/// extracted to `E`.
+impl<T, E: Extract<T>> Extract<T> for HashMap<String, E> {
+    fn extract(from: Value<T>) -> Option<Self> {
+        match from {
+            Value::Dictionary(dict) =>
+                dict.into_properties()
+                    .map(|(k, v)| E::extract(v).map(|e| (k, e)))
+                    .collect(),
+            _ => None,
+        }
+    }
+}
+
 impl<T, E: Extract<T>> Extract<T> for Option<E> {
     fn extract(from: Value<T>) -> Option<Self> {
         match from {
@@ -259,6 +1110,32 @@ impl<T, E: Extract<T>> Extract<T> for Option<E> {
     }
 }
 
+/// `PartialEq` between `Value` and the base type `$ty`, in both directions. Integers also equal a
+/// [`IntegerWide`](crate::value::Value::IntegerWide) of the same value.
+macro_rules! impl_partial_eq {
+    ($ty:ty, |$value:ident, $other:ident| $eq:expr) => {
+        impl<S> PartialEq<$ty> for Value<S> {
+            fn eq(&self, $other: &$ty) -> bool {
+                let $value = self;
+                $eq
+            }
+        }
+
+        impl<S> PartialEq<Value<S>> for $ty {
+            fn eq(&self, other: &Value<S>) -> bool {
+                other == self
+            }
+        }
+    }
+}
+
+impl_partial_eq!(i64, |value, other| value.as_i64() == Some(*other));
+impl_partial_eq!(f64, |value, other| value.as_f64() == Some(*other));
+impl_partial_eq!(bool, |value, other| value.as_bool() == Some(*other));
+impl_partial_eq!(str, |value, other| value.as_str() == Some(other));
+impl_partial_eq!(&str, |value, other| value.as_str() == Some(*other));
+impl_partial_eq!(String, |value, other| value.as_str() == Some(other.as_str()));
+
 /// Extracts a `Value::List` with the same runtime type values into a vector of extracted values.
 /// ```
 /// # use packs::{NoStruct, Value, extract_list_ref};
@@ -325,4 +1202,355 @@ pub fn extract_list_mut<S, T: ExtractMut<S>>(value: &mut Value<S>) -> Option<Vec
         },
         _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// Compares two sequences of values item by item, a shorter one being less if it is a prefix.
+fn cmp_lexicographic<'a, S: 'a, I>(mut a: I, mut b: I) -> Ordering
+    where I: Iterator<Item=&'a Value<S>> {
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match x.total_cmp(y) {
+                Ordering::Equal => (),
+                o => return o,
+            },
+        }
+    }
+}
+
+/// The segments of a path for [`pointer`](crate::value::Value::pointer).
+fn path_segments(path: &str) -> impl Iterator<Item=&str> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    path.split('/').filter(move |_| !path.is_empty())
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{Value, ValueKind, NoStruct, GenericStruct, Dictionary, ExtractRef, NotADictionary, CoerceError, KeyPathError, DecodeError, EncodeError, Pack, Unpack, Bytes};
+    use crate::ll::types::fixed::IntWidth;
+
+    fn dict(value: &Value<NoStruct>) -> &Dictionary<NoStruct> {
+        Dictionary::extract_ref(value).expect("Expected a dictionary.")
+    }
+
+    fn nested() -> Value<NoStruct> {
+        let mut inner = Dictionary::new();
+        inner.add_property("name", "alice");
+        let mut outer = Dictionary::new();
+        outer.add_property("user", Value::Dictionary(inner));
+        outer.add_property("ids", vec!(Value::from("a1"), Value::Integer(1)));
+        Value::Dictionary(outer)
+    }
+
+    #[test]
+    fn with_capacity() {
+        match Value::<NoStruct>::list_with_capacity(16) {
+            Value::List(l) => assert!(l.is_empty() && l.capacity() >= 16),
+            v => panic!("Expected a list, got {:?}", v),
+        }
+
+        match Value::<NoStruct>::dict_with_capacity(16) {
+            Value::Dictionary(d) => assert!(d.is_empty() && d.capacity() >= 16),
+            v => panic!("Expected a dictionary, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn coerce() {
+        fn coerce(value: Value<NoStruct>, target: ValueKind) -> Result<Value<NoStruct>, CoerceError> {
+            value.coerce(target)
+        }
+
+        assert_eq!(coerce(Value::Integer(3), ValueKind::Float), Ok(Value::Float(3.0)));
+        assert_eq!(coerce(Value::Float(-3.7), ValueKind::Integer), Ok(Value::Integer(-3)));
+        assert_eq!(coerce(Value::Integer(1), ValueKind::Boolean), Ok(Value::Boolean(true)));
+        assert_eq!(coerce(Value::Boolean(false), ValueKind::Integer), Ok(Value::Integer(0)));
+        assert_eq!(coerce(Value::Float(0.5), ValueKind::String), Ok(Value::from("0.5")));
+        assert_eq!(coerce(Value::from("-1.5e3"), ValueKind::Float), Ok(Value::Float(-1500.0)));
+        assert_eq!(coerce(Value::from("true"), ValueKind::Boolean), Ok(Value::Boolean(true)));
+        assert_eq!(coerce(Value::Null, ValueKind::Null), Ok(Value::Null));
+
+        let error = |from, to| Err(CoerceError { from, to });
+        assert_eq!(coerce(Value::Float(f64::NAN), ValueKind::Integer), error(ValueKind::Float, ValueKind::Integer));
+        assert_eq!(coerce(Value::Float(1e19), ValueKind::Integer), error(ValueKind::Float, ValueKind::Integer));
+        assert_eq!(coerce(Value::from("4x"), ValueKind::Integer), error(ValueKind::String, ValueKind::Integer));
+        assert_eq!(coerce(Value::Null, ValueKind::String), error(ValueKind::Null, ValueKind::String));
+        assert_eq!(coerce(Value::List(vec!()), ValueKind::String), error(ValueKind::List, ValueKind::String));
+    }
+
+    #[test]
+    fn insert_entry_into_null_and_dictionary() {
+        let mut value : Value<NoStruct> = Value::Null;
+        assert_eq!(value.insert_entry("a", 1), Ok(None));
+        assert_eq!(value.insert_entry("a", 2), Ok(Some(Value::Integer(1))));
+        assert_eq!(dict(&value).get_property("a"), Some(&Value::Integer(2)));
+
+        let mut list : Value<NoStruct> = Value::List(Vec::new());
+        assert_eq!(list.insert_entry("a", 1), Err(NotADictionary));
+        assert_eq!(list, Value::List(Vec::new()));
+    }
+
+    #[test]
+    fn map_strings_in_nested_dictionaries() {
+        let mut value = nested();
+        value.map_strings(|s| s.make_ascii_uppercase());
+
+        let user = dict(&value).get_property("user").unwrap();
+        assert_eq!(dict(user).get_property("name"), Some(&Value::from("ALICE")));
+        assert!(dict(&value).has_property("ids"));
+
+        let mut value = nested();
+        value.map_strings_keys(|s| s.make_ascii_uppercase());
+
+        assert_eq!(
+            dict(&value).get_property("IDS"),
+            Some(&Value::List(vec!(Value::from("A1"), Value::Integer(1)))));
+        let user = dict(&value).get_property("USER").unwrap();
+        assert_eq!(dict(user).get_property("NAME"), Some(&Value::from("ALICE")));
+    }
+
+    #[test]
+    fn retain_keys_in_nested_dictionaries() {
+        let mut value = nested();
+        value.insert_entry("_meta", Value::List(vec!(nested(), Value::Integer(1)))).unwrap();
+        value.retain_keys(|key| !key.starts_with('_') && key != "name");
+
+        let mut expected = nested();
+        if let Value::Dictionary(outer) = &mut expected {
+            outer.add_property("user", Value::Dictionary(Dictionary::new()));
+        }
+        assert_eq!(value, expected);
+
+        let mut value = Value::List(vec!(nested(), nested()));
+        value.retain_keys(|key| key == "user");
+        for v in Vec::<Value<NoStruct>>::extract_ref(&value).unwrap() {
+            assert_eq!(dict(v).len(), 1);
+            let user = dict(v).get_property("user").unwrap();
+            assert!(dict(user).is_empty());
+        }
+    }
+
+    #[test]
+    fn decode_deeply_nested_list_fails() {
+        let mut bytes = vec![0x91; 500];
+        bytes.push(0x90);
+
+        match <Value<NoStruct>>::decode(&mut bytes.as_slice()) {
+            Err(DecodeError::DepthLimitExceeded) => (),
+            r => panic!("Expected DepthLimitExceeded, got {:?}", r),
+        }
+
+        assert!(<Value<NoStruct>>::decode_with_limit(&mut bytes.as_slice(), 501).is_ok());
+        assert!(<Value<NoStruct>>::decode_with_limit(&mut bytes.as_slice(), 500).is_err());
+
+        let mut bytes = [0xB1, 0x01].repeat(500);
+        bytes.push(0xC0);
+        match <Value<GenericStruct>>::decode(&mut bytes.as_slice()) {
+            Err(DecodeError::DepthLimitExceeded) => (),
+            r => panic!("Expected DepthLimitExceeded, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn flatten_deeply_wrapped_singletons() {
+        let mut wrapped = Value::Integer(42);
+        for _ in 0..50 {
+            wrapped = Value::List(vec!(wrapped));
+        }
+
+        let mut props = Dictionary::new();
+        props.add_property("answer", wrapped);
+        props.add_property("empty", Value::List(Vec::new()));
+        let mut value: Value<NoStruct> = Value::List(vec!(Value::Dictionary(props)));
+
+        value.flatten_singletons();
+
+        let props = dict(&value);
+        assert_eq!(props.get_property("answer"), Some(&Value::Integer(42)));
+        assert_eq!(props.get_property("empty"), Some(&Value::List(Vec::new())));
+    }
+
+    #[test]
+    fn extract_collections() {
+        use crate::Extract;
+        use std::collections::HashMap;
+
+        let list : Value<GenericStruct> = vec!(1, 2, 3).into_iter().collect();
+        assert_eq!(Some(vec!(1, 2, 3)), <Vec<i64>>::extract(list.clone()));
+        assert_eq!(None, <Vec<String>>::extract(list.clone()));
+        assert_eq!(Some(3), <Vec<Value<GenericStruct>>>::extract(list).map(|l| l.len()));
+
+        let partial : Value<GenericStruct> =
+            vec!(Value::Integer(1), Value::String(String::from("two")), Value::Integer(3)).into_iter().collect();
+        assert_eq!(None, <Vec<i64>>::extract(partial));
+
+        let nested : Value<GenericStruct> = Value::List(vec!(vec!(1, 2).into_iter().collect(), Value::List(vec!())));
+        assert_eq!(Some(vec!(vec!(1, 2), vec!())), <Vec<Vec<i64>>>::extract(nested));
+
+        let mut dict : Dictionary<GenericStruct> = Dictionary::new();
+        dict.add_property("a", 1);
+        dict.add_property("b", 2);
+        let mut expected = HashMap::new();
+        expected.insert(String::from("a"), 1);
+        expected.insert(String::from("b"), 2);
+        assert_eq!(Some(expected), <HashMap<String, i64>>::extract(Value::Dictionary(dict.clone())));
+
+        dict.add_property("c", "three");
+        assert_eq!(None, <HashMap<String, i64>>::extract(Value::Dictionary(dict)));
+        assert_eq!(None, <HashMap<String, i64>>::extract(Value::<GenericStruct>::Integer(1)));
+    }
+
+    #[test]
+    fn nested_access() {
+        let mut value = nested();
+        assert_eq!(value.get("user").and_then(|u| u.get("name")), Some(&Value::from("alice")));
+        assert_eq!(value.get("ids").and_then(|ids| ids.get_index(1)), Some(&Value::Integer(1)));
+        assert_eq!(value.pointer(""), Some(&nested()));
+        assert_eq!(value.pointer("/user/name"), Some(&Value::from("alice")));
+        assert_eq!(value.pointer("ids/0"), Some(&Value::from("a1")));
+
+        // missing keys, wrong variants and out-of-range indices:
+        assert_eq!(value.get("missing"), None);
+        assert_eq!(value.get_index(0), None);
+        assert_eq!(value.pointer("/ids/2"), None);
+        assert_eq!(value.pointer("/ids/x"), None);
+        assert_eq!(value.pointer("/user/name/first"), None);
+
+        *value.pointer_mut("/ids/1").unwrap() = Value::Integer(2);
+        *value.get_mut("user").and_then(|u| u.get_mut("name")).unwrap() = Value::from("bob");
+        value.get_mut("ids").and_then(|ids| ids.get_index_mut(0)).unwrap().map_strings(|s| s.make_ascii_uppercase());
+        assert_eq!(value.pointer("/ids"), Some(&Value::List(vec!(Value::from("A1"), Value::Integer(2)))));
+        assert_eq!(value.pointer("/user/name"), Some(&Value::from("bob")));
+        assert!(value.get_index_mut(0).is_none());
+    }
+
+    #[test]
+    fn encode_bounded_stops_at_limit() {
+        let value: Value<NoStruct> = (0..100).map(Value::Integer).collect();
+        let len = value.encoded_len();
+
+        let mut buffer = Vec::new();
+        assert_eq!(value.encode_bounded(&mut buffer, len).unwrap(), len);
+
+        for limit in &[0, 1, len / 2, len - 1] {
+            let mut buffer = Vec::new();
+            match value.encode_bounded(&mut buffer, *limit) {
+                Err(EncodeError::OutputTooLarge { limit: l, needed: None }) => assert_eq!(l, *limit),
+                r => panic!("Expected OutputTooLarge, got {:?}", r),
+            }
+            assert!(buffer.len() <= *limit);
+        }
+    }
+
+    #[test]
+    fn type_names() {
+        let values: Vec<(Value<GenericStruct>, &str)> = vec!(
+            (Value::Null, "Null"),
+            (Value::Boolean(false), "Boolean"),
+            (Value::Integer(1), "Integer"),
+            (Value::IntegerWide(1, IntWidth::Int32), "Integer"),
+            (Value::Float(1.0), "Float"),
+            (Value::Bytes(Bytes(Vec::new())), "Bytes"),
+            (Value::from("x"), "String"),
+            (Value::List(Vec::new()), "List"),
+            (Value::Dictionary(Dictionary::new()), "Dictionary"),
+            (Value::Structure(GenericStruct { tag_byte: 0x01, fields: Vec::new() }), "Structure"),
+        );
+
+        for (value, name) in values {
+            assert_eq!(value.type_name(), name);
+        }
+    }
+
+    #[test]
+    fn remap_nested_structure_tags() {
+        let leaf = |tag| Value::Structure(GenericStruct { tag_byte: tag, fields: vec!(Value::Integer(1)) });
+        let tree = |a, b, c| {
+            let mut props = Dictionary::new();
+            props.add_property("leaf", leaf(c));
+            Value::List(vec!(
+                Value::Structure(GenericStruct {
+                    tag_byte: a,
+                    fields: vec!(leaf(b), Value::Dictionary(props)),
+                }),
+                Value::from("untouched"),
+            ))
+        };
+
+        let mut value = tree(0x4E, 0x46, 0x66);
+        value.remap_structure_tags(|tag| match tag {
+            0x46 => 0x49,
+            0x66 => 0x69,
+            tag => tag,
+        });
+        assert_eq!(value, tree(0x4E, 0x49, 0x69));
+
+        let s = GenericStruct { tag_byte: 0x01, fields: vec!(Value::Null) }.with_tag(0x02);
+        assert_eq!(s, GenericStruct { tag_byte: 0x02, fields: vec!(Value::Null) });
+    }
+
+    #[test]
+    fn set_and_get_path() {
+        let mut value : Value<NoStruct> = Value::Dictionary(Dictionary::new());
+        value.set_path("a.b", 1.into()).unwrap();
+
+        let mut inner = Dictionary::new();
+        inner.add_property("b", 1);
+        let mut expected = Dictionary::new();
+        expected.add_property("a", Value::Dictionary(inner));
+        assert_eq!(value, Value::Dictionary(expected));
+        assert_eq!(value.get_path("a.b"), Some(&Value::Integer(1)));
+        assert_eq!(value.get_path("a.c"), None);
+        assert_eq!(value.get_path("a.b.c"), None);
+
+        // existing dictionaries are kept, values replaced:
+        value.set_path("a.c.d", "x".into()).unwrap();
+        value.set_path("a.b", 2.into()).unwrap();
+        assert_eq!(value.get_path("a.b"), Some(&Value::Integer(2)));
+        assert_eq!(value.get_path("a.c.d"), Some(&Value::from("x")));
+        assert_eq!(value.get("a").and_then(Value::as_dict).map(Dictionary::len), Some(2));
+
+        assert_eq!(
+            value.set_path("a.b.c", Value::Null),
+            Err(KeyPathError::NotADictionary(String::from("a.b"), ValueKind::Integer)));
+        assert_eq!(value.set_path("a..b", Value::Null), Err(KeyPathError::EmptySegment(String::from("a..b"))));
+        assert_eq!(value.set_path("", Value::Null), Err(KeyPathError::EmptySegment(String::new())));
+        assert_eq!(
+            Value::<NoStruct>::List(Vec::new()).set_path("a", Value::Null),
+            Err(KeyPathError::NotADictionary(String::new(), ValueKind::List)));
+    }
+
+    #[test]
+    fn sort_nested_lists() {
+        let mut dict = Dictionary::new();
+        dict.add_property("xs", vec!(Value::Float(2.5), Value::Float(f64::NAN), Value::Float(-0.0)));
+        let mut value : Value<NoStruct> = Value::List(vec!(
+            Value::List(vec!(Value::Integer(3), Value::IntegerWide(1, IntWidth::Int32))),
+            Value::Dictionary(dict),
+            Value::List(vec!(Value::Integer(2), Value::Integer(1))),
+            Value::Boolean(true),
+            Value::Null,
+        ));
+
+        value.sort_lists();
+
+        let list = value.as_list().unwrap();
+        assert_eq!(&list[..2], &[Value::Null, Value::Boolean(true)]);
+        assert_eq!(list[2], Value::List(vec!(Value::Integer(1), Value::Integer(2))));
+        assert_eq!(list[3], Value::List(vec!(Value::IntegerWide(1, IntWidth::Int32), Value::Integer(3))));
+        // NaN is not equal to itself, so compare the dictionary by the order of its floats:
+        let xs = list[4].get("xs").and_then(Value::as_list).unwrap();
+        let floats : Vec<u64> = xs.iter().map(|x| match x {
+            Value::Float(f) => f.to_bits(),
+            _ => panic!("expected a float"),
+        }).collect();
+        assert_eq!(floats, vec!((-0.0f64).to_bits(), 2.5f64.to_bits(), f64::NAN.to_bits()));
+
+        // sorting again changes nothing:
+        let before = value.to_vec().unwrap();
+        value.sort_lists();
+        assert_eq!(value.to_vec().unwrap(), before);
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::{Read, Write};
+use crate::{Pack, Unpack, EncodeError, DecodeError, Marker};
+use crate::ll::types::lengths::{Length, read_bytes_size};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// An `i128` which is encoded as 16 bytes, holding the integer in big-endian two's complement.
+/// PackStream integers are at most 64 bit wide, hence there is no native way to encode an `i128`.
+///
+/// **Note**: This is a convention of this crate and **not** part of PackStream. Any other
+/// implementation reads a `BigInt` as plain bytes.
+/// ```
+/// use packs::{Pack, Unpack, BigInt};
+///
+/// let big = BigInt(i128::from(i64::MAX) + 1);
+/// let mut buffer = Vec::new();
+/// big.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(buffer.len(), 2 + 16); // `Bytes8` marker, size and the bytes
+/// assert_eq!(big, BigInt::decode(&mut buffer.as_slice()).unwrap());
+/// ```
+pub struct BigInt(pub i128);
+
+impl Pack for BigInt {
+    fn encode<T: Write>(&self, writer: &mut T) -> Result<usize, EncodeError> {
+        let bytes = self.0.to_be_bytes();
+        Ok(Marker::Bytes8.encode(writer)?
+            + Length::Bit8(bytes.len() as u8).encode(writer)?
+            + writer.write(&bytes)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + 16
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + 16
+    }
+}
+
+impl Unpack for BigInt {
+    fn decode_body<T: Read>(marker: Marker, reader: &mut T) -> Result<Self, DecodeError> {
+        let len = read_bytes_size(marker, reader)?;
+        if len != 16 {
+            return Err(DecodeError::UnexpectedNumberOfBytes(16, len))
+        }
+
+        let mut buf = [0; 16];
+        reader.read_exact(&mut buf)?;
+        Ok(BigInt(i128::from_be_bytes(buf)))
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(i: i128) -> Self {
+        BigInt(i)
+    }
+}
+
+impl From<BigInt> for i128 {
+    fn from(big: BigInt) -> Self {
+        big.0
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::packable::test::{pack_unpack_test, pack_to_test};
+    use crate::value::big_int::BigInt;
+    use crate::{Unpack, DecodeError};
+
+    #[test]
+    fn pack_unpack_big_int() {
+        pack_unpack_test(&[BigInt(i128::MIN), BigInt(i128::MAX), BigInt(0), BigInt(-1)]);
+    }
+
+    #[test]
+    fn pack_to_big_int() {
+        pack_to_test(
+            BigInt(-2),
+            &[0xCC, 0x10,
+                0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]);
+    }
+
+    #[test]
+    fn unpack_wrong_length() {
+        match BigInt::decode(&mut [0xCCu8, 0x02, 0x01, 0x02].as_ref()) {
+            Err(DecodeError::UnexpectedNumberOfBytes(16, 2)) => (),
+            r => panic!("Expected UnexpectedNumberOfBytes, got {:?}", r),
+        }
+    }
+}
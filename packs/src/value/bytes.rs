@@ -1,2 +1,29 @@
+use crate::error::DecodeError;
+
 #[derive(Debug, Clone, PartialEq)]
-pub struct Bytes(pub Vec<u8>);
\ No newline at end of file
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+    /// Parses bytes given as hex digits, two per byte, either contiguous or separated by
+    /// whitespace. Anything else is reported as `InvalidHex`:
+    /// ```
+    /// use packs::Bytes;
+    ///
+    /// assert_eq!(Bytes::from_hex("C9 00 80").unwrap(), Bytes(vec!(0xC9, 0x00, 0x80)));
+    /// assert_eq!(Bytes::from_hex("c90080").unwrap(), Bytes(vec!(0xC9, 0x00, 0x80)));
+    /// assert!(Bytes::from_hex("C9 0").is_err());
+    /// ```
+    pub fn from_hex(hex: &str) -> Result<Bytes, DecodeError> {
+        let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|pair|
+                std::str::from_utf8(pair)
+                    .ok()
+                    .filter(|pair| pair.len() == 2 && pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| DecodeError::InvalidHex(String::from(hex))))
+            .collect::<Result<Vec<u8>, DecodeError>>()
+            .map(Bytes)
+    }
+}
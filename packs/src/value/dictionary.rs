@@ -1,35 +1,80 @@
 use std::collections::HashMap;
-use crate::{Value, Extract};
-use std::collections::hash_map::{Iter, IterMut, Entry};
+use std::io::{Read, Write};
+use crate::{Value, Extract, Pack, Unpack, Marker, EncodeError, DecodeError, EncodeOptions, DecodeOptions};
 use std::iter::FromIterator;
+use std::marker::PhantomData;
 use crate::value::ExtractRef;
+use crate::ll::types::lengths::Length;
+#[cfg(not(feature = "ordered"))]
+use std::collections::hash_map::{Iter, IterMut, Entry};
+#[cfg(feature = "ordered")]
+use indexmap::map::{Iter, IterMut, Entry};
+
+/// The map backing a [`Dictionary`](crate::value::dictionary::Dictionary): a `HashMap`, or with
+/// the feature `ordered` an `IndexMap`, which keeps the properties in the order they have been
+/// added, also when encoding them. The iterators of the dictionary are the ones of this map.
+#[cfg(not(feature = "ordered"))]
+pub type PropertyMap<T> = HashMap<String, Value<T>>;
+#[cfg(feature = "ordered")]
+pub type PropertyMap<T> = indexmap::IndexMap<String, Value<T>>;
 
 #[derive(Debug, Clone, PartialEq)]
 /// A `Dictionary` is a map of `String` to [`Value<T>`](crate::value::Value) pairs. These pairs are
 /// also called `properties` and can be seen as named values. The type parameter denotes the allowed
 /// structures for a value.
-pub struct Dictionary<T>(HashMap<String, Value<T>>);
+///
+/// The order of the properties is arbitrary, unless the feature `ordered` is enabled, which keeps
+/// them in the order they have been added.
+pub struct Dictionary<T>(PropertyMap<T>);
 
 impl<T> Dictionary<T> {
     pub fn with_capacity(capacity: usize) -> Self {
-        Dictionary(HashMap::with_capacity(capacity))
+        Dictionary(PropertyMap::with_capacity(capacity))
     }
 
     pub fn new() -> Self {
-        Dictionary(HashMap::new())
+        Dictionary(PropertyMap::new())
     }
 
+    #[cfg(not(feature = "ordered"))]
     pub fn from_inner(map: HashMap<String, Value<T>>) -> Self {
         Dictionary(map)
     }
 
+    #[cfg(feature = "ordered")]
+    pub fn from_inner(map: HashMap<String, Value<T>>) -> Self {
+        map.into_iter().collect()
+    }
+
+    #[cfg(not(feature = "ordered"))]
     pub fn into_inner(self) -> HashMap<String, Value<T>> {
         self.0
     }
 
-    pub fn inner(&self) -> &HashMap<String, Value<T>> {
+    /// The properties as a `HashMap`, losing their order; see
+    /// [`into_properties`](crate::value::dictionary::Dictionary::into_properties) to keep it.
+    #[cfg(feature = "ordered")]
+    pub fn into_inner(self) -> HashMap<String, Value<T>> {
+        self.0.into_iter().collect()
+    }
+
+    pub fn inner(&self) -> &PropertyMap<T> {
         &self.0
     }
+
+    pub(crate) fn from_map(map: PropertyMap<T>) -> Self {
+        Dictionary(map)
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn into_map(self) -> PropertyMap<T> {
+        self.0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
     /// Adds a key-value pair to the `Dictionary`. Returns the original value of the property
     /// if it was already set.
     pub fn add_property<V: Into<Value<T>>>(&mut self, key: &str, value: V) -> Option<Value<T>> {
@@ -41,15 +86,30 @@ impl<T> Dictionary<T> {
     }
 
     pub fn extract_property(&mut self, key: &str) -> Option<Value<T>> {
+        self.remove(key)
+    }
+
+    /// Removes the property from the dictionary and returns it, same as
+    /// [`extract_property`](crate::value::dictionary::Dictionary::extract_property).
+    #[cfg(not(feature = "ordered"))]
+    pub fn remove(&mut self, key: &str) -> Option<Value<T>> {
         self.0.remove(key)
     }
 
+    /// Removes the property from the dictionary and returns it, same as
+    /// [`extract_property`](crate::value::dictionary::Dictionary::extract_property). The other
+    /// properties keep their order.
+    #[cfg(feature = "ordered")]
+    pub fn remove(&mut self, key: &str) -> Option<Value<T>> {
+        self.0.shift_remove(key)
+    }
+
     /// Removes the property from the dictionary and returns it. Tries to extract the value
     /// strongly typed.
     ///
     /// **Panics** if it cannot cast the value to provided type.
     pub fn extract_property_typed<V: Extract<T>>(&mut self, key: &str) -> Option<V> {
-        self.0.remove(key).map(|v| V::extract(v).unwrap())
+        self.remove(key).map(|v| V::extract(v).unwrap())
     }
 
     /// Retrieves a property.
@@ -75,27 +135,549 @@ impl<T> Dictionary<T> {
         self.0.get(key).map(|v| V::extract_ref(v).unwrap())
     }
 
-    pub fn properties(&self) -> Iter<String, Value<T>> {
+    /// Retrieves a property for changing it in place:
+    /// ```
+    /// # use packs::*;
+    /// let mut dict : Dictionary<()> = Dictionary::new();
+    /// dict.add_property("tags", vec!(Value::from("a")));
+    ///
+    /// if let Some(Value::List(tags)) = dict.get_mut("tags") {
+    ///     tags.push(Value::from("b"));
+    /// }
+    ///
+    /// assert_eq!(dict.get_property("tags"), Some(&Value::List(vec!(Value::from("a"), Value::from("b")))));
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value<T>> {
+        self.0.get_mut(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=&String> {
+        self.0.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&Value<T>> {
+        self.0.values()
+    }
+
+    pub fn properties(&self) -> Iter<'_, String, Value<T>> {
         self.0.iter()
     }
 
-    pub fn properties_mut(&mut self) -> IterMut<String, Value<T>> {
+    pub fn properties_mut(&mut self) -> IterMut<'_, String, Value<T>> {
         self.0.iter_mut()
     }
 
-    pub fn entry(&mut self, key: String) -> Entry<String, Value<T>> {
+    pub fn entry(&mut self, key: String) -> Entry<'_, String, Value<T>> {
         self.0.entry(key)
     }
-    
+
+    /// Consumes the dictionary into its properties, in the order of
+    /// [`properties`](crate::value::dictionary::Dictionary::properties).
+    pub fn into_properties(self) -> impl ExactSizeIterator<Item=(String, Value<T>)> {
+        self.0.into_iter()
+    }
+
+    /// Retrieves a property for changing it in place, adding it with the value of `default` first
+    /// if it is not set:
+    /// ```
+    /// # use packs::*;
+    /// let mut dict : Dictionary<()> = Dictionary::new();
+    /// for word in &["a", "b", "a"] {
+    ///     if let Value::Integer(count) = dict.get_or_insert_with(word, || Value::Integer(0)) {
+    ///         *count += 1;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(dict.get_property("a"), Some(&Value::Integer(2)));
+    /// ```
+    pub fn get_or_insert_with<F: FnOnce() -> Value<T>>(&mut self, key: &str, default: F) -> &mut Value<T> {
+        if !self.0.contains_key(key) {
+            self.0.insert(String::from(key), default());
+        }
+        self.0.get_mut(key).expect("The property has just been added.")
+    }
+
+    /// Keeps only the properties for which `keep` returns `true`. Under the feature `ordered` the
+    /// kept properties keep their order.
+    pub fn retain<F: FnMut(&String, &mut Value<T>) -> bool>(&mut self, keep: F) {
+        self.0.retain(keep)
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks that every key can be encoded, i.e. that it is not empty and its length fits into the
+    /// 32 bit size information of a string. Reports the first invalid key as `InvalidKey`. The same
+    /// check is done before encoding, such that nothing of the dictionary is written then.
+    /// ```
+    /// # use packs::*;
+    /// let mut dict : Dictionary<()> = Dictionary::new();
+    /// dict.add_property("name", "Alice");
+    /// assert!(dict.validate_keys().is_ok());
+    ///
+    /// dict.add_property("", "empty");
+    /// assert!(matches!(dict.validate_keys(), Err(EncodeError::InvalidKey(0))));
+    /// ```
+    pub fn validate_keys(&self) -> Result<(), EncodeError> {
+        self.0.keys().try_for_each(|key| validate_key_len(key.len()))
+    }
+
+    /// A view on all properties whose value is a `V`; all other properties are skipped. Nothing is
+    /// copied, the values are extracted on access:
+    /// ```
+    /// # use packs::*;
+    /// let mut dict : Dictionary<()> = Dictionary::new();
+    /// dict.add_property("pages", 302);
+    /// dict.add_property("title", "A Book's Title");
+    /// dict.add_property("year", 1999);
+    ///
+    /// let integers = dict.typed_view::<i64>();
+    ///
+    /// assert_eq!(integers.get("pages"), Some(&302));
+    /// assert_eq!(integers.get("title"), None);
+    /// assert_eq!(integers.len(), 2);
+    /// ```
+    pub fn typed_view<V: ExtractRef<T>>(&self) -> TypedView<'_, T, V> {
+        TypedView {
+            dict: self,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T: Pack> Dictionary<T> {
+    /// Encodes the dictionary with its properties sorted by key, comparing the keys' UTF-8 bytes,
+    /// such that equal dictionaries always lead to the same bytes, e.g. to sign them. Dictionaries
+    /// nested in properties, directly or in lists, are sorted the same way; the fields of a
+    /// structure are encoded as the structure does. The `Pack` implementation keeps the order of
+    /// the underlying map, which avoids sorting:
+    /// ```
+    /// # use packs::*;
+    /// let mut dict : Dictionary<NoStruct> = Dictionary::new();
+    /// dict.add_property("b", 2);
+    /// dict.add_property("a", 1);
+    ///
+    /// let mut buffer = Vec::new();
+    /// dict.encode_sorted(&mut buffer).unwrap();
+    ///
+    /// assert_eq!(buffer, vec!(0xA2, 0x81, 0x61, 0x01, 0x81, 0x62, 0x02));
+    /// ```
+    /// The keys are checked first, the nested ones included, such that nothing is written for an
+    /// invalid one.
+    pub fn encode_sorted<W: Write>(&self, writer: &mut W) -> Result<usize, EncodeError> {
+        validate_keys_nested(self)?;
+        self.write_sorted(writer)
+    }
+
+    fn write_sorted<W: Write>(&self, writer: &mut W) -> Result<usize, EncodeError> {
+        let mut properties: Vec<_> = self.0.iter().collect();
+        properties.sort_by_key(|(key, _)| key.as_bytes());
+
+        let len = Length::from_usize(properties.len()).expect("Dictionary has invalid length");
+        let mut written = len.encode_as_dict_size(writer)?;
+        for (key, value) in properties {
+            written += key.encode(writer)? + write_value_sorted(value, writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+fn validate_keys_nested<T>(dict: &Dictionary<T>) -> Result<(), EncodeError> {
+    dict.validate_keys()?;
+    dict.values().try_for_each(validate_value_keys)
+}
+
+fn validate_value_keys<T>(value: &Value<T>) -> Result<(), EncodeError> {
+    match value {
+        Value::Dictionary(dict) => validate_keys_nested(dict),
+        Value::List(list) => list.iter().try_for_each(validate_value_keys),
+        _ => Ok(()),
+    }
+}
+
+fn write_value_sorted<T: Pack, W: Write>(value: &Value<T>, writer: &mut W) -> Result<usize, EncodeError> {
+    match value {
+        Value::Dictionary(dict) => dict.write_sorted(writer),
+        Value::List(list) => {
+            let len = Length::from_usize(list.len()).expect("List has invalid length");
+            let mut written = len.encode_as_list_size(writer)?;
+            for item in list {
+                written += write_value_sorted(item, writer)?;
+            }
+            Ok(written)
+        },
+        value => value.encode(writer),
+    }
+}
+
+impl<T: Clone + PartialEq> Dictionary<T> {
+    /// The changes which turn this dictionary into `other`:
+    /// ```
+    /// # use packs::*;
+    /// let mut old : Dictionary<()> = Dictionary::new();
+    /// old.add_property("name", "Alice");
+    /// old.add_property("age", 42);
+    ///
+    /// let mut new = old.clone();
+    /// new.add_property("age", 43);
+    /// new.add_property("city", "Berlin");
+    ///
+    /// let patch = old.diff(&new);
+    /// assert_eq!(patch.changed.get_property("age"), Some(&Value::Integer(43)));
+    /// assert_eq!(patch.added.len(), 1);
+    /// assert!(patch.removed.is_empty());
+    ///
+    /// old.apply_patch(patch);
+    /// assert_eq!(old, new);
+    /// ```
+    pub fn diff(&self, other: &Dictionary<T>) -> DictPatch<T> {
+        let mut patch = DictPatch::default();
+        for (key, value) in other.properties() {
+            match self.get_property(key) {
+                None => { patch.added.add_property(key, value.clone()); },
+                Some(old) if old != value => { patch.changed.add_property(key, value.clone()); },
+                _ => (),
+            }
+        }
+
+        patch.removed =
+            self.0.keys()
+                .filter(|key| !other.has_property(key))
+                .cloned()
+                .collect();
+
+        patch
+    }
+}
+
+impl<T> Dictionary<T> {
+    /// Applies the changes of a patch as created by
+    /// [`diff`](crate::value::dictionary::Dictionary::diff).
+    pub fn apply_patch(&mut self, patch: DictPatch<T>) {
+        for key in patch.removed {
+            self.remove(&key);
+        }
+
+        self.0.extend(patch.added.0);
+        self.0.extend(patch.changed.0);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The difference between two dictionaries, see
+/// [`diff`](crate::value::dictionary::Dictionary::diff).
+///
+/// It is encoded as a structure with the tag byte [`TAG`](crate::value::dictionary::DictPatch::TAG)
+/// and the three fields `added`, `removed` and `changed`. This is a convention of this crate and
+/// not part of PackStream.
+pub struct DictPatch<T> {
+    /// The properties which are new.
+    pub added: Dictionary<T>,
+    /// The keys of the properties which are gone.
+    pub removed: Vec<String>,
+    /// The properties which have a new value.
+    pub changed: Dictionary<T>,
+}
+
+impl<T> DictPatch<T> {
+    pub const TAG: u8 = 0x70;
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<T> Default for DictPatch<T> {
+    fn default() -> Self {
+        DictPatch {
+            added: Dictionary::new(),
+            removed: Vec::new(),
+            changed: Dictionary::new(),
+        }
+    }
+}
+
+impl<T: Pack> Pack for DictPatch<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<usize, EncodeError> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    fn encode_with<W: Write>(&self, writer: &mut W, options: &EncodeOptions) -> Result<usize, EncodeError> {
+        Ok(Marker::Structure(3, Self::TAG).encode(writer)?
+            + self.added.encode_with(writer, options)?
+            + self.removed.encode(writer)?
+            + self.changed.encode_with(writer, options)?)
+    }
+
+    fn size_hint(&self) -> usize {
+        2 + self.added.size_hint() + self.removed.size_hint() + self.changed.size_hint()
+    }
+
+    fn encoded_len(&self) -> usize {
+        2 + self.added.encoded_len() + self.removed.encoded_len() + self.changed.encoded_len()
+    }
+}
+
+impl<T: Unpack> Unpack for DictPatch<T> {
+    fn decode_body<R: Read>(marker: Marker, reader: &mut R) -> Result<Self, DecodeError> {
+        Self::decode_body_with(marker, reader, &DecodeOptions::default())
+    }
+
+    fn decode_body_with<R: Read>(marker: Marker, reader: &mut R, options: &DecodeOptions) -> Result<Self, DecodeError> {
+        match marker {
+            Marker::Structure(3, Self::TAG) => {
+                let options = &options.descend()?;
+                Ok(DictPatch {
+                    added: Dictionary::decode_with(reader, options)?,
+                    removed: <Vec<String>>::decode_with(reader, options)?,
+                    changed: Dictionary::decode_with(reader, options)?,
+                })
+            },
+            Marker::Structure(3, tag) => Err(DecodeError::UnexpectedTagByte(tag)),
+            Marker::Structure(u, _) => Err(DecodeError::UnexpectedNumberOfFields(3, u)),
+            _ => Err(DecodeError::UnexpectedMarker(marker)),
+        }
+    }
+}
+
+/// A view on the properties of a [`Dictionary`](crate::value::dictionary::Dictionary) with values
+/// of type `V`, see [`typed_view`](crate::value::dictionary::Dictionary::typed_view).
+pub struct TypedView<'a, T, V> {
+    dict: &'a Dictionary<T>,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<'a, T, V: ExtractRef<T> + 'a> TypedView<'a, T, V> {
+    /// Retrieves the value of a property, if it is a `V`.
+    pub fn get(&self, key: &str) -> Option<&'a V> {
+        self.dict.0.get(key).and_then(V::extract_ref)
+    }
+
+    /// Iterates over all properties whose value is a `V`.
+    pub fn iter(&self) -> impl Iterator<Item=(&'a String, &'a V)> {
+        self.dict.0.iter().filter_map(|(k, v)| V::extract_ref(v).map(|v| (k, v)))
+    }
+
+    /// The number of properties whose value is a `V`. This needs to check every property.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// Checks the length of a dictionary key, see
+/// [`validate_keys`](crate::value::dictionary::Dictionary::validate_keys).
+pub(crate) fn validate_key_len(len: usize) -> Result<(), EncodeError> {
+    if len == 0 || len > i32::MAX as usize {
+        Err(EncodeError::InvalidKey(len))
+    } else {
+        Ok(())
+    }
+}
+
+impl<T> Default for Dictionary<T> {
+    fn default() -> Self {
+        Dictionary::new()
+    }
 }
 
 impl<T> FromIterator<(String, Value<T>)> for Dictionary<T> {
     fn from_iter<I: IntoIterator<Item=(String, Value<T>)>>(iter: I) -> Self {
-        let data: HashMap<String, Value<T>> = iter.into_iter().collect();
+        let data: PropertyMap<T> = iter.into_iter().collect();
         Dictionary(data)
     }
 }
 
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+    use crate::packable::test::pack_unpack_test;
+    use crate::value::dictionary::{DictPatch, validate_key_len};
+    use crate::{Dictionary, Value, NoStruct, GenericStruct, EncodeError, DecodeError, DecodeOptions, Pack, Unpack};
+
+    fn dict(entries: &[(&str, Value<GenericStruct>)]) -> Dictionary<GenericStruct> {
+        entries.iter().map(|(k, v)| (String::from(*k), v.clone())).collect()
+    }
+
+    #[test]
+    fn validate_key_lengths() {
+        assert!(validate_key_len(1).is_ok());
+        assert!(validate_key_len(i32::MAX as usize).is_ok());
+        assert!(matches!(validate_key_len(0), Err(EncodeError::InvalidKey(0))));
+
+        let too_long = i32::MAX as usize + 1;
+        assert!(matches!(validate_key_len(too_long), Err(EncodeError::InvalidKey(l)) if l == too_long));
+    }
+
+    #[test]
+    fn encode_rejects_empty_key() {
+        let dict = dict(&[("a", Value::Integer(1)), ("", Value::Null), ("b", Value::Integer(2))]);
+        let mut buffer = Vec::new();
+
+        assert!(matches!(dict.encode(&mut buffer), Err(EncodeError::InvalidKey(0))));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn diff_and_apply_patch() {
+        let old = dict(&[("a", Value::Integer(1)), ("b", Value::from("x")), ("c", Value::Null)]);
+        let new = dict(&[("a", Value::Integer(1)), ("b", Value::from("y")), ("d", Value::Float(0.5))]);
+
+        let patch = old.diff(&new);
+        assert_eq!(patch.added, dict(&[("d", Value::Float(0.5))]));
+        assert_eq!(patch.changed, dict(&[("b", Value::from("y"))]));
+        assert_eq!(patch.removed, vec!(String::from("c")));
+
+        let mut patched = old.clone();
+        patched.apply_patch(patch.clone());
+        assert_eq!(patched, new);
+        assert!(new.diff(&patched).is_empty());
+
+        pack_unpack_test(&[patch, DictPatch::default()]);
+    }
+
+    #[test]
+    fn decode_patch_applies_options_to_removed() {
+        let patch: DictPatch<GenericStruct> = DictPatch {
+            removed: vec!(String::from("a"), String::from("b"), String::from("c")),
+            ..DictPatch::default()
+        };
+        let bytes = patch.to_vec().unwrap();
+        let options = DecodeOptions { max_collection_len: Some(2), ..DecodeOptions::default() };
+
+        assert!(matches!(
+            DictPatch::<GenericStruct>::decode_with(&mut bytes.as_slice(), &options),
+            Err(DecodeError::SizeLimitExceeded(3, 2))));
+    }
+
+    #[test]
+    fn map_like_accessors() {
+        let mut dict = dict(&[("a", Value::Integer(1)), ("b", Value::from("x")), ("c", Value::Null)]);
+
+        let mut keys: Vec<&String> = dict.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!("a", "b", "c"));
+
+        let mut values: Vec<String> = dict.values().map(|v| format!("{:?}", v)).collect();
+        values.sort();
+        assert_eq!(values, vec!("Integer(1)", "Null", "String(\"x\")"));
+
+        *dict.get_mut("a").unwrap() = Value::Integer(2);
+        assert_eq!(dict.get_property("a"), Some(&Value::Integer(2)));
+        assert!(dict.get_mut("d").is_none());
+
+        assert_eq!(dict.remove("b"), Some(Value::from("x")));
+        assert_eq!(dict.remove("b"), None);
+        assert_eq!(dict.len(), 2);
+    }
+
+    #[test]
+    fn typed_view_skips_other_values() {
+        let mut dict : Dictionary<NoStruct> = Dictionary::new();
+        dict.add_property("a", "x");
+        dict.add_property("b", 1);
+        dict.add_property("c", Value::Null);
+        dict.add_property("d", "y");
+
+        let strings = dict.typed_view::<String>();
+        let mut entries : Vec<(&String, &String)> = strings.iter().collect();
+        entries.sort();
+
+        assert_eq!(
+            entries,
+            vec!((&String::from("a"), &String::from("x")), (&String::from("d"), &String::from("y"))));
+        assert_eq!(strings.len(), 2);
+        assert!(!strings.is_empty());
+        assert!(dict.typed_view::<f64>().is_empty());
+    }
+
+    #[test]
+    fn encode_sorted_is_deterministic() {
+        let entries = [
+            ("zebra", Value::Integer(1)),
+            ("apple", Value::List(vec!(Value::Dictionary(dict(&[("y", Value::Null), ("x", Value::Null)]))))),
+            ("Äpfel", Value::from("sorted after the ASCII keys")),
+            ("mango", Value::Dictionary(dict(&[("2", Value::Integer(2)), ("1", Value::Integer(1))]))),
+        ];
+
+        let forward = dict(&entries);
+        let mut backward_entries = entries.clone();
+        backward_entries.reverse();
+        let backward = dict(&backward_entries);
+
+        let mut first = Vec::new();
+        forward.encode_sorted(&mut first).unwrap();
+        let mut second = Vec::new();
+        backward.encode_sorted(&mut second).unwrap();
+        assert_eq!(first, second);
+
+        // the keys in order, the nested ones included:
+        let position = |s: &str| first.windows(s.len()).position(|w| w == s.as_bytes()).unwrap();
+        assert!(position("apple") < position("x"));
+        assert!(position("x") < position("y"));
+        assert!(position("y") < position("mango"));
+        assert!(position("1") < position("2"));
+        assert!(position("zebra") < position("Äpfel"));
+
+        let decoded = <Dictionary<GenericStruct>>::decode(&mut first.as_slice()).unwrap();
+        assert_eq!(decoded, forward);
+    }
+
+    #[test]
+    fn encode_sorted_rejects_nested_empty_key() {
+        let inner = dict(&[("", Value::Null)]);
+        let outer = dict(&[("a", Value::Integer(1)), ("b", Value::List(vec!(Value::Dictionary(inner))))]);
+        let mut buffer = Vec::new();
+
+        assert!(matches!(outer.encode_sorted(&mut buffer), Err(EncodeError::InvalidKey(0))));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn convert_from_and_into_hash_map() {
+        let mut map : HashMap<String, Value<GenericStruct>> = HashMap::new();
+        map.insert(String::from("a"), Value::Integer(1));
+        map.insert(String::from("b"), Value::Null);
+
+        let mut dict = Dictionary::from_inner(map.clone());
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.clone().into_inner(), map);
+
+        dict.retain(|_, value| *value != Value::Null);
+        *dict.get_or_insert_with("c", || Value::Integer(0)) = Value::Integer(3);
+        let mut properties: Vec<(String, Value<GenericStruct>)> = dict.into_properties().collect();
+        properties.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(properties, vec!((String::from("a"), Value::Integer(1)), (String::from("c"), Value::Integer(3))));
+    }
+
+    #[test]
+    #[cfg(feature = "ordered")]
+    fn keeps_insertion_order() {
+        let keys = ["zeta", "alpha", "mu", "beta", "omega", "gamma"];
+        let mut dict = Dictionary::<NoStruct>::new();
+        for (i, key) in keys.iter().enumerate() {
+            dict.add_property(key, i as i64);
+        }
+        dict.extract_property("mu");
+
+        let expected: Vec<&str> = keys.iter().copied().filter(|k| *k != "mu").collect();
+        assert_eq!(dict.properties().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), expected);
+
+        let bytes = dict.to_vec().unwrap();
+        assert_eq!(&bytes[..6], &[0xA5, 0x84, b'z', b'e', b't', b'a']);
+
+        let decoded = <Dictionary<NoStruct>>::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded.properties().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), expected);
+        assert_eq!(decoded.to_vec().unwrap(), bytes);
+    }
+}
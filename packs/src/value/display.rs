@@ -0,0 +1,119 @@
+//! A readable one-line rendering of a [`Value`](crate::value::Value) for logs, given by its
+//! `Display` implementation.
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::Value;
+use crate::structure::{GenericStruct, NoStruct};
+
+/// Displays a value in a readable, JSON-like form without claiming to be JSON:
+///
+/// | value | rendering |
+/// | :--- | :--- |
+/// | `Null` | `null` |
+/// | integers, floats, booleans | as they are, e.g. `42`, `0.5`, `true` |
+/// | strings | quoted and escaped, e.g. `"a\"b"` |
+/// | byte arrays | hex, e.g. `0x00FF` |
+/// | lists | `[a, b, c]` |
+/// | dictionaries | `{key: value, ...}`, sorted by key |
+/// | structures | given by the `Display` of the structure |
+///
+/// It is meant for reading only; see
+/// [`into_json_string`](crate::value::Value::into_json_string) for JSON:
+/// ```
+/// use packs::*;
+///
+/// let mut dict = Dictionary::<GenericStruct>::new();
+/// dict.add_property("name", "Alice");
+/// dict.add_property("raw", Value::Bytes(Bytes(vec!(0x00, 0xFF))));
+/// dict.add_property("scores", vec!(Value::Integer(1), Value::Float(2.5), Value::Null));
+/// dict.add_property("node", Value::Structure(GenericStruct { tag_byte: 0x4E, fields: vec!(Value::Integer(7)) }));
+///
+/// assert_eq!(
+///     Value::Dictionary(dict).to_string(),
+///     r#"{name: "Alice", node: Structure(0x4E)[7], raw: 0x00FF, scores: [1, 2.5, null]}"#);
+/// ```
+impl<S: Display> Display for Value<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Integer(i) | Value::IntegerWide(i, _) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Bytes(bytes) => {
+                f.write_str("0x")?;
+                for b in &bytes.0 {
+                    write!(f, "{:02X}", b)?;
+                }
+                Ok(())
+            },
+            Value::List(list) => {
+                f.write_char('[')?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            },
+            Value::Dictionary(dict) => {
+                let mut entries: Vec<_> = dict.properties().collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                f.write_char('}')
+            },
+            Value::Structure(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Displays the tag and the fields, e.g. `Structure(0x4E)[1, "a"]`.
+impl Display for GenericStruct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Structure({:#04X})", self.tag_byte)?;
+        f.write_char('[')?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", field)?;
+        }
+        f.write_char(']')
+    }
+}
+
+impl Display for NoStruct {
+    fn fmt(&self, _: &mut Formatter<'_>) -> fmt::Result {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{Value, NoStruct, Bytes, Dictionary};
+
+    #[test]
+    fn display_values() {
+        let cases: Vec<(Value<NoStruct>, &str)> = vec!(
+            (Value::Null, "null"),
+            (Value::Float(1.0), "1.0"),
+            (Value::from("a\"b\n"), r#""a\"b\n""#),
+            (Value::Bytes(Bytes(Vec::new())), "0x"),
+            (Value::List(Vec::new()), "[]"),
+            (Value::Dictionary(Dictionary::new()), "{}"),
+            (Value::List(vec!(Value::List(vec!(Value::Boolean(false))))), "[[false]]"),
+        );
+
+        for (value, expected) in cases {
+            assert_eq!(value.to_string(), expected);
+        }
+    }
+}
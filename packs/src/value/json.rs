@@ -0,0 +1,190 @@
+//! Rendering of a [`Value`](crate::value::Value) as compact JSON, meant for logs and debugging
+//! output.
+#[cfg(feature = "json")]
+use std::convert::Infallible;
+
+use crate::{Pack, Unpack, Value, GenericStruct, JsonError};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl<S: Pack> Value<S> {
+    /// Renders the value as a line of compact JSON, e.g. for logging:
+    /// ```
+    /// use packs::*;
+    ///
+    /// let mut dict = Dictionary::<NoStruct>::new();
+    /// dict.add_property("name", "Jane \"JD\" Doe");
+    /// dict.add_property("scores", vec!(Value::Integer(1), Value::Float(2.5)));
+    /// dict.add_property("raw", Value::Bytes(Bytes(vec!(0x01, 0x02, 0x03))));
+    ///
+    /// assert_eq!(
+    ///     Value::Dictionary(dict).into_json_string().unwrap(),
+    ///     r#"{"name":"Jane \"JD\" Doe","raw":"AQID","scores":[1,2.5]}"#);
+    /// ```
+    /// Every value maps to valid JSON, but not every one without loss: byte arrays become base64
+    /// strings, floats which are not finite become `null` and a structure becomes an object
+    /// `{"$tag": tag, "fields": [...]}`. The output is meant to be read, not to be decoded again.
+    ///
+    /// A structure is only known by its encoding, so it is encoded and read back as a
+    /// [`GenericStruct`](crate::structure::GenericStruct); if this fails, the error is returned
+    /// as `JsonError`.
+    pub fn into_json_string(self) -> Result<String, JsonError> {
+        let mut json = String::new();
+        write_value(&self, &mut json, &write_packed_struct)?;
+        Ok(json)
+    }
+}
+
+/// The JSON of a value with generic structures, which cannot fail, see
+/// [`into_json_string`](crate::value::Value::into_json_string).
+#[cfg(feature = "json")]
+pub(crate) fn generic_json_string(value: &Value<GenericStruct>) -> String {
+    let mut json = String::new();
+    match write_value::<_, Infallible, _>(value, &mut json, &write_generic_struct) {
+        Ok(()) => json,
+        Err(e) => match e {},
+    }
+}
+
+fn write_value<S, E, F>(value: &Value<S>, json: &mut String, structure: &F) -> Result<(), E>
+    where F: Fn(&S, &mut String) -> Result<(), E> {
+    match value {
+        Value::Null => json.push_str("null"),
+        Value::Boolean(b) => json.push_str(&b.to_string()),
+        Value::Integer(i) | Value::IntegerWide(i, _) => json.push_str(&i.to_string()),
+        Value::Float(x) if x.is_finite() => json.push_str(&format!("{:?}", x)),
+        Value::Float(_) => json.push_str("null"),
+        Value::String(s) => write_json_string(s, json),
+        Value::Bytes(bytes) => {
+            json.push('"');
+            write_base64(&bytes.0, json);
+            json.push('"');
+        },
+        Value::List(list) => write_list(list, json, structure)?,
+        Value::Dictionary(dict) => {
+            // sorted, such that the same dictionary always gives the same line:
+            let mut entries: Vec<_> = dict.properties().collect();
+            entries.sort_by_key(|(key, _)| *key);
+
+            json.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_json_string(key, json);
+                json.push(':');
+                write_value(value, json, structure)?;
+            }
+            json.push('}');
+        },
+        Value::Structure(s) => structure(s, json)?,
+    }
+
+    Ok(())
+}
+
+fn write_list<S, E, F>(list: &[Value<S>], json: &mut String, structure: &F) -> Result<(), E>
+    where F: Fn(&S, &mut String) -> Result<(), E> {
+    json.push('[');
+    for (i, item) in list.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_value(item, json, structure)?;
+    }
+    json.push(']');
+    Ok(())
+}
+
+fn write_generic_struct<E>(s: &GenericStruct, json: &mut String) -> Result<(), E> {
+    json.push_str(&format!("{{\"$tag\":{},\"fields\":", s.tag_byte));
+    write_list(&s.fields, json, &write_generic_struct)?;
+    json.push('}');
+    Ok(())
+}
+
+fn write_packed_struct<S: Pack>(s: &S, json: &mut String) -> Result<(), JsonError> {
+    let mut buffer = Vec::with_capacity(s.size_hint());
+    s.encode(&mut buffer)?;
+    let generic = GenericStruct::decode(&mut buffer.as_slice())?;
+    write_generic_struct(&generic, json)
+}
+
+fn write_json_string(s: &str, json: &mut String) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+fn write_base64(bytes: &[u8], json: &mut String) {
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                json.push(BASE64_ALPHABET[(n >> (18 - 6 * i)) & 0x3F] as char);
+            } else {
+                json.push('=');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{Value, NoStruct, GenericStruct, ExtStruct, Bytes, JsonError};
+
+    #[test]
+    fn escapes_strings() {
+        let value = Value::<NoStruct>::String(String::from("a\"b\\c\nd\u{1}é"));
+        assert_eq!(value.into_json_string().unwrap(), r#""a\"b\\c\nd\u0001é""#);
+    }
+
+    #[test]
+    fn bytes_as_base64() {
+        let cases: [(&[u8], &str); 4] = [
+            (&[], r#""""#),
+            (b"f", r#""Zg==""#),
+            (b"fo", r#""Zm8=""#),
+            (b"foobar", r#""Zm9vYmFy""#),
+        ];
+        for (bytes, expected) in cases.iter() {
+            let value = Value::<NoStruct>::Bytes(Bytes(bytes.to_vec()));
+            assert_eq!(&value.into_json_string().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn structure_which_cannot_be_read_back() {
+        // more than 15 fields are encoded under a marker `GenericStruct` does not read:
+        let fields = vec!(Value::Null; 16);
+        let value = Value::Structure(ExtStruct { tag_byte: 0x01, fields });
+
+        assert!(matches!(value.into_json_string(), Err(JsonError::Decode(_))));
+    }
+
+    #[test]
+    fn structures_and_floats() {
+        let value = Value::List(vec!(
+            Value::Structure(GenericStruct {
+                tag_byte: 0x4E,
+                fields: vec!(Value::Integer(1), Value::Null),
+            }),
+            Value::Float(f64::NAN),
+            Value::Float(-0.5),
+        ));
+
+        assert_eq!(value.into_json_string().unwrap(), r#"[{"$tag":78,"fields":[1,null]},null,-0.5]"#);
+    }
+}
@@ -0,0 +1,241 @@
+//! `serde` support for [`Value`](crate::value::Value), behind the `serde` feature. PackStream types
+//! map to the serde data model as follows:
+//!
+//! | value | serde |
+//! | :--- | :--- |
+//! | `Null` | unit; `None` and unit both deserialize to `Null` |
+//! | `Boolean`, `Integer`, `Float`, `String` | `bool`, `i64`, `f64`, string |
+//! | `Bytes` | bytes |
+//! | `List` | sequence |
+//! | `Dictionary` | map with string keys |
+//! | `Structure(s)` | a map with the single key `"$structure"` and `s` as its value |
+//!
+//! Formats without a bytes type, like JSON, write bytes as a sequence of integers, which then
+//! deserializes as a `List`. Likewise a dictionary whose only key is `"$structure"` deserializes as
+//! a structure.
+use std::fmt::{self, Formatter};
+use std::marker::PhantomData;
+use std::convert::TryFrom;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::de::{self, Visitor, SeqAccess, MapAccess};
+
+use crate::Value;
+use crate::value::bytes::Bytes;
+use crate::value::dictionary::Dictionary;
+use crate::structure::NoStruct;
+
+/// The key which marks a structure.
+const STRUCTURE_KEY: &str = "$structure";
+
+impl<S: Serialize> Serialize for Value<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Boolean(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) | Value::IntegerWide(i, _) => serializer.serialize_i64(*i),
+            Value::Float(x) => serializer.serialize_f64(*x),
+            Value::Bytes(bytes) => bytes.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            },
+            Value::Dictionary(dict) => dict.serialize(serializer),
+            Value::Structure(s) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(STRUCTURE_KEY, s)?;
+                map.end()
+            },
+        }
+    }
+}
+
+impl<'de, S: Deserialize<'de>> Deserialize<'de> for Value<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor(PhantomData))
+    }
+}
+
+struct ValueVisitor<S>(PhantomData<S>);
+
+impl<'de, S: Deserialize<'de>> Visitor<'de> for ValueVisitor<S> {
+    type Value = Value<S>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a PackStream value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(Value::Boolean(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<Self::Value, E> {
+        Ok(Value::Integer(i))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<Self::Value, E> {
+        i64::try_from(u)
+            .map(Value::Integer)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(u), &"an integer of at most 64 bit"))
+    }
+
+    fn visit_f64<E: de::Error>(self, x: f64) -> Result<Self::Value, E> {
+        Ok(Value::Float(x))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(Value::String(String::from(s)))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Self::Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(Bytes(bytes.to_vec())))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(Bytes(bytes)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let first = match map.next_key::<String>()? {
+            Some(key) => key,
+            None => return Ok(Value::Dictionary(Dictionary::new())),
+        };
+
+        if first == STRUCTURE_KEY {
+            let s = map.next_value()?;
+            if map.next_key::<String>()?.is_none() {
+                return Ok(Value::Structure(s))
+            }
+            return Err(de::Error::custom("a structure is a map with a single entry"))
+        }
+
+        let mut dict = Dictionary::with_capacity(map.size_hint().unwrap_or(0) + 1);
+        dict.add_property(&first, map.next_value::<Value<S>>()?);
+        while let Some((key, value)) = map.next_entry::<String, Value<S>>()? {
+            dict.add_property(&key, value);
+        }
+        Ok(Value::Dictionary(dict))
+    }
+}
+
+impl<S: Serialize> Serialize for Dictionary<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.properties() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, S: Deserialize<'de>> Deserialize<'de> for Dictionary<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::<S>::deserialize(deserializer)? {
+            Value::Dictionary(dict) => Ok(dict),
+            _ => Err(de::Error::custom("expected a dictionary")),
+        }
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Vec<u8>>::deserialize(deserializer).map(Bytes)
+    }
+}
+
+impl Serialize for NoStruct {
+    fn serialize<Ser: Serializer>(&self, _: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match *self {}
+    }
+}
+
+/// Always fails, as there is no structure to deserialize into.
+impl<'de> Deserialize<'de> for NoStruct {
+    fn deserialize<D: Deserializer<'de>>(_: D) -> Result<Self, D::Error> {
+        Err(de::Error::custom("structures are not allowed"))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{Value, GenericStruct, NoStruct, Dictionary, Bytes};
+
+    fn json_round_trip<S>(value: &Value<S>) -> Value<S>
+        where S: serde::Serialize + serde::de::DeserializeOwned {
+        let json = serde_json::to_value(value).unwrap();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn round_trip_tree() {
+        let mut properties = Dictionary::new();
+        properties.add_property("name", "Alice");
+        properties.add_property("age", 42);
+        properties.add_property("score", 0.5);
+        properties.add_property("active", true);
+        properties.add_property("nothing", Value::Null);
+        properties.add_property("tags", vec!(Value::from("a"), Value::from("b")));
+        properties.add_property("empty", Value::Dictionary(Dictionary::new()));
+
+        let node = GenericStruct {
+            tag_byte: 0x4E,
+            fields: vec!(Value::Integer(1), Value::List(vec!(Value::from("Person"))), Value::Dictionary(properties)),
+        };
+        let value = Value::List(vec!(Value::Structure(node), Value::Integer(i64::MIN), Value::Null));
+
+        assert_eq!(json_round_trip(&value), value);
+    }
+
+    #[test]
+    fn json_representation() {
+        let value : Value<GenericStruct> =
+            Value::Structure(GenericStruct { tag_byte: 0x01, fields: vec!(Value::Null) });
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"$structure":{"tag_byte":1,"fields":[null]}}"#);
+
+        // JSON has no bytes, so they come back as a list:
+        let bytes : Value<NoStruct> = Value::Bytes(Bytes(vec!(0x01, 0xFF)));
+        assert_eq!(
+            json_round_trip(&bytes),
+            Value::List(vec!(Value::Integer(0x01), Value::Integer(0xFF))));
+
+        assert!(serde_json::from_str::<Value<NoStruct>>(r#"{"$structure":{}}"#).is_err());
+        assert!(serde_json::from_str::<Value<NoStruct>>("18446744073709551615").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! A visitor over the tree of a [`Value`](crate::value::Value), see
+//! [`Value::accept`](crate::value::Value::accept).
+use crate::Value;
+use crate::value::bytes::Bytes;
+
+/// Callbacks for a traversal of a value, driven by [`Value::accept`](crate::value::Value::accept).
+/// Every method does nothing by default, so a visitor only implements those it is interested in.
+///
+/// Lists are visited as `visit_list_start`, each of their items, then `visit_list_end`.
+/// Dictionaries are visited alike, with a `visit_dict_entry` for the key before each value.
+/// Structures are opaque to the traversal; a visitor can walk their fields itself by calling
+/// `accept` on them:
+/// ```
+/// use packs::*;
+///
+/// struct Strings(Vec<String>);
+///
+/// impl Visitor<GenericStruct> for Strings {
+///     fn visit_string(&mut self, s: &str) {
+///         self.0.push(String::from(s));
+///     }
+///
+///     fn visit_structure(&mut self, s: &GenericStruct) {
+///         for field in &s.fields {
+///             field.accept(self);
+///         }
+///     }
+/// }
+///
+/// let value = Value::List(vec!(
+///     Value::from("a"),
+///     Value::Structure(GenericStruct { tag_byte: 0x01, fields: vec!(Value::from("b")) })));
+///
+/// let mut strings = Strings(Vec::new());
+/// value.accept(&mut strings);
+/// assert_eq!(strings.0, vec!("a", "b"));
+/// ```
+pub trait Visitor<S> {
+    fn visit_null(&mut self) {}
+    fn visit_boolean(&mut self, _b: bool) {}
+    /// Called for `Integer` as well as for `IntegerWide`.
+    fn visit_integer(&mut self, _i: i64) {}
+    fn visit_float(&mut self, _x: f64) {}
+    fn visit_bytes(&mut self, _bytes: &Bytes) {}
+    fn visit_string(&mut self, _s: &str) {}
+    /// Called before the `len` items of a list.
+    fn visit_list_start(&mut self, _len: usize) {}
+    fn visit_list_end(&mut self) {}
+    /// Called before the `len` entries of a dictionary.
+    fn visit_dict_start(&mut self, _len: usize) {}
+    /// Called with the key of an entry, right before its value is visited.
+    fn visit_dict_entry(&mut self, _key: &str) {}
+    fn visit_dict_end(&mut self) {}
+    fn visit_structure(&mut self, _s: &S) {}
+}
+
+impl<S> Value<S> {
+    /// Walks the value depth first, calling the methods of the `visitor` on the way; see
+    /// [`Visitor`](crate::value::visitor::Visitor). The entries of a dictionary are visited in the
+    /// order of its iteration.
+    pub fn accept<V: Visitor<S> + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Value::Null => visitor.visit_null(),
+            Value::Boolean(b) => visitor.visit_boolean(*b),
+            Value::Integer(i) | Value::IntegerWide(i, _) => visitor.visit_integer(*i),
+            Value::Float(x) => visitor.visit_float(*x),
+            Value::Bytes(bytes) => visitor.visit_bytes(bytes),
+            Value::String(s) => visitor.visit_string(s),
+            Value::List(items) => {
+                visitor.visit_list_start(items.len());
+                for item in items {
+                    item.accept(visitor);
+                }
+                visitor.visit_list_end();
+            },
+            Value::Dictionary(dict) => {
+                visitor.visit_dict_start(dict.len());
+                for (key, value) in dict.properties() {
+                    visitor.visit_dict_entry(key);
+                    value.accept(visitor);
+                }
+                visitor.visit_dict_end();
+            },
+            Value::Structure(s) => visitor.visit_structure(s),
+        }
+    }
+}
+
+/// A sample visitor counting the nodes of a value: every value counts as one, including lists,
+/// dictionaries and structures themselves. Keys are not counted, neither are the fields of
+/// structures.
+/// ```
+/// use packs::*;
+///
+/// let value : Value<NoStruct> = Value::List(vec!(Value::Integer(1), Value::List(vec!(Value::Null))));
+///
+/// let mut counter = NodeCounter::default();
+/// value.accept(&mut counter);
+/// assert_eq!(counter.nodes, 4);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCounter {
+    pub nodes: usize,
+}
+
+impl<S> Visitor<S> for NodeCounter {
+    fn visit_null(&mut self) { self.nodes += 1; }
+    fn visit_boolean(&mut self, _b: bool) { self.nodes += 1; }
+    fn visit_integer(&mut self, _i: i64) { self.nodes += 1; }
+    fn visit_float(&mut self, _x: f64) { self.nodes += 1; }
+    fn visit_bytes(&mut self, _bytes: &Bytes) { self.nodes += 1; }
+    fn visit_string(&mut self, _s: &str) { self.nodes += 1; }
+    fn visit_list_start(&mut self, _len: usize) { self.nodes += 1; }
+    fn visit_dict_start(&mut self, _len: usize) { self.nodes += 1; }
+    fn visit_structure(&mut self, _s: &S) { self.nodes += 1; }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{Value, NoStruct, Dictionary};
+    use crate::value::visitor::{Visitor, NodeCounter};
+
+    /// Records the traversal as a sequence of events.
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Visitor<NoStruct> for Events {
+        fn visit_null(&mut self) { self.0.push(String::from("null")); }
+        fn visit_integer(&mut self, i: i64) { self.0.push(i.to_string()); }
+        fn visit_string(&mut self, s: &str) { self.0.push(format!("{:?}", s)); }
+        fn visit_list_start(&mut self, len: usize) { self.0.push(format!("[{}", len)); }
+        fn visit_list_end(&mut self) { self.0.push(String::from("]")); }
+        fn visit_dict_start(&mut self, len: usize) { self.0.push(format!("{{{}", len)); }
+        fn visit_dict_entry(&mut self, key: &str) { self.0.push(format!("{}:", key)); }
+        fn visit_dict_end(&mut self) { self.0.push(String::from("}")); }
+    }
+
+    #[test]
+    fn traversal_order() {
+        let mut dict = Dictionary::new();
+        dict.add_property("k", vec!(1, 2).into_iter().collect::<Value<NoStruct>>());
+        let value : Value<NoStruct> =
+            Value::List(vec!(Value::Null, Value::Dictionary(dict), Value::from("s"), Value::List(vec!())));
+
+        let mut events = Events::default();
+        value.accept(&mut events);
+        assert_eq!(
+            events.0,
+            vec!("[4", "null", "{1", "k:", "[2", "1", "2", "]", "}", "\"s\"", "[0", "]", "]"));
+
+        let mut counter = NodeCounter::default();
+        value.accept(&mut counter);
+        assert_eq!(counter.nodes, 8);
+    }
+}
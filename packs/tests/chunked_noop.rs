@@ -0,0 +1,39 @@
+use packs::*;
+use packs::framing::ChunkedReader;
+
+/// Frames the message into chunks of at most `chunk_size` bytes, preceded by `noops` NOOPs.
+fn chunked(message: &[u8], chunk_size: usize, noops: usize) -> Vec<u8> {
+    let mut bytes = [0x00, 0x00].repeat(noops);
+    for chunk in message.chunks(chunk_size) {
+        bytes.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+    bytes.extend_from_slice(&[0x00, 0x00]);
+    bytes
+}
+
+#[test]
+fn decode_values_between_noops() {
+    let values: Vec<Value<GenericStruct>> = vec!(
+        Value::Structure(GenericStruct {
+            tag_byte: 0x71,
+            fields: vec!((0..50).map(|i| Value::String(format!("item {}", i))).collect()),
+        }),
+        Value::Integer(0),
+        Value::Null,
+    );
+
+    let mut stream = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        stream.extend(chunked(&value.to_vec().unwrap(), 16, i + 1));
+    }
+    // NOOPs after the last message:
+    stream.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+    let mut reader = ChunkedReader::new(stream.as_slice());
+    for value in values {
+        assert_eq!(<Value<GenericStruct>>::decode(&mut reader).unwrap(), value);
+        // the rest of the message is empty:
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+}
@@ -0,0 +1,44 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[packs(as_list)]
+struct Record {
+    name: String,
+    age: i64,
+    active: bool,
+}
+
+#[test]
+fn pack_unpack_struct_as_list() {
+    let record = Record { name: String::from("Ada"), age: 36, active: true };
+    let mut buf = Vec::new();
+    record.encode(&mut buf).expect("Cannot encode 'Record'");
+
+    assert_eq!(
+        buf,
+        vec![
+            0x93, // TinyList(3)
+            0x83, 0x41, 0x64, 0x61,
+            0x24,
+            0xC2]); // True
+
+    let decoded = Record::decode(&mut buf.as_slice()).expect("Cannot decode 'Record'");
+    assert_eq!(record, decoded);
+    assert!(record.size_hint() >= buf.len());
+}
+
+#[test]
+fn unpack_list_of_other_length() {
+    let bytes: &[u8] = &[0x92, 0x80, 0x01];
+    match Record::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfElements(3, 2)) => (),
+        r => panic!("Expected UnexpectedNumberOfElements, got {:?}", r),
+    }
+}
+
+#[test]
+fn unpack_list_with_list8_marker() {
+    let bytes: &[u8] = &[0xD4, 0x03, 0x80, 0x01, 0xC3];
+    let decoded = Record::decode(&mut &bytes[..]).expect("Cannot decode 'Record'");
+    assert_eq!(decoded, Record { name: String::new(), age: 1, active: false });
+}
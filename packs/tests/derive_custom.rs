@@ -8,7 +8,7 @@ struct Part {
 }
 
 fn pack_part<T: Write>(part: &Part, writer: &mut T) -> Result<usize, EncodeError> {
-    Ok(&part.field_a.encode(writer)? + &part.field_b.encode(writer)?)
+    Ok(part.field_a.encode(writer)? + part.field_b.encode(writer)?)
 }
 
 fn unpack_part<T: Read>(reader: &mut T) -> Result<Part, DecodeError> {
@@ -73,4 +73,20 @@ fn pack_check_struct() {
             0x87, 0x66, 0x69, 0x65, 0x6c, 0x64, 0x20, 0x41, // "field A"
             0xC0, // None
             42]); // 42
-}
\ No newline at end of file
+}
+#[test]
+fn size_hint_skips_custom_fields() {
+    let c = Complete {
+        part: Part {
+            field_a: String::from("field A"),
+            field_b: Some(String::from("field B")),
+        },
+        id: 42,
+    };
+
+    // the custom field is estimated with zero, it is not encoded for the hint:
+    assert_eq!(c.size_hint(), 2 + 42i64.size_hint());
+
+    let len = c.to_vec().expect("Cannot encode 'Complete'").len();
+    assert_eq!(c.encoded_len(), len);
+}
@@ -0,0 +1,89 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Version1 {
+    name: String,
+    age: i64,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Version2 {
+    name: String,
+    age: i64,
+    #[packs(default)]
+    email: Option<String>,
+}
+
+#[test]
+fn unpack_with_missing_trailing_field() {
+    let old = Version1 { name: String::from("Ada"), age: 36 };
+    let bytes = old.to_vec().unwrap();
+
+    let decoded = Version2::decode(&mut bytes.as_slice()).expect("Cannot decode 'Version2'");
+    assert_eq!(decoded, Version2 { name: String::from("Ada"), age: 36, email: None });
+}
+
+#[test]
+fn pack_unpack_with_all_fields() {
+    let new = Version2 { name: String::from("Ada"), age: 36, email: Some(String::from("ada@example.com")) };
+    let bytes = new.to_vec().unwrap();
+    assert_eq!(bytes[0], 0xB3);
+
+    let decoded = Version2::decode(&mut bytes.as_slice()).expect("Cannot decode 'Version2'");
+    assert_eq!(decoded, new);
+}
+
+#[test]
+fn unpack_with_too_few_fields() {
+    let bytes: &[u8] = &[0xB1, 0x01, 0x80];
+    match Version2::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(3, 1)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
+
+#[derive(Debug, PartialEq, Default)]
+struct Contact {
+    email: String,
+    phone: String,
+}
+
+fn pack_contact<T: std::io::Write>(contact: &Contact, writer: &mut T) -> Result<usize, EncodeError> {
+    Ok(contact.email.encode(writer)? + contact.phone.encode(writer)?)
+}
+
+fn unpack_contact<T: std::io::Read>(reader: &mut T) -> Result<Contact, DecodeError> {
+    Ok(Contact { email: String::decode(reader)?, phone: String::decode(reader)? })
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Version3 {
+    name: String,
+    #[packs(default)]
+    #[pack(pack_contact)]
+    #[unpack(unpack_contact)]
+    #[fields = 2]
+    contact: Contact,
+}
+
+#[test]
+fn unpack_only_whole_entries_of_several_fields() {
+    let decoded = Version3::decode(&mut [0xB1, 0x01, 0x81, 0x61].as_ref()).unwrap();
+    assert_eq!(decoded, Version3 { name: String::from("a"), contact: Contact::default() });
+
+    let full = Version3 {
+        name: String::from("a"),
+        contact: Contact { email: String::from("b"), phone: String::from("c") },
+    };
+    assert_eq!(Version3::decode(&mut full.to_vec().unwrap().as_slice()).unwrap(), full);
+
+    // the second field ends within `contact`:
+    let bytes: &[u8] = &[0xB2, 0x01, 0x81, 0x61, 0x81, 0x62];
+    match Version3::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(3, 2)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
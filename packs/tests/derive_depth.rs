@@ -0,0 +1,67 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Tree {
+    children: Vec<Tree>,
+}
+
+/// A `Tree` nested `depth` times, each level being a structure holding a list of one tree.
+fn nested_tree_bytes(depth: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for _ in 0..depth {
+        bytes.extend_from_slice(&[0xB1, 0x01, 0x91]);
+    }
+    bytes.extend_from_slice(&[0xB1, 0x01, 0x90]);
+    bytes
+}
+
+fn with_max_depth(depth: usize) -> DecodeOptions {
+    DecodeOptions { max_depth: Some(depth), ..DecodeOptions::default() }
+}
+
+#[test]
+fn decode_nested_derived_struct_within_limit() {
+    let bytes = nested_tree_bytes(3);
+    // each level takes two: the structure and the list
+    let tree = Tree::decode_with(&mut bytes.as_slice(), &with_max_depth(8)).unwrap();
+    assert_eq!(tree.to_vec().unwrap(), bytes);
+}
+
+#[test]
+fn decode_nested_derived_struct_beyond_limit() {
+    let bytes = nested_tree_bytes(1000);
+    match Tree::decode_with(&mut bytes.as_slice(), &with_max_depth(64)) {
+        Err(DecodeError::DepthLimitExceeded) => (),
+        r => panic!("Expected DepthLimitExceeded, got {:?}", r),
+    }
+
+    let bytes = nested_tree_bytes(3);
+    match Tree::decode_with(&mut bytes.as_slice(), &with_max_depth(7)) {
+        Err(DecodeError::DepthLimitExceeded) => (),
+        r => panic!("Expected DepthLimitExceeded, got {:?}", r),
+    }
+}
+
+#[cfg(feature = "std_structs")]
+#[test]
+fn decode_nested_values_in_std_struct_beyond_limit() {
+    use packs::std_structs::{Node, StdStruct, StdStructPrimitive};
+
+    let mut node = Node::new(1);
+    let mut nested : Value<StdStructPrimitive> = Value::Null;
+    for _ in 0..10 {
+        nested = Value::List(vec!(nested));
+    }
+    node.properties.add_property("nested", nested);
+    let bytes = Value::Structure(StdStruct::Node(node.clone())).to_vec().unwrap();
+
+    match <Value<StdStruct>>::decode_with(&mut bytes.as_slice(), &with_max_depth(8)) {
+        Err(DecodeError::DepthLimitExceeded) => (),
+        r => panic!("Expected DepthLimitExceeded, got {:?}", r),
+    }
+
+    // the node, its properties and the ten lists:
+    let decoded = <Value<StdStruct>>::decode_with(&mut bytes.as_slice(), &with_max_depth(12)).unwrap();
+    assert_eq!(decoded, Value::Structure(StdStruct::Node(node)));
+}
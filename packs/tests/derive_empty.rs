@@ -0,0 +1,40 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x0F]
+struct Goodbye {}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x0E]
+struct Reset;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+enum Message {
+    #[tag = 0x0F]
+    Goodbye(Goodbye),
+    #[tag = 0x0E]
+    Reset(Reset),
+}
+
+#[test]
+fn pack_unpack_struct_without_fields() {
+    let bytes = Goodbye {}.to_vec().unwrap();
+    assert_eq!(bytes, vec!(0xB0, 0x0F));
+    assert_eq!(Goodbye {}.encoded_len(), 2);
+    assert_eq!(Goodbye::decode(&mut bytes.as_slice()).unwrap(), Goodbye {});
+
+    let bytes = Reset.to_vec().unwrap();
+    assert_eq!(bytes, vec!(0xB0, 0x0E));
+    assert_eq!(Reset::decode(&mut bytes.as_slice()).unwrap(), Reset);
+}
+
+#[test]
+fn unpack_sum_of_structs_without_fields() {
+    assert_eq!(Message::decode(&mut [0xB0, 0x0E].as_ref()).unwrap(), Message::Reset(Reset));
+    assert_eq!(Message::Goodbye(Goodbye {}).to_vec().unwrap(), vec!(0xB0, 0x0F));
+
+    match Goodbye::decode(&mut [0xB1, 0x0F, 0xC0].as_ref()) {
+        Err(DecodeError::UnexpectedNumberOfFields(0, 1)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
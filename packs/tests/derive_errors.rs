@@ -0,0 +1,7 @@
+/// The derive macros report misuse as compile errors pointing at the offending item; the expected
+/// messages are kept next to each case in `tests/ui`.
+#[test]
+fn derive_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,68 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Unpack)]
+#[packs(from_dict)]
+struct SuccessMeta {
+    server: String,
+    #[rename = "connection_id"]
+    connection: String,
+    hints: Option<Dictionary<GenericStruct>>,
+    t_first: Option<i64>,
+}
+
+fn meta() -> Dictionary<GenericStruct> {
+    let mut dict = Dictionary::new();
+    dict.add_property("server", "Neo4j/4.2.0");
+    dict.add_property("connection_id", "bolt-1");
+    dict.add_property("t_first", Value::Null);
+    dict
+}
+
+#[test]
+fn from_dictionary() {
+    let meta = SuccessMeta::from_dictionary(meta()).expect("Cannot read 'SuccessMeta'");
+
+    assert_eq!(
+        meta,
+        SuccessMeta {
+            server: String::from("Neo4j/4.2.0"),
+            connection: String::from("bolt-1"),
+            hints: None,
+            t_first: None,
+        });
+}
+
+#[test]
+fn from_dictionary_missing_key() {
+    let mut dict = meta();
+    dict.extract_property("connection_id");
+
+    match SuccessMeta::from_dictionary(dict) {
+        Err(DecodeError::MissingProperty(key)) => assert_eq!(key, "connection_id"),
+        r => panic!("Expected MissingProperty, got {:?}", r),
+    }
+}
+
+#[test]
+fn from_dictionary_unexpected_type() {
+    let mut dict = meta();
+    dict.add_property("server", 4);
+
+    match SuccessMeta::from_dictionary(dict) {
+        Err(DecodeError::UnexpectedPropertyType(key)) => assert_eq!(key, "server"),
+        r => panic!("Expected UnexpectedPropertyType, got {:?}", r),
+    }
+}
+
+#[test]
+fn unpack_from_dictionary() {
+    let mut dict = meta();
+    dict.add_property("t_first", 12);
+
+    let mut buf = Vec::new();
+    dict.encode(&mut buf).expect("Cannot encode 'Dictionary'");
+    let meta = SuccessMeta::decode(&mut buf.as_slice()).expect("Cannot decode 'SuccessMeta'");
+
+    assert_eq!(meta.t_first, Some(12));
+    assert_eq!(meta.connection, "bolt-1");
+}
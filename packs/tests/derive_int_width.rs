@@ -0,0 +1,55 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Reading {
+    #[packs(int_width = 8)]
+    sensor: i64,
+    #[packs(int_width = 2)]
+    value: i32,
+    offset: i64,
+}
+
+#[test]
+fn pack_small_value_with_full_width() {
+    let reading = Reading { sensor: 1, value: 2, offset: 3 };
+    let mut buf = Vec::new();
+    reading.encode(&mut buf).expect("Cannot encode 'Reading'");
+
+    assert_eq!(
+        buf,
+        vec![
+            0xB3, 0x01,
+            0xCB, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // Int64
+            0xC9, 0x00, 0x02, // Int16
+            0x03]); // PlusTinyInt
+
+    let decoded = Reading::decode(&mut buf.as_slice()).expect("Cannot decode 'Reading'");
+    assert_eq!(reading, decoded);
+}
+
+#[test]
+fn pack_value_out_of_width() {
+    let reading = Reading { sensor: 1, value: 70000, offset: 3 };
+    let mut buf = Vec::new();
+
+    match reading.encode(&mut buf) {
+        Err(EncodeError::IntegerOutOfRange(70000, 2)) => (),
+        r => panic!("Expected IntegerOutOfRange, got {:?}", r),
+    }
+}
+
+#[test]
+fn size_hint_of_derived_struct() {
+    let reading = Reading { sensor: 1, value: 2, offset: 3 };
+
+    // header, the fixed widths and the worst case of an `i64`:
+    assert_eq!(reading.size_hint(), 2 + 9 + 3 + 9);
+    assert!(reading.size_hint() >= reading.to_vec().unwrap().len());
+}
+
+#[test]
+fn encoded_len_of_derived_struct() {
+    let reading = Reading { sensor: 1, value: 2, offset: 300 };
+    assert_eq!(reading.encoded_len(), reading.to_vec().unwrap().len());
+}
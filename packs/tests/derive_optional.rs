@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use packs::*;
+
+/// A node as sent by older servers, without an element id.
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x4E]
+struct NodeV4 {
+    id: i64,
+    labels: HashSet<String>,
+    properties: Dictionary<NoStruct>,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x4E]
+struct NodeV5 {
+    id: i64,
+    labels: HashSet<String>,
+    properties: Dictionary<NoStruct>,
+    #[packs(optional)]
+    element_id: Option<String>,
+}
+
+fn labels() -> HashSet<String> {
+    vec!(String::from("Person")).into_iter().collect()
+}
+
+#[test]
+fn decode_short_form() {
+    let old = NodeV4 { id: 1, labels: labels(), properties: Dictionary::new() };
+    let bytes = old.to_vec().unwrap();
+    assert_eq!(bytes[0], 0xB3);
+
+    let decoded = NodeV5::decode(&mut bytes.as_slice()).expect("Cannot decode 'NodeV5'");
+    assert_eq!(decoded, NodeV5 { id: 1, labels: labels(), properties: Dictionary::new(), element_id: None });
+}
+
+#[test]
+fn decode_long_form() {
+    let new = NodeV5 {
+        id: 1,
+        labels: labels(),
+        properties: Dictionary::new(),
+        element_id: Some(String::from("4:abc:1")),
+    };
+    let bytes = new.to_vec().unwrap();
+    assert_eq!(bytes[0], 0xB4);
+
+    let decoded = NodeV5::decode(&mut bytes.as_slice()).expect("Cannot decode 'NodeV5'");
+    assert_eq!(decoded, new);
+}
+
+#[test]
+fn reject_other_lengths() {
+    // an extra fifth field:
+    let bytes: &[u8] = &[0xB5, 0x4E, 0x01, 0x90, 0xA0, 0x80, 0x80];
+    match NodeV5::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(4, 5)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+
+    // too few fields to fill the required ones:
+    let bytes: &[u8] = &[0xB2, 0x4E, 0x01, 0x90];
+    match NodeV5::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(4, 2)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
+
+fn pack_point<T: std::io::Write>(point: &Option<(i64, i64)>, writer: &mut T) -> Result<usize, EncodeError> {
+    let (x, y) = point.unwrap_or_default();
+    Ok(x.encode(writer)? + y.encode(writer)?)
+}
+
+fn unpack_point<T: std::io::Read>(reader: &mut T) -> Result<Option<(i64, i64)>, DecodeError> {
+    Ok(Some((i64::decode(reader)?, i64::decode(reader)?)))
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x50]
+struct Marked {
+    id: i64,
+    #[packs(optional)]
+    #[pack(pack_point)]
+    #[unpack(unpack_point)]
+    #[fields = 2]
+    point: Option<(i64, i64)>,
+}
+
+#[test]
+fn reject_length_within_optional_entry() {
+    let decoded = Marked::decode(&mut [0xB1, 0x50, 0x01].as_ref()).unwrap();
+    assert_eq!(decoded, Marked { id: 1, point: None });
+
+    let decoded = Marked::decode(&mut [0xB3, 0x50, 0x01, 0x02, 0x03].as_ref()).unwrap();
+    assert_eq!(decoded, Marked { id: 1, point: Some((2, 3)) });
+
+    // only `x` of the point:
+    let bytes: &[u8] = &[0xB2, 0x50, 0x01, 0x02];
+    match Marked::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(3, 2)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
@@ -0,0 +1,66 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, PackString)]
+enum Status {
+    Active,
+    Inactive,
+    #[rename = "on-hold"]
+    OnHold,
+}
+
+#[test]
+fn pack_unpack_status() {
+    for status in &[Status::Active, Status::Inactive, Status::OnHold] {
+        let mut buf = Vec::new();
+        status.encode(&mut buf).expect("Cannot encode 'Status'");
+        let decoded = Status::decode(&mut buf.as_slice()).expect("Cannot decode 'Status'");
+
+        assert_eq!(status, &decoded);
+    }
+}
+
+#[test]
+fn pack_check_status() {
+    let mut buf = Vec::new();
+    Status::OnHold.encode(&mut buf).expect("Cannot encode 'Status'");
+
+    assert_eq!(
+        buf,
+        vec![0x87, 0x6F, 0x6E, 0x2D, 0x68, 0x6F, 0x6C, 0x64]); // "on-hold"
+
+    let mut buf = Vec::new();
+    Status::Active.encode(&mut buf).expect("Cannot encode 'Status'");
+
+    assert_eq!(
+        buf,
+        vec![0x86, 0x41, 0x63, 0x74, 0x69, 0x76, 0x65]); // "Active"
+}
+
+#[test]
+fn unpack_unknown_status() {
+    let mut buf = Vec::new();
+    String::from("Deleted").encode(&mut buf).unwrap();
+
+    match Status::decode(&mut buf.as_slice()) {
+        Err(DecodeError::UnknownVariant(s)) => assert_eq!(s, "Deleted"),
+        r => panic!("Expected UnknownVariant, got {:?}", r),
+    }
+}
+
+#[test]
+fn encoded_len_of_status() {
+    let len = Status::OnHold.to_vec().unwrap().len();
+    assert_eq!(Status::OnHold.encoded_len(), len);
+    assert_eq!(Status::OnHold.size_hint(), len);
+}
+
+#[test]
+fn unpack_status_with_options() {
+    let buf = Status::OnHold.to_vec().unwrap();
+    let options = DecodeOptions { max_string_len: Some(4), ..DecodeOptions::default() };
+
+    match Status::decode_with(&mut buf.as_slice(), &options) {
+        Err(DecodeError::SizeLimitExceeded(7, 4)) => (),
+        r => panic!("Expected SizeLimitExceeded, got {:?}", r),
+    }
+}
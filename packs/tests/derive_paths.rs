@@ -0,0 +1,44 @@
+// no `use packs::*`: the derived implementations name everything by its full path.
+use packs::{Pack, Unpack, PackString};
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Point {
+    #[packs(int_width = 2)]
+    x: i64,
+    y: i64,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[packs(as_list)]
+struct Pair {
+    first: bool,
+    second: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+enum Shape {
+    #[tag = 0x02]
+    Circle { radius: f64 },
+    #[tag = 0x01]
+    Point(Point),
+}
+
+#[derive(Debug, PartialEq, PackString)]
+enum Colour {
+    Red,
+}
+
+#[test]
+fn derive_without_glob_import() {
+    let shapes = vec!(Shape::Circle { radius: 1.5 }, Shape::Point(Point { x: 1, y: 2 }));
+    let bytes = shapes.to_vec().unwrap();
+    assert_eq!(<Vec<Shape>>::decode(&mut bytes.as_slice()).unwrap(), shapes);
+
+    let pair = Pair { first: true, second: None };
+    let bytes = pair.to_vec().unwrap();
+    assert_eq!(Pair::decode(&mut bytes.as_slice()).unwrap(), pair);
+
+    let bytes = Colour::Red.to_vec().unwrap();
+    assert_eq!(Colour::decode(&mut bytes.as_slice()).unwrap(), Colour::Red);
+}
@@ -0,0 +1,66 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Full {
+    name: String,
+    age: i64,
+    email: String,
+    score: f64,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Partial {
+    name: String,
+    #[packs(rest)]
+    rest: Vec<Value<GenericStruct>>,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x02]
+struct OnlyRest {
+    #[packs(rest)]
+    fields: Vec<Value<NoStruct>>,
+}
+
+#[test]
+fn round_trip_keeps_extra_fields() {
+    let full = Full { name: String::from("Ada"), age: 36, email: String::from("ada@example.com"), score: 0.5 };
+    let bytes = full.to_vec().unwrap();
+
+    let mut partial = Partial::decode(&mut bytes.as_slice()).expect("Cannot decode 'Partial'");
+    assert_eq!(partial.name, "Ada");
+    assert_eq!(
+        partial.rest,
+        vec!(Value::Integer(36), Value::String(String::from("ada@example.com")), Value::Float(0.5)));
+
+    partial.name = String::from("Grace");
+    let edited = partial.to_vec().unwrap();
+    assert_eq!(edited.len(), partial.encoded_len());
+
+    let decoded = Full::decode(&mut edited.as_slice()).expect("Cannot decode 'Full'");
+    assert_eq!(decoded, Full { name: String::from("Grace"), ..full });
+}
+
+#[test]
+fn rest_can_be_empty() {
+    let bytes: &[u8] = &[0xB1, 0x01, 0x81, 0x61];
+    let partial = Partial::decode(&mut &bytes[..]).expect("Cannot decode 'Partial'");
+    assert_eq!(partial, Partial { name: String::from("a"), rest: Vec::new() });
+    assert_eq!(partial.to_vec().unwrap(), bytes);
+
+    let only = OnlyRest::decode(&mut &[0xB0, 0x02][..]).expect("Cannot decode 'OnlyRest'");
+    assert!(only.fields.is_empty());
+}
+
+#[test]
+fn too_few_or_too_many_fields() {
+    match Partial::decode(&mut &[0xB0, 0x01][..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(1, 0)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+
+    let only = OnlyRest { fields: (0..16).map(Value::Integer).collect() };
+    assert!(matches!(only.to_vec(), Err(EncodeError::TooManyStructFields(16))));
+}
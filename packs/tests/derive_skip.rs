@@ -0,0 +1,54 @@
+use std::marker::PhantomData;
+use packs::*;
+
+#[derive(Debug, PartialEq, Default)]
+struct Meters;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Distance {
+    #[packs(skip)]
+    unit: PhantomData<Meters>,
+    value: f64,
+    #[packs(skip)]
+    cached_label: Option<String>,
+    source: String,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Plain {
+    value: f64,
+    source: String,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x02]
+struct OnlySkipped {
+    #[packs(skip)]
+    cache: Vec<i64>,
+}
+
+#[test]
+fn skipped_fields_are_not_on_the_wire() {
+    let distance = Distance {
+        unit: PhantomData,
+        value: 1.5,
+        cached_label: Some(String::from("1.5 m")),
+        source: String::from("gps"),
+    };
+    let bytes = distance.to_vec().unwrap();
+    assert_eq!(bytes.len(), distance.encoded_len());
+    assert_eq!(bytes, Plain { value: 1.5, source: String::from("gps") }.to_vec().unwrap());
+    assert_eq!(bytes[0], 0xB2);
+
+    let decoded = Distance::decode(&mut bytes.as_slice()).expect("Cannot decode 'Distance'");
+    assert_eq!(decoded, Distance { cached_label: None, ..distance });
+}
+
+#[test]
+fn struct_of_skipped_fields_is_empty() {
+    let bytes = OnlySkipped { cache: vec!(1, 2, 3) }.to_vec().unwrap();
+    assert_eq!(bytes, &[0xB0, 0x02]);
+    assert_eq!(OnlySkipped::decode(&mut bytes.as_slice()).unwrap(), OnlySkipped { cache: Vec::new() });
+}
@@ -0,0 +1,63 @@
+use packs::*;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x03]
+struct Goodbye {
+    reason: String,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+enum Msg {
+    #[tag = 0x01]
+    Hello { agent: String, version: i64 },
+    #[tag = 0x02]
+    Pair(i64, bool),
+    #[tag = 0x03]
+    Goodbye(Goodbye),
+    #[tag = 0x04]
+    Empty {},
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag = 0x01]
+struct Hello {
+    agent: String,
+    version: i64,
+}
+
+#[test]
+fn round_trip_mixed_enum() {
+    let msgs = vec!(
+        Msg::Hello { agent: String::from("packs/0.2"), version: 4 },
+        Msg::Pair(-1, true),
+        Msg::Goodbye(Goodbye { reason: String::from("done") }),
+        Msg::Empty {},
+    );
+
+    for msg in msgs {
+        let bytes = msg.to_vec().unwrap();
+        assert_eq!(bytes.len(), msg.encoded_len());
+        assert_eq!(Msg::decode(&mut bytes.as_slice()).expect("Cannot decode 'Msg'"), msg);
+    }
+}
+
+#[test]
+fn struct_variant_is_a_structure() {
+    let msg = Msg::Hello { agent: String::from("a"), version: 1 };
+    let bytes = msg.to_vec().unwrap();
+    assert_eq!(bytes, &[0xB2, 0x01, 0x81, 0x61, 0x01]);
+
+    let hello = Hello::decode(&mut bytes.as_slice()).expect("Cannot decode 'Hello'");
+    assert_eq!(hello, Hello { agent: String::from("a"), version: 1 });
+
+    assert_eq!(Msg::Empty {}.to_vec().unwrap(), &[0xB0, 0x04]);
+}
+
+#[test]
+fn wrong_number_of_fields() {
+    let bytes: &[u8] = &[0xB1, 0x02, 0x01];
+    match Msg::decode(&mut &bytes[..]) {
+        Err(DecodeError::UnexpectedNumberOfFields(2, 1)) => (),
+        r => panic!("Expected UnexpectedNumberOfFields, got {:?}", r),
+    }
+}
@@ -0,0 +1,71 @@
+use packs::*;
+
+mod tags {
+    pub const POINT: u8 = 0x01;
+    pub const LINE: u8 = 0x02;
+    pub const LINE_V2: u8 = LINE + 0x10;
+}
+
+const TAG: u8 = 0x03;
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag(tags::POINT)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+#[tag(TAG)]
+struct Label {
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Pack, Unpack)]
+enum Shape {
+    #[tag(tags::POINT)]
+    Point(Point),
+    #[tag(tags::LINE)]
+    #[tag(tags::LINE_V2)]
+    Line { from: Point, to: Point },
+    #[tag = 0x03]
+    Label(Label),
+}
+
+#[test]
+fn struct_with_const_tag() {
+    let point = Point { x: 1, y: -1 };
+    let bytes = point.to_vec().unwrap();
+    assert_eq!(bytes, &[0xB2, tags::POINT, 0x01, 0xFF]);
+    assert_eq!(Point::decode(&mut bytes.as_slice()).unwrap(), point);
+
+    let label = Label { text: String::from("a") };
+    let bytes = label.to_vec().unwrap();
+    assert_eq!(bytes[1], TAG);
+    assert_eq!(Label::decode(&mut bytes.as_slice()).unwrap(), label);
+
+    match Label::decode(&mut &[0xB1, 0x04, 0x80][..]) {
+        Err(DecodeError::UnexpectedTagByte(0x04)) => (),
+        r => panic!("Expected UnexpectedTagByte, got {:?}", r),
+    }
+}
+
+#[test]
+fn enum_with_const_tags() {
+    let shapes = vec!(
+        Shape::Point(Point { x: 0, y: 0 }),
+        Shape::Line { from: Point { x: 0, y: 0 }, to: Point { x: 1, y: 1 } },
+        Shape::Label(Label { text: String::from("origin") }),
+    );
+
+    for shape in shapes {
+        let bytes = shape.to_vec().unwrap();
+        assert_eq!(Shape::decode(&mut bytes.as_slice()).unwrap(), shape);
+    }
+
+    // the second tag is accepted as well:
+    let bytes: &[u8] = &[0xB2, tags::LINE_V2, 0xB2, 0x01, 0x00, 0x00, 0xB2, 0x01, 0x01, 0x01];
+    assert_eq!(
+        Shape::decode(&mut &bytes[..]).unwrap(),
+        Shape::Line { from: Point { x: 0, y: 0 }, to: Point { x: 1, y: 1 } });
+}
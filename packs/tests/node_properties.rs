@@ -0,0 +1,60 @@
+use packs::*;
+use packs::std_structs::Node;
+use std::collections::HashSet;
+
+node_properties! {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct PersonProps {
+        pub name: String,
+        pub age: i64,
+        #[rename = "nick"]
+        pub nickname: Option<String>,
+    }
+}
+
+fn person_node() -> Node {
+    let mut properties = Dictionary::new();
+    properties.add_property("name", "Jane Doe");
+    properties.add_property("age", 42);
+    properties.add_property("nick", Value::Null);
+
+    Node {
+        id: 1,
+        labels: vec!(String::from("Person")).into_iter().collect::<HashSet<_>>(),
+        properties,
+    }
+}
+
+#[test]
+fn read_node_properties() {
+    let mut buffer = Vec::new();
+    person_node().encode(&mut buffer).unwrap();
+    let node = Node::decode(&mut buffer.as_slice()).unwrap();
+
+    let mut person = PersonProps::new(node.properties);
+    assert_eq!(person.name().unwrap(), "Jane Doe");
+    assert_eq!(*person.age().unwrap(), 42);
+    assert_eq!(person.nickname().unwrap(), None);
+
+    person.set_nickname(Some(String::from("JD")));
+    person.set_age(43);
+    assert_eq!(person.nickname().unwrap().map(String::as_str), Some("JD"));
+    assert_eq!(*person.age().unwrap(), 43);
+
+    person.set_nickname(None);
+    assert!(!person.properties.has_property("nick"));
+    assert_eq!(person.into_properties().len(), 2);
+}
+
+#[test]
+fn missing_and_mistyped_properties() {
+    let mut properties = person_node().properties;
+    properties.extract_property("name");
+    properties.add_property("age", "forty-two");
+    properties.add_property("nick", 1);
+
+    let person = PersonProps::from(properties);
+    assert!(matches!(person.name(), Err(DecodeError::MissingProperty(key)) if key == "name"));
+    assert!(matches!(person.age(), Err(DecodeError::UnexpectedPropertyType(key)) if key == "age"));
+    assert!(matches!(person.nickname(), Err(DecodeError::UnexpectedPropertyType(key)) if key == "nick"));
+}
@@ -0,0 +1,33 @@
+use packs::*;
+
+#[derive(Unpack)]
+#[tag = 0x01]
+struct Defaults {
+    #[packs(default)]
+    first: i64,
+    second: i64,
+}
+
+#[derive(Pack)]
+#[tag = 0x02]
+struct Width {
+    #[packs(int_width = 3)]
+    value: i64,
+}
+
+#[derive(Unpack)]
+enum Duplicate {
+    #[tag = 0x01]
+    A(i64),
+    #[tag = 0x01]
+    B(bool),
+}
+
+#[derive(Unpack)]
+#[tag = 0x03]
+struct NotOptional {
+    #[packs(optional)]
+    email: String,
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use packs::*;
+
+#[derive(Pack)]
+#[tag]
+struct BareTag {
+    value: i64,
+}
+
+#[derive(Pack)]
+#[tag = 0x01]
+struct BareFields {
+    #[fields]
+    value: i64,
+}
+
+#[derive(Pack)]
+#[tag = 0x02]
+struct BarePack {
+    #[pack]
+    value: i64,
+}
+
+#[derive(PackString)]
+enum BareRename {
+    #[rename("a")]
+    A,
+}
+
+fn main() {}
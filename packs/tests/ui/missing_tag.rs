@@ -0,0 +1,8 @@
+use packs::*;
+
+#[derive(Pack, Unpack)]
+struct Untagged {
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use packs::*;
+
+#[derive(Pack)]
+#[tag = 0x01]
+struct Wide {
+    f0: i64, f1: i64, f2: i64, f3: i64, f4: i64, f5: i64, f6: i64, f7: i64,
+    f8: i64, f9: i64, f10: i64, f11: i64, f12: i64, f13: i64, f14: i64, f15: i64,
+}
+
+fn main() {}
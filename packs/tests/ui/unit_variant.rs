@@ -0,0 +1,11 @@
+use packs::*;
+
+#[derive(Pack)]
+enum Msg {
+    #[tag = 0x01]
+    Hello { agent: String },
+    #[tag = 0x02]
+    Goodbye,
+}
+
+fn main() {}